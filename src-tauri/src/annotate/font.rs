@@ -0,0 +1,138 @@
+// Built-in bitmap font for text labels and step numbers. A fixed bitmap keeps
+// rendering identical on every machine, which system fonts would not.
+//
+// Each printable ASCII glyph (0x20..=0x7E) is 5 columns of 8 rows; bit 0 of a
+// column byte is the top row. Glyphs are drawn 6 columns apart (one blank column
+// of spacing) and lines 9 rows apart.
+
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 8;
+pub const ADVANCE: u32 = GLYPH_WIDTH + 1;
+pub const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 1;
+
+const FIRST: u8 = 0x20;
+const LAST: u8 = 0x7E;
+
+#[rustfmt::skip]
+const GLYPHS: [[u8; 5]; (LAST - FIRST + 1) as usize] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x56, 0x20, 0x50], // &
+    [0x00, 0x08, 0x07, 0x03, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x2A, 0x1C, 0x7F, 0x1C, 0x2A], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x80, 0x70, 0x30, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x00, 0x60, 0x60, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x72, 0x49, 0x49, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x49, 0x4D, 0x33], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x31], // 6
+    [0x41, 0x21, 0x11, 0x09, 0x07], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x46, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x00, 0x14, 0x00, 0x00], // :
+    [0x00, 0x40, 0x34, 0x00, 0x00], // ;
+    [0x00, 0x08, 0x14, 0x22, 0x41], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x59, 0x09, 0x06], // ?
+    [0x3E, 0x41, 0x5D, 0x59, 0x4E], // @
+    [0x7C, 0x12, 0x11, 0x12, 0x7C], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x41, 0x3E], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x41, 0x51, 0x73], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x1C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x26, 0x49, 0x49, 0x49, 0x32], // S
+    [0x03, 0x01, 0x7F, 0x01, 0x03], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x59, 0x49, 0x4D, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x41], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x41, 0x7F], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x03, 0x07, 0x08, 0x00], // `
+    [0x20, 0x54, 0x54, 0x78, 0x40], // a
+    [0x7F, 0x28, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x28], // c
+    [0x38, 0x44, 0x44, 0x28, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x00, 0x08, 0x7E, 0x09, 0x02], // f
+    [0x18, 0xA4, 0xA4, 0x9C, 0x78], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x40, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x78, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0xFC, 0x18, 0x24, 0x24, 0x18], // p
+    [0x18, 0x24, 0x24, 0x18, 0xFC], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x24], // s
+    [0x04, 0x04, 0x3F, 0x44, 0x24], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x4C, 0x90, 0x90, 0x90, 0x7C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x77, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x02, 0x01, 0x02, 0x04, 0x02], // ~
+];
+
+/// Column bytes for `c`; characters outside printable ASCII show as '?'
+pub fn glyph(c: char) -> &'static [u8; 5] {
+    let code = if (FIRST as u32..=LAST as u32).contains(&(c as u32)) { c as u8 } else { b'?' };
+    &GLYPHS[(code - FIRST) as usize]
+}
+
+/// Whether the glyph for `c` covers column `x`, row `y`
+pub fn is_set(c: char, x: u32, y: u32) -> bool {
+    x < GLYPH_WIDTH && y < GLYPH_HEIGHT && glyph(c)[x as usize] & (1 << y) != 0
+}
+
+/// Integer magnification for a requested cap height, so glyph pixels stay square
+pub fn scale_for(size: f32) -> u32 {
+    ((size / GLYPH_HEIGHT as f32).round() as u32).max(1)
+}
+
+/// Unscaled size of `text` in pixels: widest line by line count
+pub fn measure(text: &str) -> (u32, u32) {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let columns = lines.iter().map(|line| line.chars().count() as u32).max().unwrap_or(0);
+    let width = (columns * ADVANCE).saturating_sub(1);
+    let height = (lines.len() as u32 * LINE_HEIGHT).saturating_sub(1);
+    (width, height)
+}
@@ -0,0 +1,6 @@
+pub mod font;
+pub mod model;
+pub mod render;
+
+pub use model::{Annotation, AnnotationDocument, Color, Point, Rect, RedactStyle};
+pub use render::render;
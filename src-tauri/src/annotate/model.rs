@@ -0,0 +1,242 @@
+// Annotation document: what was drawn on a capture, in the capture's pixel
+// coordinates. Saved as JSON next to the capture and flattened by render.rs.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const DOCUMENT_VERSION: u32 = 1;
+const MAX_ANNOTATIONS: usize = 1000;
+const MAX_TEXT_CHARS: usize = 500;
+
+/// RGBA color, written as "#rrggbb" or "#rrggbbaa" in JSON
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color(pub [u8; 4]);
+
+impl Color {
+    pub const RED: Color = Color([0xE5, 0x39, 0x35, 0xFF]);
+    pub const WHITE: Color = Color([0xFF, 0xFF, 0xFF, 0xFF]);
+    pub const BLACK: Color = Color([0x00, 0x00, 0x00, 0xFF]);
+    pub const HIGHLIGHT: Color = Color([0xFF, 0xEB, 0x3B, 0x66]); // Marker yellow, see-through
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, String> {
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Invalid color '{}', expected #rrggbb or #rrggbbaa", value));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap_or(0);
+        let alpha = if hex.len() == 8 { channel(3) } else { 0xFF };
+        Ok(Color([channel(0), channel(1), channel(2), alpha]))
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> String {
+        color.to_string()
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.0;
+        if a == 0xFF {
+            write!(f, "#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            write!(f, "#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// Whole pixels the rectangle touches, clipped to a `width` x `height` image
+    pub fn pixel_bounds(&self, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        let x1 = self.x.floor().max(0.0) as u32;
+        let y1 = self.y.floor().max(0.0) as u32;
+        let x2 = ((self.x + self.width).ceil().max(0.0) as u32).min(width);
+        let y2 = ((self.y + self.height).ceil().max(0.0) as u32).min(height);
+        (x2 > x1 && y2 > y1).then_some((x1, y1, x2, y2))
+    }
+}
+
+/// How a redaction hides what is under it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "style", rename_all = "snake_case")]
+pub enum RedactStyle {
+    /// Blocks of the average color; blocks smaller than 4px would leave text legible
+    Pixelate { block_size: u32 },
+    /// Repeated box blur, strong enough that text can't be read back
+    Blur { radius: u32 },
+    /// Solid color
+    Fill { color: Color },
+}
+
+pub const MIN_PIXELATE_BLOCK: u32 = 4;
+pub const MIN_BLUR_RADIUS: u32 = 4;
+
+fn default_stroke_width() -> f32 {
+    4.0
+}
+
+fn default_color() -> Color {
+    Color::RED
+}
+
+fn default_highlight() -> Color {
+    Color::HIGHLIGHT
+}
+
+fn default_text_size() -> f32 {
+    16.0
+}
+
+fn default_step_radius() -> f32 {
+    14.0
+}
+
+fn default_redact_style() -> RedactStyle {
+    RedactStyle::Pixelate { block_size: 12 }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    Rectangle {
+        rect: Rect,
+        #[serde(default = "default_color")]
+        color: Color,
+        #[serde(default = "default_stroke_width")]
+        stroke_width: f32,
+        #[serde(default)]
+        fill: Option<Color>,
+    },
+    /// Line from `from` with a head at `to`
+    Arrow {
+        from: Point,
+        to: Point,
+        #[serde(default = "default_color")]
+        color: Color,
+        #[serde(default = "default_stroke_width")]
+        stroke_width: f32,
+    },
+    /// Label with its top-left corner at `position`; `size` is the cap height in pixels
+    Text {
+        position: Point,
+        text: String,
+        #[serde(default = "default_color")]
+        color: Color,
+        #[serde(default = "default_text_size")]
+        size: f32,
+        #[serde(default)]
+        background: Option<Color>,
+    },
+    /// See-through marker over the rectangle
+    Highlight {
+        rect: Rect,
+        #[serde(default = "default_highlight")]
+        color: Color,
+    },
+    Redact {
+        rect: Rect,
+        #[serde(flatten, default = "default_redact_style")]
+        style: RedactStyle,
+    },
+    /// Numbered circle for step-by-step instructions
+    Step {
+        center: Point,
+        number: u32,
+        #[serde(default = "default_color")]
+        color: Color,
+        #[serde(default = "default_step_radius")]
+        radius: f32,
+    },
+}
+
+impl Annotation {
+    fn validate(&self) -> Result<(), String> {
+        let finite = |values: &[f32]| values.iter().all(|value| value.is_finite());
+        let ok = match self {
+            Annotation::Rectangle { rect, stroke_width, .. } =>
+                finite(&[rect.x, rect.y, rect.width, rect.height, *stroke_width]) && *stroke_width >= 0.0,
+            Annotation::Arrow { from, to, stroke_width, .. } =>
+                finite(&[from.x, from.y, to.x, to.y, *stroke_width]) && *stroke_width > 0.0,
+            Annotation::Text { position, text, size, .. } => {
+                if text.chars().count() > MAX_TEXT_CHARS {
+                    return Err(format!("Text labels are limited to {} characters", MAX_TEXT_CHARS));
+                }
+                finite(&[position.x, position.y, *size]) && *size > 0.0
+            },
+            Annotation::Highlight { rect, .. } => finite(&[rect.x, rect.y, rect.width, rect.height]),
+            Annotation::Redact { rect, style } => {
+                match style {
+                    RedactStyle::Pixelate { block_size } if *block_size < MIN_PIXELATE_BLOCK =>
+                        return Err(format!("Pixelate blocks must be at least {}px", MIN_PIXELATE_BLOCK)),
+                    RedactStyle::Blur { radius } if *radius < MIN_BLUR_RADIUS =>
+                        return Err(format!("Blur radius must be at least {}px", MIN_BLUR_RADIUS)),
+                    _ => {},
+                }
+                finite(&[rect.x, rect.y, rect.width, rect.height])
+            },
+            Annotation::Step { center, radius, .. } => finite(&[center.x, center.y, *radius]) && *radius > 0.0,
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("Invalid annotation {:?}", self))
+        }
+    }
+}
+
+/// Everything drawn on one capture
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationDocument {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    // Size of the capture the coordinates refer to
+    pub width: u32,
+    pub height: u32,
+    pub annotations: Vec<Annotation>, // Drawn in order, later on top
+}
+
+fn default_version() -> u32 {
+    DOCUMENT_VERSION
+}
+
+impl AnnotationDocument {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { version: DOCUMENT_VERSION, width, height, annotations: Vec::new() }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version > DOCUMENT_VERSION {
+            return Err(format!("Annotations were saved by a newer version (format {})", self.version));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err("Annotated capture has no size".to_string());
+        }
+        if self.annotations.len() > MAX_ANNOTATIONS {
+            return Err(format!("At most {} annotations per capture", MAX_ANNOTATIONS));
+        }
+        self.annotations.iter().try_for_each(Annotation::validate)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+}
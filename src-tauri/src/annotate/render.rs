@@ -0,0 +1,340 @@
+// Flattening annotations onto a capture for export.
+//
+// Rendering has to give the same pixels on every machine so golden images can be
+// compared exactly: shapes are distance fields sampled at pixel centers with only
+// +, *, / and sqrt (no trig, whose precision varies by platform), text comes from
+// the built-in bitmap font, and blending and redaction use integer math.
+use image::{Rgba, RgbaImage};
+use super::font;
+use super::model::{Annotation, AnnotationDocument, Color, Point, Rect, RedactStyle, MIN_BLUR_RADIUS, MIN_PIXELATE_BLOCK};
+
+// Blur is this many box blurs in a row, which comes close to a gaussian
+const BLUR_PASSES: usize = 3;
+
+/// `image` with every annotation drawn on it, in order. Coordinates are scaled if
+/// the document was made for a different size (e.g. before a downscale).
+pub fn render(image: &RgbaImage, document: &AnnotationDocument) -> Result<RgbaImage, String> {
+    document.validate()?;
+    let scale = Scale {
+        x: image.width() as f32 / document.width as f32,
+        y: image.height() as f32 / document.height as f32,
+    };
+    let mut output = image.clone();
+    for annotation in &document.annotations {
+        draw(&mut output, annotation, &scale);
+    }
+    Ok(output)
+}
+
+struct Scale {
+    x: f32,
+    y: f32,
+}
+
+impl Scale {
+    fn point(&self, point: &Point) -> Point {
+        Point { x: point.x * self.x, y: point.y * self.y }
+    }
+
+    // Rectangles dragged up or left arrive with a negative size
+    fn rect(&self, rect: &Rect) -> Rect {
+        let (x1, x2) = (rect.x * self.x, (rect.x + rect.width) * self.x);
+        let (y1, y2) = (rect.y * self.y, (rect.y + rect.height) * self.y);
+        Rect { x: x1.min(x2), y: y1.min(y2), width: (x2 - x1).abs(), height: (y2 - y1).abs() }
+    }
+
+    fn length(&self, length: f32) -> f32 {
+        length * (self.x + self.y) / 2.0
+    }
+}
+
+fn draw(image: &mut RgbaImage, annotation: &Annotation, scale: &Scale) {
+    match annotation {
+        Annotation::Rectangle { rect, color, stroke_width, fill } => {
+            let rect = scale.rect(rect);
+            let half = scale.length(*stroke_width) / 2.0;
+            if let Some(fill) = fill {
+                fill_shape(image, expand(&rect, 0.0), *fill, |p| box_distance(p, &rect));
+            }
+            if half > 0.0 {
+                fill_shape(image, expand(&rect, half), *color, |p| box_distance(p, &rect).abs() - half);
+            }
+        },
+        Annotation::Arrow { from, to, color, stroke_width } =>
+            draw_arrow(image, scale.point(from), scale.point(to), *color, scale.length(*stroke_width)),
+        Annotation::Text { position, text, color, size, background } => {
+            let position = scale.point(position);
+            let pixel_size = font::scale_for(scale.length(*size)) as i64;
+            let (width, height) = font::measure(text);
+            let (x, y) = (position.x.round() as i64, position.y.round() as i64);
+            if let Some(background) = background {
+                let pad = 2 * pixel_size;
+                fill_pixels(image, (x - pad, y - pad, x + width as i64 * pixel_size + pad, y + height as i64 * pixel_size + pad), *background);
+            }
+            draw_text(image, text, x, y, pixel_size, *color);
+        },
+        Annotation::Highlight { rect, color } => {
+            let rect = scale.rect(rect);
+            fill_shape(image, expand(&rect, 0.0), *color, |p| box_distance(p, &rect));
+        },
+        Annotation::Redact { rect, style } => {
+            let Some(bounds) = scale.rect(rect).pixel_bounds(image.width(), image.height()) else {
+                return;
+            };
+            match style {
+                RedactStyle::Pixelate { block_size } => {
+                    let block = (scale.length(*block_size as f32).round() as u32).max(MIN_PIXELATE_BLOCK);
+                    pixelate(image, bounds, block);
+                },
+                RedactStyle::Blur { radius } => {
+                    let radius = (scale.length(*radius as f32).round() as u32).max(MIN_BLUR_RADIUS);
+                    blur(image, bounds, radius as usize);
+                },
+                // Always opaque: a see-through redaction would not hide anything
+                RedactStyle::Fill { color } => {
+                    let (x1, y1, x2, y2) = bounds;
+                    fill_pixels(image, (x1 as i64, y1 as i64, x2 as i64, y2 as i64), Color([color.0[0], color.0[1], color.0[2], 0xFF]));
+                },
+            }
+        },
+        Annotation::Step { center, number, color, radius } =>
+            draw_step(image, scale.point(center), *number, *color, scale.length(*radius)),
+    }
+}
+
+fn draw_arrow(image: &mut RgbaImage, from: Point, to: Point, color: Color, stroke_width: f32) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length < 0.5 {
+        return; // No direction to point in
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    let head_length = (stroke_width * 3.0).max(10.0).min(length);
+    let head_half_width = head_length * 0.6;
+    let base = Point { x: to.x - ux * head_length, y: to.y - uy * head_length };
+    let head = [
+        to,
+        Point { x: base.x - uy * head_half_width, y: base.y + ux * head_half_width },
+        Point { x: base.x + uy * head_half_width, y: base.y - ux * head_half_width },
+    ];
+    // The shaft runs into the head so no seam shows between them
+    let shaft_end = Point { x: base.x + ux * head_length * 0.5, y: base.y + uy * head_length * 0.5 };
+
+    let half = stroke_width / 2.0;
+    let points = [from, head[0], head[1], head[2]];
+    let bounds = (
+        points.iter().map(|p| p.x).fold(f32::MAX, f32::min) - half,
+        points.iter().map(|p| p.y).fold(f32::MAX, f32::min) - half,
+        points.iter().map(|p| p.x).fold(f32::MIN, f32::max) + half,
+        points.iter().map(|p| p.y).fold(f32::MIN, f32::max) + half,
+    );
+    // One shape, so the overlap of shaft and head isn't blended twice
+    fill_shape(image, bounds, color, |p| {
+        (segment_distance(p, from, shaft_end) - half).min(triangle_distance(p, &head))
+    });
+}
+
+fn draw_step(image: &mut RgbaImage, center: Point, number: u32, color: Color, radius: f32) {
+    let bounds = (center.x - radius, center.y - radius, center.x + radius, center.y + radius);
+    fill_shape(image, bounds, color, |p| {
+        let (dx, dy) = (p.x - center.x, p.y - center.y);
+        (dx * dx + dy * dy).sqrt() - radius
+    });
+
+    // Digits only use the top 7 rows of the font; shrink long numbers to fit the circle
+    let label = number.to_string();
+    let (width, _) = font::measure(&label);
+    let mut pixel_size = font::scale_for(radius);
+    while pixel_size > 1 && (width * pixel_size) as f32 > radius * 1.5 {
+        pixel_size -= 1;
+    }
+    let x = (center.x - (width * pixel_size) as f32 / 2.0).round() as i64;
+    let y = (center.y - (7 * pixel_size) as f32 / 2.0).round() as i64;
+    let [r, g, b, _] = color.0;
+    let luma = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+    let text_color = if luma > 160 { Color::BLACK } else { Color::WHITE };
+    draw_text(image, &label, x, y, pixel_size as i64, text_color);
+}
+
+/// Bitmap text with its top-left corner at (x, y), each font pixel `pixel_size` wide
+fn draw_text(image: &mut RgbaImage, text: &str, x: i64, y: i64, pixel_size: i64, color: Color) {
+    for (row, line) in text.split('\n').enumerate() {
+        let line_y = y + (row as u32 * font::LINE_HEIGHT) as i64 * pixel_size;
+        for (column, c) in line.chars().enumerate() {
+            let glyph_x = x + (column as u32 * font::ADVANCE) as i64 * pixel_size;
+            for gx in 0..font::GLYPH_WIDTH {
+                for gy in 0..font::GLYPH_HEIGHT {
+                    if font::is_set(c, gx, gy) {
+                        let (px, py) = (glyph_x + gx as i64 * pixel_size, line_y + gy as i64 * pixel_size);
+                        fill_pixels(image, (px, py, px + pixel_size, py + pixel_size), color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Blend `color` over the pixels within `bounds` (x1, y1, x2, y2) by how much of
+/// each is inside the shape: `distance` is negative inside, positive outside
+fn fill_shape(image: &mut RgbaImage, bounds: (f32, f32, f32, f32), color: Color, distance: impl Fn(Point) -> f32) {
+    let (x1, y1, x2, y2) = bounds;
+    let x_range = (x1.floor().max(0.0) as u32)..(x2.ceil().max(0.0) as u32).min(image.width());
+    let y_range = (y1.floor().max(0.0) as u32)..(y2.ceil().max(0.0) as u32).min(image.height());
+    for y in y_range {
+        for x in x_range.clone() {
+            let coverage = (0.5 - distance(Point { x: x as f32 + 0.5, y: y as f32 + 0.5 })).clamp(0.0, 1.0);
+            let alpha = (coverage * color.0[3] as f32).round() as u8;
+            blend(image.get_pixel_mut(x, y), color, alpha);
+        }
+    }
+}
+
+/// Blend `color` over whole pixels, clipped to the image
+fn fill_pixels(image: &mut RgbaImage, bounds: (i64, i64, i64, i64), color: Color) {
+    let (x1, y1, x2, y2) = bounds;
+    let (x1, x2) = (x1.max(0), x2.min(image.width() as i64));
+    let (y1, y2) = (y1.max(0), y2.min(image.height() as i64));
+    for y in y1..y2 {
+        for x in x1..x2 {
+            blend(image.get_pixel_mut(x as u32, y as u32), color, color.0[3]);
+        }
+    }
+}
+
+fn blend(pixel: &mut Rgba<u8>, color: Color, alpha: u8) {
+    if alpha == 0 {
+        return;
+    }
+    let a = alpha as u32;
+    for channel in 0..3 {
+        pixel[channel] = ((color.0[channel] as u32 * a + pixel[channel] as u32 * (255 - a) + 127) / 255) as u8;
+    }
+    pixel[3] = (a + (pixel[3] as u32 * (255 - a) + 127) / 255).min(255) as u8;
+}
+
+fn expand(rect: &Rect, by: f32) -> (f32, f32, f32, f32) {
+    (rect.x - by, rect.y - by, rect.x + rect.width + by, rect.y + rect.height + by)
+}
+
+// Signed distance to the rectangle's edge
+fn box_distance(p: Point, rect: &Rect) -> f32 {
+    let (half_width, half_height) = (rect.width / 2.0, rect.height / 2.0);
+    let dx = (p.x - (rect.x + half_width)).abs() - half_width;
+    let dy = (p.y - (rect.y + half_height)).abs() - half_height;
+    let (outside_x, outside_y) = (dx.max(0.0), dy.max(0.0));
+    (outside_x * outside_x + outside_y * outside_y).sqrt() + dx.max(dy).min(0.0)
+}
+
+fn segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (abx, aby) = (b.x - a.x, b.y - a.y);
+    let (apx, apy) = (p.x - a.x, p.y - a.y);
+    let length_squared = abx * abx + aby * aby;
+    let t = if length_squared > 0.0 { ((apx * abx + apy * aby) / length_squared).clamp(0.0, 1.0) } else { 0.0 };
+    let (dx, dy) = (apx - abx * t, apy - aby * t);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Largest distance outside any edge: exact along the edges, slightly short of the
+// corners, which only affects their anti-aliasing
+fn triangle_distance(p: Point, triangle: &[Point; 3]) -> f32 {
+    let centroid = Point {
+        x: (triangle[0].x + triangle[1].x + triangle[2].x) / 3.0,
+        y: (triangle[0].y + triangle[1].y + triangle[2].y) / 3.0,
+    };
+    (0..3).map(|i| {
+        let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+        let (ex, ey) = (b.x - a.x, b.y - a.y);
+        let length = (ex * ex + ey * ey).sqrt().max(f32::EPSILON);
+        let (nx, ny) = (ey / length, -ex / length);
+        let side = |q: Point| (q.x - a.x) * nx + (q.y - a.y) * ny;
+        // Normals point away from the centroid
+        if side(centroid) > 0.0 { -side(p) } else { side(p) }
+    }).fold(f32::MIN, f32::max)
+}
+
+/// Replace each `block` x `block` square (from the region's corner) with its average
+fn pixelate(image: &mut RgbaImage, bounds: (u32, u32, u32, u32), block: u32) {
+    let (x1, y1, x2, y2) = bounds;
+    for block_y in (y1..y2).step_by(block as usize) {
+        for block_x in (x1..x2).step_by(block as usize) {
+            let (end_x, end_y) = ((block_x + block).min(x2), (block_y + block).min(y2));
+            let count = (end_x - block_x) * (end_y - block_y);
+            let mut sums = [0u32; 4];
+            for y in block_y..end_y {
+                for x in block_x..end_x {
+                    let pixel = image.get_pixel(x, y);
+                    for channel in 0..4 {
+                        sums[channel] += pixel[channel] as u32;
+                    }
+                }
+            }
+            let average = Rgba(sums.map(|sum| ((sum + count / 2) / count) as u8));
+            for y in block_y..end_y {
+                for x in block_x..end_x {
+                    image.put_pixel(x, y, average);
+                }
+            }
+        }
+    }
+}
+
+/// Box blur repeated within the region; only pixels inside it are read, so
+/// nothing from outside bleeds in and the edges stay sharp
+fn blur(image: &mut RgbaImage, bounds: (u32, u32, u32, u32), radius: usize) {
+    let (x1, y1, x2, y2) = bounds;
+    let (width, height) = ((x2 - x1) as usize, (y2 - y1) as usize);
+    let mut pixels: Vec<[u32; 4]> = Vec::with_capacity(width * height);
+    for y in y1..y2 {
+        for x in x1..x2 {
+            pixels.push(image.get_pixel(x, y).0.map(u32::from));
+        }
+    }
+
+    for _ in 0..BLUR_PASSES {
+        blur_rows(&mut pixels, width, radius);
+    }
+    let mut columns = transpose(&pixels, width, height);
+    for _ in 0..BLUR_PASSES {
+        blur_rows(&mut columns, height, radius);
+    }
+    let pixels = transpose(&columns, height, width);
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        let (x, y) = (x1 + (i % width) as u32, y1 + (i / width) as u32);
+        image.put_pixel(x, y, Rgba(pixel.map(|channel| channel as u8)));
+    }
+}
+
+// Running-sum box blur of every row; the edge pixel repeats past the ends
+fn blur_rows(pixels: &mut [[u32; 4]], width: usize, radius: usize) {
+    let window = (2 * radius + 1) as u32;
+    let mut line = vec![[0u32; 4]; width];
+    for row in pixels.chunks_mut(width) {
+        line.copy_from_slice(row);
+        let at = |i: isize| line[i.clamp(0, width as isize - 1) as usize];
+        let mut sums = [0u32; 4];
+        for i in -(radius as isize)..=radius as isize {
+            let pixel = at(i);
+            for channel in 0..4 {
+                sums[channel] += pixel[channel];
+            }
+        }
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = sums.map(|sum| (sum + window / 2) / window);
+            let (entering, leaving) = (at((x + radius + 1) as isize), at(x as isize - radius as isize));
+            for channel in 0..4 {
+                sums[channel] = sums[channel] + entering[channel] - leaving[channel];
+            }
+        }
+    }
+}
+
+fn transpose(pixels: &[[u32; 4]], width: usize, height: usize) -> Vec<[u32; 4]> {
+    let mut transposed = vec![[0u32; 4]; pixels.len()];
+    for y in 0..height {
+        for x in 0..width {
+            transposed[x * height + y] = pixels[y * width + x];
+        }
+    }
+    transposed
+}
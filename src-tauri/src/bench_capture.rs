@@ -0,0 +1,120 @@
+// Capture latency benchmark: the uncached path (capture_screen_area) against
+// ScreenshotCache (capture_screen_area_optimized) on the same region
+use crate::overlay::{CaptureBounds, CaptureError, CaptureService, CaptureSource, FixtureSource, OutputOptions, ScreenCapture, ScreenInfo, ScreenshotCache};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(Clone, Serialize, Debug)]
+pub struct Timing {
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    pub min_ms: f64,
+}
+
+#[derive(Clone, Serialize, Debug)]
+pub struct BenchmarkReport {
+    pub source: String,
+    pub bounds: CaptureBounds,
+    pub iterations: usize,
+    pub uncached: Timing, // Capture + default PNG encode every time
+    pub cold: Timing,     // Cache miss: capture, hash, fast encode
+    pub warm: Timing,     // Same selection again
+    pub jitter: Timing,   // Selection moved by a pixel, cropped from the cached frame
+    pub warm_gain_percent: f64,
+    pub jitter_gain_percent: f64,
+}
+
+/// 1920x1080 screen tiled from the UI fixture, for runs without a display
+pub fn fixture_source() -> Result<Arc<dyn CaptureSource>, String> {
+    let tile = image::load_from_memory(include_bytes!("../fixtures/content/ui_screenshot.png"))
+        .map_err(|e| format!("Failed to load benchmark fixture: {}", e))?
+        .to_rgba8();
+
+    let mut frame = image::RgbaImage::new(1920, 1080);
+    for y in (0..1080).step_by(tile.height() as usize) {
+        for x in (0..1920).step_by(tile.width() as usize) {
+            image::imageops::replace(&mut frame, &tile, x as i64, y as i64);
+        }
+    }
+    let screen = ScreenInfo { id: 1, x: 0, y: 0, width: 1920, height: 1080, scale_factor: 1.0, is_primary: true };
+    Ok(Arc::new(FixtureSource::new(vec![(screen, frame)])))
+}
+
+pub fn run_cache_benchmark(source: Arc<dyn CaptureSource>, iterations: usize) -> Result<BenchmarkReport, String> {
+    println!("\n⏱️ STARTING CAPTURE BENCHMARK ({})", source.name());
+    println!("=====================================");
+
+    let iterations = iterations.max(1);
+    let screens = source.screens()?;
+    let primary = ScreenCapture::find_primary(&screens).ok_or("No screens found")?;
+    // A typical selection: 800x600 in the middle of the primary screen
+    let bounds = CaptureBounds {
+        x: primary.x + (primary.width.saturating_sub(800) / 2) as i32,
+        y: primary.y + (primary.height.saturating_sub(600) / 2) as i32,
+        width: primary.width.min(800),
+        height: primary.height.min(600),
+    };
+    let nudged = CaptureBounds { x: bounds.x + 1, ..bounds.clone() };
+
+    let service = CaptureService::with_source(source.clone());
+    let uncached = measure("Uncached capture", iterations, || {
+        service.capture(&bounds, &OutputOptions::default()).map(|_| ())
+    })?;
+
+    // Measured as selections in one overlay, which may crop from a shared frame
+    let mut cache = ScreenshotCache::with_source(source.clone());
+    cache.begin_selection();
+    let cold = measure("Cache miss", iterations, || {
+        cache.clear_cache();
+        cache.capture_optimized(&bounds).map(|_| ())
+    })?;
+
+    cache.capture_optimized(&bounds)?;
+    let warm = measure("Cache hit", iterations, || {
+        cache.capture_optimized(&bounds).map(|_| ())
+    })?;
+
+    let mut flip = false;
+    let jitter = measure("Jittered selection", iterations, || {
+        flip = !flip;
+        let area = if flip { &nudged } else { &bounds };
+        cache.capture_optimized(area).map(|_| ())
+    })?;
+
+    let gain = |timing: &Timing| (1.0 - timing.median_ms / uncached.median_ms.max(f64::EPSILON)) * 100.0;
+    let report = BenchmarkReport {
+        source: source.name().to_string(),
+        bounds,
+        iterations,
+        warm_gain_percent: gain(&warm),
+        jitter_gain_percent: gain(&jitter),
+        uncached,
+        cold,
+        warm,
+        jitter,
+    };
+
+    println!("\n📊 Cached selections are {:.0}% faster, jittered ones {:.0}%",
+             report.warm_gain_percent, report.jitter_gain_percent);
+    println!("=====================================");
+    Ok(report)
+}
+
+fn measure(label: &str, iterations: usize, mut run: impl FnMut() -> Result<(), CaptureError>) -> Result<Timing, String> {
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let started = Instant::now();
+        run()?;
+        samples.push(started.elapsed().as_secs_f64() * 1000.0);
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+
+    let timing = Timing {
+        mean_ms: samples.iter().sum::<f64>() / samples.len() as f64,
+        median_ms: samples[samples.len() / 2],
+        min_ms: samples[0],
+    };
+    println!("⏱️ {}: median {:.2}ms, mean {:.2}ms, min {:.2}ms", label, timing.median_ms, timing.mean_ms, timing.min_ms);
+    Ok(timing)
+}
@@ -1,17 +1,19 @@
-// Standalone OCR test - simplified version for GitHub Actions
-// use tesseract::Tesseract; // Disabled for GitHub Actions
+// Standalone OCR test - reports whether this build links Tesseract
+// Run with: cargo run --bin test_ocr_standalone --features tesseract-ocr
 
 fn main() {
-    println!("\n🚀 SIMPLIFIED OCR TEST (Tesseract disabled for GitHub Actions)");
+    println!("\n🚀 STANDALONE OCR BUILD CHECK");
     println!("================================================================");
     
     println!("\n📋 OCR Service Status:");
-    println!("✅ OCR service available (simplified version)");
-    println!("⚠️ Tesseract temporarily disabled for GitHub Actions builds");
-    println!("💡 Full OCR functionality available in local builds");
+    if cfg!(feature = "tesseract-ocr") {
+        println!("✅ Tesseract engine compiled in (feature `tesseract-ocr`)");
+        println!("💡 Use the run_ocr_verification command to run the fixture tests");
+    } else {
+        println!("⚠️ Tesseract not compiled in - OCR calls will return an error");
+        println!("💡 Rebuild with --features tesseract-ocr for text recognition");
+    }
     
-    println!("\n🎉 SIMPLIFIED TEST COMPLETE!");
-    println!("✅ FrameSense can build without OCR dependencies");
-    println!("✅ Ready for GitHub Actions deployment");
+    println!("\n🎉 BUILD CHECK COMPLETE!");
     println!("================================================================");
-} 
\ No newline at end of file
+}
@@ -0,0 +1,4 @@
+pub mod search;
+pub mod store;
+
+pub use store::{HistoryEntry, HistoryHit, HistoryStats, HistoryStore, HistoryUpdate, NewHistoryEntry, OpenedHistoryEntry, RetentionPolicy};
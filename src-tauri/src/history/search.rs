@@ -0,0 +1,124 @@
+// Full-text index over history entries (OCR text, AI answers and tags)
+// Terms are kept sorted so the last query word can match as a prefix while typing.
+use std::collections::{BTreeMap, HashMap};
+
+const SNIPPET_CHARS: usize = 120;
+
+/// Lowercased words; single characters are dropped unless they are digits (OCR noise)
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1 || word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+    postings: BTreeMap<String, HashMap<String, u32>>, // term → entry id → occurrences
+    entry_terms: HashMap<String, Vec<String>>,        // entry id → its distinct terms
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index (or re-index) an entry's text
+    pub fn insert(&mut self, id: &str, text: &str) {
+        self.remove(id);
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for term in tokenize(text) {
+            *counts.entry(term).or_insert(0) += 1;
+        }
+        let terms: Vec<String> = counts.keys().cloned().collect();
+        for (term, count) in counts {
+            self.postings.entry(term).or_default().insert(id.to_string(), count);
+        }
+        self.entry_terms.insert(id.to_string(), terms);
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        let Some(terms) = self.entry_terms.remove(id) else {
+            return;
+        };
+        for term in terms {
+            if let Some(entries) = self.postings.get_mut(&term) {
+                entries.remove(id);
+                if entries.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Ids of entries containing every query word (the last one as a prefix), with a
+    /// tf-idf score. An empty query matches nothing.
+    pub fn search(&self, query: &str) -> HashMap<String, f32> {
+        let words = tokenize(query);
+        let Some((last, rest)) = words.split_last() else {
+            return HashMap::new();
+        };
+
+        let mut scores: Option<HashMap<String, f32>> = None;
+        for (word, prefix) in rest.iter().map(|w| (w, false)).chain(std::iter::once((last, true))) {
+            let matches = self.term_scores(word, prefix);
+            scores = Some(match scores {
+                None => matches,
+                Some(previous) => previous.into_iter()
+                    .filter_map(|(id, score)| matches.get(&id).map(|extra| (id, score + extra)))
+                    .collect(),
+            });
+        }
+        scores.unwrap_or_default()
+    }
+
+    fn term_scores(&self, word: &str, prefix: bool) -> HashMap<String, f32> {
+        let total = self.entry_terms.len().max(1) as f32;
+        let terms: Vec<(&String, &HashMap<String, u32>)> = if prefix {
+            self.postings.range(word.to_string()..)
+                .take_while(|(term, _)| term.starts_with(word))
+                .collect()
+        } else {
+            self.postings.get_key_value(word).into_iter().collect()
+        };
+
+        let mut scores = HashMap::new();
+        for (term, entries) in terms {
+            let idf = (1.0 + total / entries.len() as f32).ln();
+            // Exact words rank above words that only share the prefix
+            let weight = if term == word { 1.0 } else { 0.5 };
+            for (id, &count) in entries {
+                *scores.entry(id.clone()).or_insert(0.0) += (1.0 + count as f32).ln() * idf * weight;
+            }
+        }
+        scores
+    }
+}
+
+/// Short excerpt of `text` around the first query word it contains
+pub fn snippet(text: &str, query: &str) -> String {
+    let lower = text.to_lowercase();
+    let start = tokenize(query).iter()
+        .filter_map(|word| lower.find(word.as_str()))
+        .min()
+        .unwrap_or(0);
+
+    // Lowercasing can change byte offsets; fall back to the start if it did
+    let start = if lower.len() == text.len() && text.is_char_boundary(start) { start } else { 0 };
+    let chars_before = text[..start].chars().count();
+    let from = chars_before.saturating_sub(SNIPPET_CHARS / 4);
+
+    let excerpt: String = text.chars().skip(from).take(SNIPPET_CHARS).collect();
+    let excerpt = excerpt.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut result = String::new();
+    if from > 0 {
+        result.push('…');
+    }
+    result.push_str(&excerpt);
+    if from + SNIPPET_CHARS < text.chars().count() {
+        result.push('…');
+    }
+    result
+}
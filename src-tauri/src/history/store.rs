@@ -0,0 +1,648 @@
+// Capture history on disk
+// <dir>/index.json holds entry metadata and the retention policy, <dir>/images/<id>.png
+// the captures and <dir>/images/<id>.json what was drawn on them. The search index
+// is rebuilt from index.json on open; without a usable index the entries are
+// rebuilt from the images (their text and tags are only in the index).
+use crate::annotate::{self, AnnotationDocument};
+use crate::overlay::CaptureBounds;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::search::{self, SearchIndex};
+
+const INDEX_FILE: &str = "index.json";
+const IMAGES_DIR: &str = "images";
+const INDEX_VERSION: u32 = 1;
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub created_at: u64, // Unix milliseconds
+    pub bounds: Option<CaptureBounds>,
+    pub width: u32,
+    pub height: u32,
+    pub ocr_text: Option<String>,
+    pub ai_answer: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub image_file: String, // Relative to the history directory
+    pub image_bytes: u64,
+    #[serde(default)]
+    pub annotations_file: Option<String>, // Also relative; None until something is drawn
+}
+
+impl HistoryEntry {
+    // Everything full-text search looks at
+    fn searchable_text(&self) -> String {
+        [self.ocr_text.as_deref(), self.ai_answer.as_deref(), Some(&self.tags.join(" "))]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Oldest entries go first once any limit is exceeded; `None` disables a limit
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_entries: Option<usize>,
+    pub max_age_days: Option<u64>,
+    pub max_total_bytes: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(1000),
+            max_age_days: Some(30),
+            max_total_bytes: Some(1024 * 1024 * 1024), // 1GB of images
+        }
+    }
+}
+
+/// What a new history entry is made of; the image is a data URL or plain base64
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct NewHistoryEntry {
+    pub image_data: String,
+    pub bounds: Option<CaptureBounds>,
+    pub ocr_text: Option<String>,
+    pub ai_answer: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Fields to change on an existing entry; `None` leaves a field as it is
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct HistoryUpdate {
+    pub ocr_text: Option<String>,
+    pub ai_answer: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+pub struct HistoryHit {
+    pub entry: HistoryEntry,
+    pub score: f32,
+    pub snippet: String,
+}
+
+/// An entry with its image, as handed back to the UI
+#[derive(Clone, Serialize, Debug)]
+pub struct OpenedHistoryEntry {
+    pub entry: HistoryEntry,
+    pub image_data: String, // PNG data URL, without the annotations
+    pub annotations: Option<AnnotationDocument>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+pub struct HistoryStats {
+    pub entries: usize,
+    pub total_bytes: u64,
+    pub oldest: Option<u64>,
+    pub newest: Option<u64>,
+    pub retention: RetentionPolicy,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct IndexFile {
+    version: u32,
+    #[serde(default)]
+    retention: RetentionPolicy,
+    entries: Vec<HistoryEntry>,
+}
+
+pub struct HistoryStore {
+    dir: PathBuf,
+    entries: Vec<HistoryEntry>, // Newest first
+    index: SearchIndex,
+    retention: RetentionPolicy,
+    // Why index.json could not be read; nothing on disk is changed until it can
+    read_only: Option<String>,
+}
+
+impl HistoryStore {
+    /// Open (or start) the history kept in `dir`. A corrupt index is set aside as
+    /// index.json.bak rather than overwritten, and a missing or corrupt one is
+    /// rebuilt from the stored images. An index that can't be read (permissions,
+    /// I/O errors) leaves the history read-only, so it is never replaced.
+    pub fn open(dir: PathBuf) -> Self {
+        let index_path = dir.join(INDEX_FILE);
+        let mut read_only = None;
+        let (file, rebuild) = match fs::read_to_string(&index_path) {
+            Ok(json) => match serde_json::from_str::<IndexFile>(&json) {
+                Ok(file) => (file, false),
+                Err(e) => {
+                    println!("⚠️ Capture history index is corrupt ({}), rebuilding it from the images", e);
+                    let _ = fs::rename(&index_path, dir.join(format!("{}.bak", INDEX_FILE)));
+                    (IndexFile::default(), true)
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (IndexFile::default(), true),
+            Err(e) => {
+                println!("⚠️ Cannot read {} ({}), capture history is read-only", index_path.display(), e);
+                read_only = Some(format!("{} could not be read: {}", index_path.display(), e));
+                (IndexFile::default(), true)
+            },
+        };
+
+        let mut store = Self {
+            dir,
+            entries: Vec::new(),
+            index: SearchIndex::new(),
+            retention: file.retention,
+            read_only,
+        };
+        for entry in file.entries {
+            store.index.insert(&entry.id, &entry.searchable_text());
+            store.entries.push(entry);
+        }
+
+        let recovered = if rebuild { store.recover_entries() } else { 0 };
+        store.entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
+
+        if rebuild {
+            // Every image became an entry, so there is nothing orphaned to sweep, and
+            // retention waits until the policy is known again
+            if recovered > 0 {
+                println!("🩹 Recovered {} history entries from their images", recovered);
+                if store.read_only.is_none() {
+                    if let Err(e) = store.save_index() {
+                        println!("⚠️ Failed to save the rebuilt history index: {}", e);
+                    }
+                }
+            }
+        } else {
+            store.remove_orphaned_images();
+            match store.apply_retention() {
+                Ok(removed) if removed > 0 => println!("🗑️ Dropped {} expired history entries", removed),
+                Ok(_) => {},
+                Err(e) => println!("⚠️ Failed to apply history retention: {}", e),
+            }
+        }
+        println!("📚 Capture history: {} entries in {}", store.entries.len(), store.dir.display());
+        store
+    }
+
+    /// Whether changes can be saved (false when index.json could not be read)
+    pub fn is_writable(&self) -> bool {
+        self.read_only.is_none()
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Store a capture and index its text
+    pub fn add(&mut self, new_entry: NewHistoryEntry) -> Result<HistoryEntry, String> {
+        self.add_at(new_entry, now_millis())
+    }
+
+    pub fn add_at(&mut self, new_entry: NewHistoryEntry, created_at: u64) -> Result<HistoryEntry, String> {
+        self.check_writable()?;
+        let (png, width, height) = png_from_image_data(&new_entry.image_data)?;
+        self.insert(&png, width, height, new_entry, created_at)
+    }
+
+    /// Store an already encoded PNG capture as it is delivered; its text and answer
+    /// are attached with `update` once they are known
+    pub fn add_png(&mut self, png: &[u8], width: u32, height: u32, bounds: Option<CaptureBounds>) -> Result<HistoryEntry, String> {
+        self.check_writable()?;
+        if image::guess_format(png).ok() != Some(image::ImageFormat::Png) {
+            return Err("History captures must be PNG".to_string());
+        }
+        self.insert(png, width, height, NewHistoryEntry { bounds, ..NewHistoryEntry::default() }, now_millis())
+    }
+
+    fn insert(&mut self, png: &[u8], width: u32, height: u32, new_entry: NewHistoryEntry, created_at: u64) -> Result<HistoryEntry, String> {
+        let id = self.unique_id(created_at);
+        let image_file = format!("{}/{}.png", IMAGES_DIR, id);
+        let image_path = self.dir.join(&image_file);
+        fs::create_dir_all(self.dir.join(IMAGES_DIR))
+            .map_err(|e| format!("Failed to create history directory: {}", e))?;
+        fs::write(&image_path, png)
+            .map_err(|e| format!("Failed to write {}: {}", image_path.display(), e))?;
+
+        let entry = HistoryEntry {
+            id,
+            created_at,
+            bounds: new_entry.bounds,
+            width,
+            height,
+            ocr_text: new_entry.ocr_text.filter(|text| !text.trim().is_empty()),
+            ai_answer: new_entry.ai_answer.filter(|text| !text.trim().is_empty()),
+            tags: normalize_tags(new_entry.tags),
+            image_file,
+            image_bytes: png.len() as u64,
+            annotations_file: None,
+        };
+        self.index.insert(&entry.id, &entry.searchable_text());
+        let position = self.entries.iter().position(|e| e.created_at <= created_at).unwrap_or(self.entries.len());
+        self.entries.insert(position, entry.clone());
+
+        let removed = self.enforce_retention(now_millis().max(created_at))?;
+        if removed > 0 {
+            println!("🗑️ History retention dropped {} old entries", removed);
+        }
+        self.save_index()?;
+        println!("📚 Saved capture {} to history ({}x{}, {} bytes)", entry.id, width, height, entry.image_bytes);
+        Ok(entry)
+    }
+
+    /// Attach OCR text, an AI answer or tags to an entry after the fact
+    pub fn update(&mut self, id: &str, update: HistoryUpdate) -> Result<HistoryEntry, String> {
+        self.check_writable()?;
+        let entry = self.entries.iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| format!("History entry {} not found", id))?;
+
+        if let Some(text) = update.ocr_text {
+            entry.ocr_text = Some(text).filter(|text| !text.trim().is_empty());
+        }
+        if let Some(answer) = update.ai_answer {
+            entry.ai_answer = Some(answer).filter(|text| !text.trim().is_empty());
+        }
+        if let Some(tags) = update.tags {
+            entry.tags = normalize_tags(tags);
+        }
+        let entry = entry.clone();
+        self.index.insert(&entry.id, &entry.searchable_text());
+        self.save_index()?;
+        Ok(entry)
+    }
+
+    /// Entries newest first
+    pub fn list(&self, offset: usize, limit: usize) -> Vec<HistoryEntry> {
+        self.entries.iter().skip(offset).take(limit).cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Full-text search over OCR text, AI answers and tags. `tag:name` words only keep
+    /// entries carrying that tag; a query of only tags lists those entries newest first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<HistoryHit> {
+        let (tags, words): (Vec<&str>, Vec<&str>) = query.split_whitespace()
+            .partition(|word| word.to_lowercase().starts_with("tag:"));
+        let tags: Vec<String> = tags.iter()
+            .map(|tag| tag[4..].to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        let text_query = words.join(" ");
+        let has_tag = |entry: &HistoryEntry| tags.iter().all(|tag| entry.tags.contains(tag));
+
+        let mut hits: Vec<HistoryHit> = if search::tokenize(&text_query).is_empty() {
+            if tags.is_empty() {
+                return Vec::new();
+            }
+            self.entries.iter()
+                .filter(|entry| has_tag(entry))
+                .map(|entry| HistoryHit { entry: entry.clone(), score: 0.0, snippet: String::new() })
+                .collect()
+        } else {
+            let scores = self.index.search(&text_query);
+            self.entries.iter()
+                .filter(|entry| has_tag(entry))
+                .filter_map(|entry| scores.get(&entry.id).map(|&score| HistoryHit {
+                    snippet: search::snippet(&entry.searchable_text(), &text_query),
+                    entry: entry.clone(),
+                    score,
+                }))
+                .collect()
+        };
+
+        // Best match first, newer captures winning ties
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(b.entry.created_at.cmp(&a.entry.created_at)));
+        hits.truncate(limit);
+        hits
+    }
+
+    /// The entry with its image loaded
+    pub fn open_entry(&self, id: &str) -> Result<OpenedHistoryEntry, String> {
+        let entry = self.get(id).ok_or_else(|| format!("History entry {} not found", id))?;
+        let image_path = self.dir.join(&entry.image_file);
+        let png = fs::read(&image_path)
+            .map_err(|e| format!("Failed to read {}: {}", image_path.display(), e))?;
+        Ok(OpenedHistoryEntry {
+            entry: entry.clone(),
+            image_data: format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png)),
+            annotations: self.annotations(id)?,
+        })
+    }
+
+    /// What was drawn on an entry, if anything
+    pub fn annotations(&self, id: &str) -> Result<Option<AnnotationDocument>, String> {
+        let entry = self.get(id).ok_or_else(|| format!("History entry {} not found", id))?;
+        let Some(annotations_file) = &entry.annotations_file else {
+            return Ok(None);
+        };
+        let path = self.dir.join(annotations_file);
+        let json = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Save the annotations next to the entry's image, replacing any before; an
+    /// empty document removes them. The image itself is never changed.
+    pub fn save_annotations(&mut self, id: &str, document: &AnnotationDocument) -> Result<HistoryEntry, String> {
+        self.check_writable()?;
+        document.validate()?;
+        let position = self.entries.iter()
+            .position(|entry| entry.id == id)
+            .ok_or_else(|| format!("History entry {} not found", id))?;
+
+        let annotations_file = if document.is_empty() {
+            if let Some(old_file) = self.entries[position].annotations_file.take() {
+                remove_file(&self.dir.join(old_file));
+            }
+            None
+        } else {
+            let annotations_file = format!("{}/{}.json", IMAGES_DIR, id);
+            let json = serde_json::to_string_pretty(document)
+                .map_err(|e| format!("Failed to serialize annotations: {}", e))?;
+            let path = self.dir.join(&annotations_file);
+            let temp_path = self.dir.join(format!("{}.tmp", annotations_file));
+            fs::write(&temp_path, json)
+                .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+            fs::rename(&temp_path, &path)
+                .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+            Some(annotations_file)
+        };
+
+        self.entries[position].annotations_file = annotations_file;
+        self.save_index()?;
+        println!("✏️ Saved {} annotations for history entry {}", document.annotations.len(), id);
+        Ok(self.entries[position].clone())
+    }
+
+    /// The entry's image with its annotations flattened onto it, for export
+    pub fn render_annotated(&self, id: &str) -> Result<image::RgbaImage, String> {
+        let entry = self.get(id).ok_or_else(|| format!("History entry {} not found", id))?;
+        let image_path = self.dir.join(&entry.image_file);
+        let image = image::open(&image_path)
+            .map_err(|e| format!("Failed to read {}: {}", image_path.display(), e))?
+            .to_rgba8();
+        match self.annotations(id)? {
+            Some(document) => annotate::render(&image, &document),
+            None => Ok(image),
+        }
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<HistoryEntry, String> {
+        self.check_writable()?;
+        let position = self.entries.iter()
+            .position(|entry| entry.id == id)
+            .ok_or_else(|| format!("History entry {} not found", id))?;
+        let entry = self.remove_at(position);
+        self.save_index()?;
+        println!("🗑️ Deleted history entry {}", id);
+        Ok(entry)
+    }
+
+    /// Remove every entry and image
+    pub fn clear(&mut self) -> Result<usize, String> {
+        self.check_writable()?;
+        let count = self.entries.len();
+        while !self.entries.is_empty() {
+            self.remove_at(0);
+        }
+        self.save_index()?;
+        Ok(count)
+    }
+
+    pub fn retention(&self) -> &RetentionPolicy {
+        &self.retention
+    }
+
+    /// Change the limits and apply them right away; returns how many entries were dropped
+    pub fn set_retention(&mut self, retention: RetentionPolicy) -> Result<usize, String> {
+        self.check_writable()?;
+        self.retention = retention;
+        let removed = self.enforce_retention(now_millis())?;
+        self.save_index()?;
+        Ok(removed)
+    }
+
+    pub fn apply_retention(&mut self) -> Result<usize, String> {
+        self.apply_retention_at(now_millis())
+    }
+
+    /// Apply the retention policy as if it were `now` (Unix milliseconds)
+    pub fn apply_retention_at(&mut self, now: u64) -> Result<usize, String> {
+        self.check_writable()?;
+        let removed = self.enforce_retention(now)?;
+        if removed > 0 {
+            self.save_index()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn stats(&self) -> HistoryStats {
+        HistoryStats {
+            entries: self.entries.len(),
+            total_bytes: self.total_bytes(),
+            oldest: self.entries.last().map(|entry| entry.created_at),
+            newest: self.entries.first().map(|entry| entry.created_at),
+            retention: self.retention.clone(),
+        }
+    }
+
+    fn enforce_retention(&mut self, now: u64) -> Result<usize, String> {
+        let mut removed = 0;
+
+        if let Some(days) = self.retention.max_age_days {
+            let cutoff = now.saturating_sub(days.saturating_mul(DAY_MS));
+            while self.entries.last().is_some_and(|entry| entry.created_at < cutoff) {
+                self.remove_at(self.entries.len() - 1);
+                removed += 1;
+            }
+        }
+        if let Some(max_entries) = self.retention.max_entries {
+            while self.entries.len() > max_entries {
+                self.remove_at(self.entries.len() - 1);
+                removed += 1;
+            }
+        }
+        if let Some(max_bytes) = self.retention.max_total_bytes {
+            let mut total = self.total_bytes();
+            while total > max_bytes && !self.entries.is_empty() {
+                total -= self.remove_at(self.entries.len() - 1).image_bytes;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn remove_at(&mut self, position: usize) -> HistoryEntry {
+        let entry = self.entries.remove(position);
+        self.index.remove(&entry.id);
+        remove_file(&self.dir.join(&entry.image_file));
+        if let Some(annotations_file) = &entry.annotations_file {
+            remove_file(&self.dir.join(annotations_file));
+        }
+        entry
+    }
+
+    fn check_writable(&self) -> Result<(), String> {
+        match &self.read_only {
+            Some(reason) => Err(format!("Capture history is read-only: {}", reason)),
+            None => Ok(()),
+        }
+    }
+
+    // Entries for the images the index doesn't list. Ids carry the capture time
+    // (capture-<ms>[-n]); text, tags and bounds are lost with the index.
+    fn recover_entries(&mut self) -> usize {
+        let Ok(files) = fs::read_dir(self.dir.join(IMAGES_DIR)) else {
+            return 0;
+        };
+        let mut recovered = 0;
+        for file in files.flatten() {
+            let path = file.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("png") {
+                continue;
+            }
+            let Some(id) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else { continue };
+            if self.get(&id).is_some() {
+                continue;
+            }
+            let Ok((width, height)) = image::image_dimensions(&path) else {
+                println!("⚠️ Skipping unreadable history image {}", path.display());
+                continue;
+            };
+            let metadata = file.metadata().ok();
+            let created_at = id.strip_prefix("capture-")
+                .and_then(|rest| rest.split('-').next())
+                .and_then(|millis| millis.parse().ok())
+                .or_else(|| metadata.as_ref()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map(|age| age.as_millis() as u64))
+                .unwrap_or(0);
+            let annotations_file = format!("{}/{}.json", IMAGES_DIR, id);
+            let entry = HistoryEntry {
+                annotations_file: self.dir.join(&annotations_file).exists().then_some(annotations_file),
+                image_file: format!("{}/{}.png", IMAGES_DIR, id),
+                image_bytes: metadata.map(|metadata| metadata.len()).unwrap_or(0),
+                id,
+                created_at,
+                bounds: None,
+                width,
+                height,
+                ocr_text: None,
+                ai_answer: None,
+                tags: Vec::new(),
+            };
+            self.index.insert(&entry.id, &entry.searchable_text());
+            self.entries.push(entry);
+            recovered += 1;
+        }
+        recovered
+    }
+
+    // Images (and annotations) left behind by a crash between writing them and
+    // the index
+    fn remove_orphaned_images(&self) {
+        let Ok(files) = fs::read_dir(self.dir.join(IMAGES_DIR)) else {
+            return;
+        };
+        for file in files.flatten() {
+            let name = format!("{}/{}", IMAGES_DIR, file.file_name().to_string_lossy());
+            if !self.entries.iter().any(|entry| entry.image_file == name || entry.annotations_file.as_ref() == Some(&name)) {
+                let _ = fs::remove_file(file.path());
+            }
+        }
+    }
+
+    fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.image_bytes).sum()
+    }
+
+    fn unique_id(&self, created_at: u64) -> String {
+        let base = format!("capture-{}", created_at);
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while self.get(&id).is_some() {
+            id = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        id
+    }
+
+    // Write to a temporary file and rename, so a crash never leaves half an index
+    fn save_index(&self) -> Result<(), String> {
+        self.check_writable()?;
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create history directory: {}", e))?;
+        let file = IndexFile {
+            version: INDEX_VERSION,
+            retention: self.retention.clone(),
+            entries: self.entries.clone(),
+        };
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Failed to serialize history index: {}", e))?;
+
+        let index_path = self.dir.join(INDEX_FILE);
+        let temp_path = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&temp_path, json)
+            .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+        fs::rename(&temp_path, &index_path)
+            .map_err(|e| format!("Failed to replace {}: {}", index_path.display(), e))
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn remove_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            println!("⚠️ Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+// PNG bytes and dimensions of a data URL / base64 image; other formats are re-encoded
+fn png_from_image_data(image_data: &str) -> Result<(Vec<u8>, u32, u32), String> {
+    let encoded = image_data.split_once(',').map(|(_, data)| data).unwrap_or(image_data);
+    let bytes = base64::engine::general_purpose::STANDARD.decode(encoded.trim())
+        .map_err(|e| format!("Failed to decode image data: {}", e))?;
+
+    let format = image::guess_format(&bytes).map_err(|e| format!("Unknown image format: {}", e))?;
+    if format == image::ImageFormat::Png {
+        let (width, height) = image::io::Reader::with_format(Cursor::new(&bytes), format)
+            .into_dimensions()
+            .map_err(|e| format!("Failed to read image size: {}", e))?;
+        return Ok((bytes, width, height));
+    }
+
+    let image = image::load_from_memory_with_format(&bytes, format)
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok((png, image.width(), image.height()))
+}
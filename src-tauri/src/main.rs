@@ -3,41 +3,60 @@
 use tauri::{
     RunEvent, WindowEvent,
     tray::TrayIconBuilder,
-    menu::{Menu, MenuItem},
+    menu::{Menu, MenuItem, Submenu},
     Manager, Emitter, WebviewUrl, WebviewWindowBuilder,
 };
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
-use base64::Engine;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::fs;
 use std::path::PathBuf;
 
 // Import optimized overlay manager
 mod overlay;
-use overlay::{OverlayManager, ScreenshotCache};
+use overlay::{CaptureBounds, CaptureError, CaptureHandle, CaptureRegistry, CaptureResult, CaptureService, OutputOptions, OverlayManager, ScreenshotCache,
+              ScrollCapture, ScrollStatus, CaptureScheduler, ScheduleEvent, ScheduleInfo, ScheduleMode,
+              Recording, RecordingOptions, RecordingResult, RecordingState, RecordingStatus, ScreenRecorder,
+              FileContent, FileKind};
 
 // FAS 2: Import permission cache system
 mod system;
-use system::{PermissionCache, Permission};
+use system::{ClipboardContent, ClipboardService, PermissionCache, Permission};
 
 // OCR module for Tesseract integration
 mod ocr;
-use ocr::{OCRService, OCRResult};
+use ocr::{OCRService, OCRResult, LanguagePack, LanguageSelection, PreprocessOptions};
 
 // OCR test module
 mod test_ocr;
+// Capture pipeline test module
+mod test_capture;
+mod bench_capture;
+
+// Capture history module
+mod history;
+use history::{HistoryEntry, HistoryHit, HistoryStats, HistoryStore, HistoryUpdate, NewHistoryEntry, OpenedHistoryEntry, RetentionPolicy};
+// History test module
+mod test_history;
+
+// Annotations and redactions drawn on captures
+mod annotate;
+use annotate::AnnotationDocument;
+
+// Sensitive-data redaction before captures are uploaded
+mod privacy;
+use privacy::{PrivacyPolicy, PrivacySettings, PrivacyStore, RedactionReport};
+
+// Pinned regions watched for text changes
+mod watch;
+use watch::{NewRegionWatch, RegionWatch, RegionWatches, WatchEvent, WatchStatus, WatchStore};
 
 // Authentication module
 mod auth;
 // Using API approach - no direct database connection
 use auth::{AuthService, User};
 
-// Global OCR service (reuse instance for performance)
-static mut OCR_SERVICE: Option<std::sync::Mutex<OCRService>> = None;
-static OCR_INIT: std::sync::Once = std::sync::Once::new();
-
 // Note: macOS-specific imports removed since we're using native egui overlay
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -46,22 +65,6 @@ pub struct AppResult {
     pub message: String,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct CaptureBounds {
-    pub x: i32,
-    pub y: i32,
-    pub width: u32,
-    pub height: u32,
-}
-
-#[derive(Clone, Serialize, Deserialize)]
-pub struct CaptureResult {
-    pub success: bool,
-    pub message: String,
-    pub bounds: Option<CaptureBounds>,
-    pub image_data: Option<String>, // Base64 encoded image
-}
-
 // App state that persists between window creations (like Raycast)
 #[derive(Clone, Default, Serialize)]
 pub struct AppState {
@@ -81,140 +84,78 @@ type SharedPermissionCache = Arc<Mutex<PermissionCache>>;
 // FAS 3: Screenshot cache manager for optimization
 type SharedScreenshotCache = Arc<Mutex<ScreenshotCache>>;
 
+// Encoded captures the webview loads by handle (framesense://capture/<id>)
+type SharedCaptureRegistry = Arc<Mutex<CaptureRegistry>>;
+
+// Scrolling capture in progress (at most one)
+type SharedScrollCapture = Arc<Mutex<Option<ScrollCapture>>>;
+
+// Delayed, interval and change-triggered captures
+type SharedCaptureScheduler = Arc<Mutex<CaptureScheduler>>;
+
+// The screen recording in progress, if any
+type SharedRecorder = Arc<Mutex<Option<ScreenRecorder>>>;
+
+// Tray items that follow the recording state
+struct RecordingMenu {
+    pause: MenuItem<tauri::Wry>,
+    stop: MenuItem<tauri::Wry>,
+}
+
+// Tray items that finish or cancel a scrolling capture. The overlay lets the mouse
+// through while scrolling and loses keyboard focus, so its Enter/ESC can't be relied on.
+struct ScrollMenu {
+    finish: MenuItem<tauri::Wry>,
+    cancel: MenuItem<tauri::Wry>,
+}
+
+const TRAY_ID: &str = "framesense";
+
+// Capture history store (under the app data dir, managed in setup)
+type SharedHistory = Arc<Mutex<HistoryStore>>;
+
+// Privacy policies, loaded from disk in setup
+type SharedPrivacy = Arc<Mutex<PrivacyStore>>;
+
+// Pinned regions, loaded from disk in setup
+type SharedRegionWatches = Arc<Mutex<RegionWatches>>;
+
+// System clipboard, opened on first use
+type SharedClipboard = Arc<Mutex<ClipboardService>>;
+
 // Authentication service manager
 type SharedAuthService = Arc<Mutex<AuthService>>;
 
 // Test screen capture capability
 #[tauri::command]
-async fn test_screen_capture() -> Result<CaptureResult, String> {
+async fn test_screen_capture() -> Result<AppResult, CaptureError> {
     println!("🧪 Testing screen capture capability...");
     
-    match screenshots::Screen::all() {
-        Ok(screens) => {
-            if let Some(screen) = screens.first() {
-                println!("✅ Screen access working. Available: {} screen(s)", screens.len());
-                Ok(CaptureResult {
-                    success: true,
-                    message: format!("Screen capture test successful! Found {} screen(s)", screens.len()),
-                    bounds: None,
-                    image_data: None,
-                })
-            } else {
-                println!("❌ No screens available");
-                Ok(CaptureResult {
-                    success: false,
-                    message: "No screens available for capture".to_string(),
-                    bounds: None,
-                    image_data: None,
-                })
-            }
-        },
-        Err(e) => {
-            println!("❌ Screen capture test failed: {}", e);
-            Ok(CaptureResult {
-                success: false,
-                message: format!("Screen access failed: {}", e),
-                bounds: None,
-                image_data: None,
-            })
-        }
-    }
+    let screens = CaptureService::new().screens().inspect_err(|e| println!("❌ Screen capture test failed: {}", e))?;
+    println!("✅ Screen access working. Available: {} screen(s)", screens.len());
+    Ok(AppResult {
+        success: true,
+        message: format!("Screen capture test successful! Found {} screen(s)", screens.len()),
+    })
 }
 
-// Capture a specific area of the screen
+// Capture a specific area of the screen (global desktop coordinates, any monitor)
+// `output` picks the format (png, webp, jpeg, rgba), JPEG quality and a maximum edge length
 #[tauri::command]
-async fn capture_screen_area(bounds: CaptureBounds) -> Result<CaptureResult, String> {
+async fn capture_screen_area(bounds: CaptureBounds, output: Option<OutputOptions>) -> Result<CaptureResult, CaptureError> {
     println!("📸 Capturing screen area: {}x{} at ({}, {})", bounds.width, bounds.height, bounds.x, bounds.y);
     
-    match screenshots::Screen::all() {
-        Ok(screens) => {
-            if let Some(screen) = screens.first() {
-                let screen_width = screen.display_info.width;
-                let screen_height = screen.display_info.height;
-                
-                println!("📺 Screen dimensions: {}x{}", screen_width, screen_height);
-                
-                // 🔧 FIX: Validate and clamp coordinates to screen bounds
-                let safe_x = bounds.x.max(0).min((screen_width as i32) - (bounds.width as i32));
-                let safe_y = bounds.y.max(0).min((screen_height as i32) - (bounds.height as i32));
-                let safe_width = bounds.width.min((screen_width as u32) - (safe_x as u32));
-                let safe_height = bounds.height.min((screen_height as u32) - (safe_y as u32));
-                
-                println!("🔧 Adjusted coordinates: {}x{} at ({}, {}) → {}x{} at ({}, {})", 
-                         bounds.width, bounds.height, bounds.x, bounds.y,
-                         safe_width, safe_height, safe_x, safe_y);
-                
-                // Ensure minimum size
-                if safe_width < 10 || safe_height < 10 {
-                    println!("❌ Adjusted area too small: {}x{}", safe_width, safe_height);
-                    return Ok(CaptureResult {
-                        success: false,
-                        message: format!("Capture area too small after adjustment: {}x{}", safe_width, safe_height),
-                        bounds: None,
-                        image_data: None,
-                    });
-                }
-                
-                match screen.capture_area(safe_x, safe_y, safe_width, safe_height) {
-                    Ok(image) => {
-                        // Convert to PNG and then to base64
-                        match image.to_png(None) {
-                            Ok(png_data) => {
-                                let base64_data = base64::engine::general_purpose::STANDARD.encode(&png_data);
-                                let full_data = format!("data:image/png;base64,{}", base64_data);
-                                
-                                println!("✅ Screen capture successful! Size: {}KB", png_data.len() / 1024);
-                                Ok(CaptureResult {
-                                    success: true,
-                                    message: "Screen area captured successfully!".to_string(),
-                                    bounds: Some(CaptureBounds {
-                                        x: safe_x,
-                                        y: safe_y,
-                                        width: safe_width,
-                                        height: safe_height,
-                                    }),
-                                    image_data: Some(full_data),
-                                })
-                            },
-                            Err(e) => {
-                                println!("❌ PNG conversion failed: {}", e);
-                                Ok(CaptureResult {
-                                    success: false,
-                                    message: format!("PNG conversion failed: {}", e),
-                                    bounds: None,
-                                    image_data: None,
-                                })
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        println!("❌ Screen capture failed: {}", e);
-                        Ok(CaptureResult {
-                            success: false,
-                            message: format!("Screen capture failed: {}", e),
-                            bounds: None,
-                            image_data: None,
-                        })
-                    }
-                }
-            } else {
-                println!("❌ No screens available");
-                Ok(CaptureResult {
-                    success: false,
-                    message: "No screens available for capture".to_string(),
-                    bounds: None,
-                    image_data: None,
-                })
-            }
+    // Clips to the screens the area covers and stitches when it spans several
+    match CaptureService::new().capture(&bounds, &output.unwrap_or_default()) {
+        Ok(capture) => {
+            println!("✅ Screen capture successful! {}x{} at ({}, {}) as {} {}x{}, {}KB",
+                     capture.bounds.width, capture.bounds.height, capture.bounds.x, capture.bounds.y,
+                     capture.mime_type, capture.width, capture.height, capture.byte_size / 1024);
+            Ok(capture)
         },
         Err(e) => {
-            println!("❌ Failed to access screens: {}", e);
-            Ok(CaptureResult {
-                success: false,
-                message: format!("Failed to access screens: {}", e),
-                bounds: None,
-                image_data: None,
-            })
+            println!("❌ Screen capture failed: {}", e);
+            Err(e)
         }
     }
 }
@@ -257,51 +198,120 @@ async fn run_ocr_verification() -> Result<AppResult, String> {
     println!("🚀 Running comprehensive OCR verification...");
     
     // Run all tests and capture output
-    test_ocr::run_all_tests();
-    
-    // If we get here, tests passed (would have panicked otherwise)
-    Ok(AppResult {
-        success: true,
-        message: "🎉 All OCR verification tests passed! Tesseract is working correctly.".to_string(),
-    })
+    match test_ocr::run_all_tests() {
+        Ok(()) => Ok(AppResult {
+            success: true,
+            message: "🎉 All OCR verification tests passed! Tesseract is working correctly.".to_string(),
+        }),
+        Err(error) => Ok(AppResult {
+            success: false,
+            message: format!("OCR verification failed: {}", error),
+        }),
+    }
+}
+
+// Run capture pipeline verification (content classification fixtures)
+#[tauri::command]
+async fn run_capture_verification() -> Result<AppResult, String> {
+    println!("🚀 Running capture pipeline verification...");
+
+    match test_capture::run_all_tests().await {
+        Ok(()) => Ok(AppResult {
+            success: true,
+            message: "🎉 All capture verification tests passed!".to_string(),
+        }),
+        Err(error) => Ok(AppResult {
+            success: false,
+            message: format!("Capture verification failed: {}", error),
+        }),
+    }
+}
+
+// Run capture history verification (store, search, retention) in a scratch directory
+#[tauri::command]
+async fn run_history_verification() -> Result<AppResult, String> {
+    println!("🚀 Running capture history verification...");
+
+    match tokio::task::spawn_blocking(test_history::run_all_tests).await.map_err(|e| format!("Task error: {}", e))? {
+        Ok(()) => Ok(AppResult {
+            success: true,
+            message: "🎉 All history verification tests passed!".to_string(),
+        }),
+        Err(error) => Ok(AppResult {
+            success: false,
+            message: format!("History verification failed: {}", error),
+        }),
+    }
 }
 
 // Extract text from image using OCR (Step 2-3 from AI.txt)
+// Pass `detailed: true` to also get block/line/word boxes, in `bounds` coordinates when given.
+// `languages` are Tesseract codes (e.g. ["swe", "eng"]) or ["auto"] for detection.
+// `preprocess` overrides the default pipeline; the scale factor is looked up from `bounds`.
+// The image is either a data URL (`image_data`) or a registered capture (`capture_id`).
 #[tauri::command]
-async fn extract_text_ocr(image_data: String) -> Result<OCRResult, String> {
+async fn extract_text_ocr(
+    image_data: Option<String>,
+    capture_id: Option<String>,
+    detailed: Option<bool>,
+    bounds: Option<CaptureBounds>,
+    languages: Option<Vec<String>>,
+    preprocess: Option<PreprocessOptions>,
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<OCRResult, String> {
     println!("📝 Extracting text from image using OCR...");
-    
-    unsafe {
-        OCR_INIT.call_once(|| {
-            if let Ok(service) = OCRService::new() {
-                OCR_SERVICE = Some(std::sync::Mutex::new(service));
-                println!("✅ OCR service initialized successfully");
-            } else {
-                println!("❌ Failed to initialize OCR service");
-            }
-        });
-        
-        if let Some(ref service_mutex) = OCR_SERVICE {
-            let service = service_mutex.lock().unwrap();
-            match service.extract_text(&image_data) {
-                Ok(result) => {
-                    println!("✅ OCR extraction successful - Text: '{}', Confidence: {:.2}%", 
-                             result.text, result.confidence * 100.0);
-                    Ok(result)
-                },
-                Err(error) => {
-                    println!("❌ OCR extraction failed: {}", error);
-                    Err(error)
-                }
-            }
-        } else {
-            let error_msg = "OCR service not initialized".to_string();
-            println!("❌ {}", error_msg);
-            Err(error_msg)
+
+    let image = match (capture_id, image_data) {
+        (Some(id), _) => {
+            let capture = registry.lock().unwrap().get(&id).cloned()
+                .ok_or_else(|| format!("Capture {} not found", id))?;
+            capture.decode()?
+        },
+        (None, Some(image_data)) => OCRService::decode_image(&image_data)?,
+        (None, None) => return Err("Either image_data or capture_id is required".to_string()),
+    };
+
+    let mut preprocess = preprocess.unwrap_or_default();
+    if preprocess.scale_factor.is_none() {
+        preprocess.scale_factor = bounds.as_ref().map(overlay::ScreenCapture::scale_factor_for);
+    }
+
+    let mut service = ocr::get_ocr_service()?.with_preprocessing(preprocess);
+    if let Some(codes) = languages {
+        service = service.with_languages(LanguageSelection::from_codes(&codes)?)?;
+    }
+    let extraction = if detailed.unwrap_or(false) {
+        service.extract_layout_from_image(&image, bounds.as_ref())
+    } else {
+        service.extract_text_from_image(&image)
+    };
+    match extraction {
+        Ok(result) => {
+            println!("✅ OCR extraction successful - Text: '{}', Confidence: {:.2}%", 
+                     result.text, result.confidence * 100.0);
+            Ok(result)
+        },
+        Err(error) => {
+            println!("❌ OCR extraction failed: {}", error);
+            Err(error)
         }
     }
 }
 
+// Classify a finished selection and extract its text with the shared OCR service
+#[tauri::command]
+async fn process_selection(selection: overlay::SelectionResult) -> Result<overlay::ProcessedContent, String> {
+    overlay::InteractiveOverlay::process_selection(&selection).await
+}
+
+// List installed OCR language packs (for the settings dialog)
+#[tauri::command]
+async fn get_ocr_languages() -> Result<Vec<LanguagePack>, String> {
+    let packs = ocr::languages::installed_languages();
+    println!("🌍 Found {} installed OCR language pack(s)", packs.len());
+    Ok(packs)
+}
+
 // Check permissions (simplified for now)
 #[tauri::command]
 async fn check_permissions() -> Result<bool, String> {
@@ -376,28 +386,11 @@ fn cleanup_permission_cache(
 #[tauri::command]
 fn capture_screen_area_optimized(
     bounds: CaptureBounds,
+    output: Option<OutputOptions>,
     cache: tauri::State<'_, SharedScreenshotCache>
-) -> Result<CaptureResult, String> {
+) -> Result<CaptureResult, CaptureError> {
     let mut screenshot_cache = cache.lock().unwrap();
-    
-    match screenshot_cache.capture_optimized(bounds.clone()) {
-        Ok(image_data) => {
-            Ok(CaptureResult {
-                success: true,
-                message: "Optimized screen capture successful!".to_string(),
-                bounds: Some(bounds),
-                image_data: Some(image_data),
-            })
-        },
-        Err(e) => {
-            Ok(CaptureResult {
-                success: false, 
-                message: e,
-                bounds: None,
-                image_data: None,
-            })
-        }
-    }
+    screenshot_cache.capture(&bounds, &output.unwrap_or_default())
 }
 
 // Clear screenshot cache (for testing or memory management)
@@ -417,19 +410,31 @@ fn get_screenshot_cache_stats(
     cache: tauri::State<'_, SharedScreenshotCache>
 ) -> Result<serde_json::Value, String> {
     let screenshot_cache = cache.lock().unwrap();
-    let (total_entries, total_size, expired_entries) = screenshot_cache.get_cache_stats();
+    let stats = screenshot_cache.get_cache_stats();
+    let lookups = stats.hits + stats.misses;
     
-    let stats = serde_json::json!({
-        "total_entries": total_entries,
-        "total_size_bytes": total_size,
-        "total_size_mb": total_size / (1024 * 1024),
-        "expired_entries": expired_entries,
-        "active_entries": total_entries - expired_entries
+    let result = serde_json::json!({
+        "total_entries": stats.entries,
+        "total_size_bytes": stats.size_bytes,
+        "total_size_mb": stats.size_bytes / (1024 * 1024),
+        "budget_bytes": stats.budget_bytes,
+        "raw_bytes": stats.raw_bytes,
+        "encoded_bytes": stats.encoded_bytes,
+        "expired_entries": stats.expired_entries,
+        "active_entries": stats.entries - stats.expired_entries,
+        "evictions": stats.evictions,
+        "hits": stats.hits,
+        "misses": stats.misses,
+        "hit_rate": if lookups > 0 { stats.hits as f64 / lookups as f64 } else { 0.0 },
+        "bytes_served": stats.bytes_served,
+        "frame_reuses": stats.frame_reuses,
+        "cached_frames": stats.frames,
+        "frame_bytes": stats.frame_bytes
     });
     
-    println!("📊 Screenshot cache stats: {} entries, {}MB, {} expired", 
-             total_entries, total_size / (1024 * 1024), expired_entries);
-    Ok(stats)
+    println!("📊 Screenshot cache stats: {} entries, {}MB, {} hits / {} misses, {} frame reuses", 
+             stats.entries, stats.size_bytes / (1024 * 1024), stats.hits, stats.misses, stats.frame_reuses);
+    Ok(result)
 }
 
 // Cleanup expired screenshot cache entries
@@ -443,6 +448,33 @@ fn cleanup_screenshot_cache(
     Ok(())
 }
 
+// Set the screenshot cache memory budget
+#[tauri::command]
+fn set_screenshot_cache_budget(
+    budget_mb: usize,
+    cache: tauri::State<'_, SharedScreenshotCache>
+) -> Result<(), String> {
+    let budget = budget_mb.checked_mul(1024 * 1024)
+        .ok_or_else(|| format!("Cache budget of {} MB is too large", budget_mb))?;
+    let mut screenshot_cache = cache.lock().unwrap();
+    screenshot_cache.set_budget(budget);
+    Ok(())
+}
+
+// Benchmark capture latency with and without the screenshot cache
+// (`use_fixtures` runs against a synthetic screen instead of the real display)
+#[tauri::command]
+async fn run_capture_benchmark(iterations: Option<usize>, use_fixtures: Option<bool>) -> Result<bench_capture::BenchmarkReport, String> {
+    tokio::task::spawn_blocking(move || {
+        let source = if use_fixtures.unwrap_or(false) {
+            bench_capture::fixture_source()?
+        } else {
+            overlay::capture_source::default_source()
+        };
+        bench_capture::run_cache_benchmark(source, iterations.unwrap_or(20))
+    }).await.map_err(|e| format!("Task error: {}", e))?
+}
+
 // Resize screenshot buffer (for memory optimization)
 #[tauri::command]
 fn resize_screenshot_buffer(
@@ -456,6 +488,416 @@ fn resize_screenshot_buffer(
     Ok(())
 }
 
+// 🖼️ CAPTURE HANDLE COMMANDS (binary transfer instead of base64 JSON)
+
+// Capture into the registry; the webview loads `handle.url` or calls get_capture_bytes.
+// The caller owns one reference and releases it with release_capture.
+#[tauri::command]
+async fn capture_screen_area_handle(
+    bounds: CaptureBounds,
+    output: Option<OutputOptions>,
+    cache: tauri::State<'_, SharedScreenshotCache>,
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<CaptureHandle, CaptureError> {
+    let (_, image) = cache.lock().unwrap().capture_pixels(&bounds)?;
+    let encoded = tokio::task::spawn_blocking(move || {
+        overlay::image_encoding::encode_bytes(&image, &output.unwrap_or_default())
+    }).await
+        .map_err(|e| CaptureError::Backend(format!("Task error: {}", e)))?
+        .map_err(CaptureError::EncodeFailed)?;
+
+    let handle = registry.lock().unwrap().insert(encoded);
+    println!("🖼️ Registered capture {}: {} {}x{}, {}KB", handle.id, handle.mime_type, handle.width, handle.height, handle.byte_size / 1024);
+    Ok(handle)
+}
+
+// Encoded bytes of a capture as a raw IPC response (an ArrayBuffer in JS)
+#[tauri::command]
+async fn get_capture_bytes(
+    id: String,
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<tauri::ipc::Response, String> {
+    // Only the Arc is cloned under the lock; the copy happens on a worker
+    let bytes = registry.lock().unwrap().get(&id)
+        .map(|capture| capture.bytes.clone())
+        .ok_or_else(|| format!("Capture {} not found", id))?;
+    let bytes = tokio::task::spawn_blocking(move || bytes.to_vec()).await
+        .map_err(|e| format!("Task error: {}", e))?;
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+// Take another reference to a capture (e.g. a second view showing it)
+#[tauri::command]
+fn retain_capture(
+    id: String,
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<usize, String> {
+    registry.lock().unwrap().retain(&id)
+}
+
+// Drop a reference; the capture is freed with the last one
+#[tauri::command]
+fn release_capture(
+    id: String,
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<usize, String> {
+    registry.lock().unwrap().release(&id)
+}
+
+#[tauri::command]
+fn get_capture_registry_stats(
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<overlay::capture_registry::RegistryStats, String> {
+    Ok(registry.lock().unwrap().stats())
+}
+
+// 📚 CAPTURE HISTORY COMMANDS
+
+// Save a capture (data URL) with whatever OCR text, answer and tags are known so far
+#[tauri::command]
+fn save_to_history(
+    image_data: String,
+    bounds: Option<CaptureBounds>,
+    ocr_text: Option<String>,
+    ai_answer: Option<String>,
+    tags: Option<Vec<String>>,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<HistoryEntry, String> {
+    history.lock().unwrap().add(NewHistoryEntry {
+        image_data,
+        bounds,
+        ocr_text,
+        ai_answer,
+        tags: tags.unwrap_or_default(),
+    })
+}
+
+// Attach OCR text, an AI answer or tags to an existing entry
+#[tauri::command]
+fn update_history_entry(
+    id: String,
+    ocr_text: Option<String>,
+    ai_answer: Option<String>,
+    tags: Option<Vec<String>>,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<HistoryEntry, String> {
+    history.lock().unwrap().update(&id, HistoryUpdate { ocr_text, ai_answer, tags })
+}
+
+// Newest entries first, without images
+#[tauri::command]
+fn list_history(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<Vec<HistoryEntry>, String> {
+    Ok(history.lock().unwrap().list(offset.unwrap_or(0), limit.unwrap_or(50)))
+}
+
+// Full-text search over OCR text, AI answers and tags (`tag:name` filters by tag)
+#[tauri::command]
+fn search_history(
+    query: String,
+    limit: Option<usize>,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<Vec<HistoryHit>, String> {
+    let hits = history.lock().unwrap().search(&query, limit.unwrap_or(50));
+    println!("🔎 History search '{}': {} hits", query, hits.len());
+    Ok(hits)
+}
+
+// Entry plus its image as a data URL
+#[tauri::command]
+fn open_history_entry(
+    id: String,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<OpenedHistoryEntry, String> {
+    history.lock().unwrap().open_entry(&id)
+}
+
+#[tauri::command]
+fn delete_history_entry(
+    id: String,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<(), String> {
+    history.lock().unwrap().delete(&id).map(|_| ())
+}
+
+#[tauri::command]
+fn clear_history(history: tauri::State<'_, SharedHistory>) -> Result<usize, String> {
+    let removed = history.lock().unwrap().clear()?;
+    println!("🗑️ Cleared {} history entries", removed);
+    Ok(removed)
+}
+
+#[tauri::command]
+fn get_history_stats(history: tauri::State<'_, SharedHistory>) -> Result<HistoryStats, String> {
+    Ok(history.lock().unwrap().stats())
+}
+
+// Change retention limits; returns how many entries they removed
+#[tauri::command]
+fn set_history_retention(
+    retention: RetentionPolicy,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<usize, String> {
+    history.lock().unwrap().set_retention(retention)
+}
+
+// 🛡️ PRIVACY FILTER COMMANDS
+
+/// A capture and its text as they may leave the machine
+#[derive(Clone, Serialize)]
+struct FilteredUpload {
+    image_data: Option<String>, // PNG data URL; None if the image was withheld
+    text: Option<String>,
+    report: RedactionReport,
+}
+
+// Redact sensitive data (emails, cards, IBANs, keys, passwords, phone numbers) before
+// a capture and its text are sent for analysis. The image is a data URL or a
+// registered capture; `team` picks that team's policy instead of the default.
+#[tauri::command]
+async fn filter_for_upload(
+    image_data: Option<String>,
+    capture_id: Option<String>,
+    text: Option<String>,
+    team: Option<String>,
+    registry: tauri::State<'_, SharedCaptureRegistry>,
+    privacy: tauri::State<'_, SharedPrivacy>
+) -> Result<FilteredUpload, String> {
+    let image = match (capture_id, image_data) {
+        (Some(id), _) => {
+            let capture = registry.lock().unwrap().get(&id).cloned()
+                .ok_or_else(|| format!("Capture {} not found", id))?;
+            Some(capture.decode()?.to_rgba8())
+        },
+        (None, Some(image_data)) => Some(OCRService::decode_image(&image_data)?.to_rgba8()),
+        (None, None) => None,
+    };
+    let policy = privacy.lock().unwrap().policy(team.as_deref()).clone();
+
+    tokio::task::spawn_blocking(move || {
+        // Word boxes in the image's own pixels. Deskewing stays on so skewed secrets are
+        // still read; the boxes come back rotated onto the skewed text.
+        let read_layout = |image: &image::RgbaImage| {
+            let image = image::DynamicImage::ImageRgba8(image.clone());
+            ocr::get_ocr_service()?.extract_layout_from_image(&image, None)?
+                .layout
+                .ok_or_else(|| "OCR returned no word boxes".to_string())
+        };
+        let filtered = privacy::filter::filter(&policy, image, text.as_deref(), &read_layout);
+        let image_data = match &filtered.image {
+            Some(image) => Some(overlay::image_encoding::encode(image, &OutputOptions::default())?.image_data),
+            None => None,
+        };
+
+        let report = filtered.report;
+        if !report.redactions.is_empty() {
+            println!("🛡️ Redacted before upload: {:?}", report.counts());
+        }
+        if report.image_withheld {
+            println!("🛡️ Image withheld: it could not be scanned for sensitive data");
+        } else if report.scan_error.is_some() && image_data.is_some() {
+            println!("🛡️ Image sent unscanned, the policy allows it");
+        }
+        Ok(FilteredUpload { image_data, text: filtered.text, report })
+    }).await
+        .map_err(|e| format!("Task error: {}", e))?
+}
+
+#[tauri::command]
+fn get_privacy_settings(privacy: tauri::State<'_, SharedPrivacy>) -> Result<PrivacySettings, String> {
+    Ok(privacy.lock().unwrap().settings().clone())
+}
+
+// Replace the default policy, or one team's when `team` is given
+#[tauri::command]
+fn set_privacy_policy(
+    team: Option<String>,
+    policy: PrivacyPolicy,
+    privacy: tauri::State<'_, SharedPrivacy>
+) -> Result<(), String> {
+    privacy.lock().unwrap().set_policy(team.as_deref(), policy)
+}
+
+// Make a team use the default policy again
+#[tauri::command]
+fn remove_team_privacy_policy(team: String, privacy: tauri::State<'_, SharedPrivacy>) -> Result<bool, String> {
+    privacy.lock().unwrap().remove_team_policy(&team)
+}
+
+// ✏️ ANNOTATION COMMANDS
+
+// Save what was drawn on a history entry (kept as JSON next to the image, which stays
+// untouched); an empty list removes the annotations
+#[tauri::command]
+fn save_history_annotations(
+    id: String,
+    document: AnnotationDocument,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<HistoryEntry, String> {
+    history.lock().unwrap().save_annotations(&id, &document)
+}
+
+#[tauri::command]
+fn get_history_annotations(
+    id: String,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<Option<AnnotationDocument>, String> {
+    history.lock().unwrap().annotations(&id)
+}
+
+// History entry with its annotations flattened onto it, for sharing
+#[tauri::command]
+async fn export_history_entry(
+    id: String,
+    output: Option<OutputOptions>,
+    history: tauri::State<'_, SharedHistory>
+) -> Result<overlay::image_encoding::EncodedCapture, String> {
+    let image = history.lock().unwrap().render_annotated(&id)?;
+    tokio::task::spawn_blocking(move || overlay::image_encoding::encode(&image, &output.unwrap_or_default()))
+        .await
+        .map_err(|e| format!("Task error: {}", e))?
+}
+
+// Flatten annotations onto a registered capture; the result is a new capture the
+// caller owns, the original is left as it was
+#[tauri::command]
+async fn annotate_capture(
+    capture_id: String,
+    document: AnnotationDocument,
+    output: Option<OutputOptions>,
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<CaptureHandle, String> {
+    let capture = registry.lock().unwrap().get(&capture_id).cloned()
+        .ok_or_else(|| format!("Capture {} not found", capture_id))?;
+    let encoded = tokio::task::spawn_blocking(move || {
+        let image = annotate::render(&capture.decode()?.to_rgba8(), &document)?;
+        overlay::image_encoding::encode_bytes(&image, &output.unwrap_or_default())
+    }).await
+        .map_err(|e| format!("Task error: {}", e))??;
+
+    let handle = registry.lock().unwrap().insert(encoded);
+    println!("✏️ Annotated capture {} as {}", capture_id, handle.id);
+    Ok(handle)
+}
+
+// 📋 CLIPBOARD COMMANDS
+
+// Copy text (OCR text, an AI answer) or an image to the clipboard. The image is a
+// registered capture, a history entry (with its annotations) or a data URL.
+#[tauri::command]
+fn copy_to_clipboard(
+    text: Option<String>,
+    image_data: Option<String>,
+    capture_id: Option<String>,
+    history_id: Option<String>,
+    registry: tauri::State<'_, SharedCaptureRegistry>,
+    history: tauri::State<'_, SharedHistory>,
+    clipboard: tauri::State<'_, SharedClipboard>
+) -> Result<(), String> {
+    let image = match (capture_id, history_id, image_data) {
+        (Some(id), _, _) => {
+            let capture = registry.lock().unwrap().get(&id).cloned()
+                .ok_or_else(|| format!("Capture {} not found", id))?;
+            Some(capture.decode()?.to_rgba8())
+        },
+        (None, Some(id), _) => Some(history.lock().unwrap().render_annotated(&id)?),
+        (None, None, Some(image_data)) => Some(OCRService::decode_image(&image_data)?.to_rgba8()),
+        (None, None, None) => None,
+    };
+
+    let mut clipboard = clipboard.lock().unwrap();
+    match (text, image) {
+        (Some(text), None) => clipboard.copy_text(&text),
+        (None, Some(image)) => clipboard.copy_image(&image),
+        (None, None) => Err("Nothing to copy".to_string()),
+        (Some(_), Some(_)) => Err("Copy either text or an image, not both".to_string()),
+    }
+}
+
+// Analyze what is on the clipboard like a screen selection: an image is delivered
+// as a capture, text as a selection-result of type "text". Returns which it was.
+#[tauri::command]
+fn analyze_clipboard(app: tauri::AppHandle) -> Result<String, String> {
+    analyze_clipboard_contents(&app).map(str::to_string)
+}
+
+fn analyze_clipboard_contents(app: &tauri::AppHandle) -> Result<&'static str, String> {
+    let content = app.state::<SharedClipboard>().lock().unwrap().read()?
+        .ok_or_else(|| "The clipboard holds no image or text".to_string())?;
+
+    match content {
+        ClipboardContent::Image(image) => {
+            println!("📋 Analyzing {}x{} image from the clipboard", image.width(), image.height());
+            let bounds = CaptureBounds { x: 0, y: 0, width: image.width(), height: image.height() };
+            let registry = app.state::<SharedCaptureRegistry>();
+            deliver_selection(app, &bounds, None, Ok((bounds.clone(), image)), &registry, false)
+                .map_err(|e| format!("Failed to deliver the clipboard image: {}", e))?;
+            Ok("image")
+        },
+        ClipboardContent::Text(text) => {
+            println!("📋 Analyzing {} characters of text from the clipboard", text.chars().count());
+            deliver_text(app, &text, "clipboard")?;
+            Ok("text")
+        },
+    }
+}
+
+// 📂 FILE COMMANDS
+
+/// What process_file found in a file
+#[derive(Clone, Serialize)]
+struct ProcessedFile {
+    name: String,
+    kind: FileKind,
+    page: u32,
+    page_count: u32,
+    content: overlay::ProcessedContent,
+}
+
+// Analyze an image (PNG, JPEG, WebP, BMP) or a PDF page from disk like a screen
+// selection: classified, OCR'd and sent to the main window as a selection-result.
+// `page` picks the PDF page, from 1.
+#[tauri::command]
+async fn process_file(
+    app: tauri::AppHandle,
+    filepath: String,
+    page: Option<u32>,
+    registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<ProcessedFile, String> {
+    println!("📂 Processing file {}", filepath);
+    let path = PathBuf::from(&filepath);
+    let file = tokio::task::spawn_blocking(move || overlay::file_input::load(&path, page))
+        .await
+        .map_err(|e| format!("Task error: {}", e))??;
+
+    let content = match file.content {
+        FileContent::Image(image) => {
+            let content = overlay::InteractiveOverlay::process_image(image::DynamicImage::ImageRgba8(image.clone())).await?;
+            let bounds = CaptureBounds { x: 0, y: 0, width: image.width(), height: image.height() };
+            deliver_selection(&app, &bounds, None, Ok((bounds.clone(), image)), &registry, false)
+                .map_err(|e| format!("Failed to deliver {}: {}", file.name, e))?;
+            content
+        },
+        // A digital PDF page: its own text is exact, there is nothing to OCR
+        FileContent::Text(text) => {
+            deliver_text(&app, &text, "file")?;
+            let lines = text.lines().filter(|line| !line.trim().is_empty()).count();
+            overlay::ProcessedContent {
+                content_type: overlay::ContentType::PlainText,
+                confidence: 1.0,
+                ai_analysis: Some(format!("PDF page with {} line(s) of embedded text.", lines)),
+                extracted_text: Some(text),
+                ocr_confidence: None,
+            }
+        },
+    };
+
+    Ok(ProcessedFile { name: file.name, kind: file.kind, page: file.page, page_count: file.page_count, content })
+}
+
 // 🚀 AUTHENTICATION COMMANDS
 
 // Login user with credentials
@@ -752,24 +1194,8 @@ async fn create_transparent_overlay(app: tauri::AppHandle) -> Result<(), String>
         }
     }
     
-    // Get actual screen dimensions
-    let (screen_width, screen_height) = match screenshots::Screen::all() {
-        Ok(screens) => {
-            if let Some(screen) = screens.first() {
-                let width = screen.display_info.width as f64;
-                let height = screen.display_info.height as f64;
-                println!("📺 Detected screen: {}x{}", width, height);
-                (width, height)
-            } else {
-                println!("⚠️ No screens found, using fallback 1920x1080");
-                (1920.0, 1080.0)
-            }
-        },
-        Err(e) => {
-            println!("❌ Failed to get screen info: {}, using fallback", e);
-            (1920.0, 1080.0)
-        }
-    };
+    // Cover every screen so a selection can be made on (or across) any monitor
+    let desktop = overlay::ScreenCapture::overlay_bounds();
     
     println!("🎯 Creating React-based transparent overlay window...");
     
@@ -780,8 +1206,8 @@ async fn create_transparent_overlay(app: tauri::AppHandle) -> Result<(), String>
         WebviewUrl::App("overlay".into())  // React route from OverlayApp.tsx
     )
     .title("FrameSense Overlay")
-    .inner_size(screen_width, screen_height)
-    .position(0.0, 0.0)
+    .inner_size(desktop.width as f64, desktop.height as f64)
+    .position(desktop.x as f64, desktop.y as f64)
     .decorations(false)
     .transparent(true)        // Transparent window
     .shadow(false)            // No shadow
@@ -835,6 +1261,9 @@ async fn create_transparent_overlay_optimized(
         }
     }
     
+    // Selections made in this overlay may share one captured frame
+    app.state::<SharedScreenshotCache>().lock().unwrap().begin_selection();
+    
     let mut manager = overlay_manager.lock().unwrap();
     manager.show_selection_overlay(&app)
 }
@@ -846,6 +1275,7 @@ async fn close_transparent_overlay_optimized(
     overlay_manager: tauri::State<'_, SharedOverlayManager>
 ) -> Result<(), String> {
     println!("🎯 Closing optimized overlay and showing main window...");
+    app.state::<SharedScreenshotCache>().lock().unwrap().end_selection();
     
     let mut manager = overlay_manager.lock().unwrap();
     let result = manager.hide_overlay();
@@ -873,43 +1303,579 @@ async fn process_screen_selection_optimized(
     app: tauri::AppHandle, 
     bounds: CaptureBounds,
     overlay_manager: tauri::State<'_, SharedOverlayManager>,
-    screenshot_cache: tauri::State<'_, SharedScreenshotCache>
+    screenshot_cache: tauri::State<'_, SharedScreenshotCache>,
+    capture_registry: tauri::State<'_, SharedCaptureRegistry>
 ) -> Result<(), String> {
     println!("📸 Processing optimized screen selection: {}x{} at ({}, {})", 
              bounds.width, bounds.height, bounds.x, bounds.y);
     
-    // Use optimized capture with caching
-    let capture_result = capture_screen_area_optimized(bounds.clone(), screenshot_cache)?;
+    let source = screenshot_cache.lock().unwrap().source();
+    let bounds = overlay_selection_to_desktop(&app, source.as_ref(), bounds);
     
-    if capture_result.success && capture_result.image_data.is_some() {
-        let image_data = capture_result.image_data.unwrap();
-        println!("✅ Optimized screen capture successful!");
-        
-        // Send result to React (same as original)
-        if let Some(window) = app.get_webview_window("main") {
-            let analysis_result = serde_json::json!({
-                "type": "image",
-                "bounds": bounds,
-                "imageData": image_data,
-                "text": null,
-                "success": true,
-                "message": "Optimized screen area captured successfully!"
-            });
-            
-            window.emit("selection-result", analysis_result).unwrap();
-            println!("📤 Sent optimized capture data to main app");
-        }
-        
+    // Capture through the cache's frames and hand the result over by handle
+    let captured = screenshot_cache.lock().unwrap().capture_pixels(&bounds);
+    if deliver_selection(&app, &bounds, None, captured, &capture_registry, true).is_ok() {
         // Hide overlay using optimized manager
         let _ = close_transparent_overlay_optimized(app, overlay_manager);
-        
-    } else {
-        println!("❌ Optimized capture failed: {}", capture_result.message);
     }
     
     Ok(())
 }
 
+// The overlay reports webview coordinates; map them to desktop units
+fn overlay_selection_to_desktop(app: &tauri::AppHandle, source: &dyn overlay::CaptureSource, bounds: CaptureBounds) -> CaptureBounds {
+    match overlay::ScreenLayout::from_source(source) {
+        Ok(layout) => {
+            let (origin, webview_scale) = overlay_geometry(app, &layout);
+            let mapped = layout.overlay_to_desktop(&bounds, origin, webview_scale);
+            println!("🔧 Overlay selection mapped to desktop: {}x{} at ({}, {}) (webview scale {})",
+                     mapped.width, mapped.height, mapped.x, mapped.y, webview_scale);
+            mapped
+        },
+        Err(e) => {
+            println!("⚠️ Screen layout unavailable ({}), using overlay coordinates as-is", e);
+            bounds
+        }
+    }
+}
+
+// Where the overlay window sits (desktop units) and its webview's scale factor
+fn overlay_geometry(app: &tauri::AppHandle, layout: &overlay::ScreenLayout) -> ((i32, i32), f64) {
+    let origin = overlay::ScreenCapture::desktop_bounds(layout.screens())
+        .map(|desktop| (desktop.x, desktop.y))
+        .unwrap_or((0, 0));
+    let webview_scale = app.get_webview_window("overlay")
+        .and_then(|window| window.scale_factor().ok())
+        .unwrap_or(1.0);
+    (origin, webview_scale)
+}
+
+// Encode an overlay capture, keep it in the registry and send its handle to the main
+// window as a selection-result. A failed capture is reported there too and returned.
+// `save_to_history` is for what the user selected in the overlay; scheduled captures,
+// clipboard images and files on disk are not kept.
+fn deliver_selection(
+    app: &tauri::AppHandle,
+    bounds: &CaptureBounds,
+    window_info: Option<&overlay::WindowInfo>,
+    captured: Result<(CaptureBounds, image::RgbaImage), CaptureError>,
+    capture_registry: &SharedCaptureRegistry,
+    save_to_history: bool
+) -> Result<(), CaptureError> {
+    let encoded = captured.and_then(|(area, image)| {
+        overlay::image_encoding::encode_bytes(&image, &OutputOptions::default())
+            .map(|encoded| (area, encoded))
+            .map_err(CaptureError::EncodeFailed)
+    });
+    
+    match encoded {
+        Ok((area, encoded)) => {
+            let capture = capture_registry.lock().unwrap().insert(encoded);
+            println!("✅ Optimized screen capture successful! ({}KB as {})", capture.byte_size / 1024, capture.url);
+            let saving_to_history = save_to_history && app.try_state::<SharedHistory>().is_some();
+            
+            // Send the handle to React; the image itself is loaded from `imageUrl`.
+            // The main window owns the reference and releases it when done.
+            if let Some(window) = app.get_webview_window("main") {
+                let analysis_result = serde_json::json!({
+                    "type": "image",
+                    "bounds": area,
+                    "window": window_info,
+                    "imageUrl": capture.url,
+                    "capture": capture,
+                    "savingToHistory": saving_to_history,
+                    "text": null,
+                    "success": true,
+                    "message": "Optimized screen area captured successfully!"
+                });
+                
+                window.emit("selection-result", analysis_result).unwrap();
+                println!("📤 Sent optimized capture handle to main app");
+                if saving_to_history {
+                    save_capture_to_history(app, capture_registry, &capture, area);
+                }
+            } else {
+                let _ = capture_registry.lock().unwrap().release(&capture.id);
+            }
+            Ok(())
+        },
+        Err(e) => {
+            println!("❌ Optimized capture failed: {}", e);
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.emit("selection-result", serde_json::json!({
+                    "type": "error",
+                    "bounds": bounds,
+                    "window": window_info,
+                    "error": e,
+                    "success": false,
+                    "message": e.to_string()
+                }));
+            }
+            Err(e)
+        },
+    }
+}
+
+// Write a delivered capture to the history on its own thread, then tell the main
+// window the entry's id (null if saving failed) so it can attach OCR text and the
+// AI answer with update_history_entry
+fn save_capture_to_history(app: &tauri::AppHandle, capture_registry: &SharedCaptureRegistry, capture: &CaptureHandle, area: CaptureBounds) {
+    let Some(stored) = capture_registry.lock().unwrap().get(&capture.id).cloned() else { return };
+    let (app, capture_id) = (app.clone(), capture.id.clone());
+    std::thread::spawn(move || {
+        let history = app.state::<SharedHistory>();
+        let saved = history.lock().unwrap().add_png(&stored.bytes, stored.width, stored.height, Some(area));
+        let history_id = match saved {
+            Ok(entry) => Some(entry.id),
+            Err(e) => {
+                println!("⚠️ Capture not saved to history: {}", e);
+                None
+            }
+        };
+        let _ = app.emit("history-saved", serde_json::json!({ "captureId": capture_id, "historyId": history_id }));
+    });
+}
+
+// Send text that needs no capture (clipboard text, a PDF page's text) to the main
+// window as a selection-result of type "text"
+fn deliver_text(app: &tauri::AppHandle, text: &str, source: &str) -> Result<(), String> {
+    let window = app.get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+    window.emit("selection-result", serde_json::json!({
+        "type": "text",
+        "text": text,
+        "source": source,
+        "success": true,
+        "message": format!("Text from {} ready for analysis", source)
+    })).map_err(|e| format!("Failed to send text: {}", e))
+}
+
+// Top-level windows for the overlay's window-picking mode, topmost first, with
+// their bounds in overlay viewport pixels so the overlay can highlight them
+#[tauri::command]
+async fn list_capture_windows(
+    app: tauri::AppHandle,
+    screenshot_cache: tauri::State<'_, SharedScreenshotCache>
+) -> Result<Vec<overlay::OverlayWindow>, CaptureError> {
+    let service = CaptureService::with_source(screenshot_cache.lock().unwrap().source());
+    let layout = overlay::ScreenLayout::new(service.screens()?);
+    let (origin, webview_scale) = overlay_geometry(&app, &layout);
+    
+    let windows: Vec<overlay::OverlayWindow> = tauri::async_runtime::spawn_blocking(move || service.windows())
+        .await
+        .map_err(|e| CaptureError::Backend(format!("Window listing task failed: {}", e)))??
+        .into_iter()
+        .map(|window| overlay::OverlayWindow {
+            overlay_bounds: layout.desktop_to_overlay(&window.bounds, origin, webview_scale),
+            window,
+        })
+        .collect();
+    println!("🪟 Listed {} window(s) for picking", windows.len());
+    Ok(windows)
+}
+
+// Capture the window picked in the overlay (its own pixels where the platform
+// can read covered windows) and send it like a drag selection
+#[tauri::command]
+async fn process_window_selection_optimized(
+    app: tauri::AppHandle,
+    window_id: u64,
+    overlay_manager: tauri::State<'_, SharedOverlayManager>,
+    screenshot_cache: tauri::State<'_, SharedScreenshotCache>,
+    capture_registry: tauri::State<'_, SharedCaptureRegistry>
+) -> Result<(), CaptureError> {
+    let service = CaptureService::with_source(screenshot_cache.lock().unwrap().source());
+    // Both talk to the display server, so they run off the async runtime
+    let (window_info, captured) = tauri::async_runtime::spawn_blocking(move || {
+        let window_info = service.windows().ok()
+            .and_then(|windows| windows.into_iter().find(|window| window.id == window_id));
+        (window_info, service.capture_window_pixels(window_id))
+    }).await
+        .map_err(|e| CaptureError::Backend(format!("Window capture task failed: {}", e)))?;
+    if let Some(window_info) = &window_info {
+        println!("🪟 Capturing window \"{}\" ({}): {}x{} at ({}, {})", window_info.title, window_info.app_name,
+                 window_info.bounds.width, window_info.bounds.height, window_info.bounds.x, window_info.bounds.y);
+    }
+    
+    let bounds = window_info.as_ref().map(|window| window.bounds.clone())
+        .unwrap_or(CaptureBounds { x: 0, y: 0, width: 0, height: 0 });
+    deliver_selection(&app, &bounds, window_info.as_ref(), captured, &capture_registry, true)?;
+    let _ = close_transparent_overlay_optimized(app, overlay_manager);
+    
+    Ok(())
+}
+
+// Start a scrolling capture of an overlay selection. The overlay lets the mouse
+// through so the content under it can be scrolled while frames are stitched.
+#[tauri::command]
+async fn start_scroll_capture(
+    app: tauri::AppHandle,
+    bounds: CaptureBounds,
+    screenshot_cache: tauri::State<'_, SharedScreenshotCache>,
+    scroll_capture: tauri::State<'_, SharedScrollCapture>
+) -> Result<(), CaptureError> {
+    let source = screenshot_cache.lock().unwrap().source();
+    let bounds = overlay_selection_to_desktop(&app, source.as_ref(), bounds);
+    
+    // Replacing an earlier capture drops (and stops) it
+    let capture = ScrollCapture::start(CaptureService::with_source(source), &bounds)?;
+    *scroll_capture.lock().unwrap() = Some(capture);
+    set_overlay_click_through(&app, true);
+    update_scroll_tray(&app, true);
+    Ok(())
+}
+
+// Stop the scrolling capture and send the stitched image like a selection
+#[tauri::command]
+async fn finish_scroll_capture(app: tauri::AppHandle) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || end_scroll_capture(&app, true)).await
+        .map_err(|e| format!("Task error: {}", e))?
+        .map(|_| ())
+}
+
+#[tauri::command]
+async fn cancel_scroll_capture(app: tauri::AppHandle) -> Result<bool, String> {
+    let ended = tokio::task::spawn_blocking(move || end_scroll_capture(&app, false)).await
+        .map_err(|e| format!("Task error: {}", e))?;
+    Ok(ended.is_ok())
+}
+
+// The tray handler runs on the event loop; joining the worker and stitching must not
+fn end_scroll_capture_in_background(app: tauri::AppHandle, deliver: bool) {
+    std::thread::spawn(move || {
+        if let Err(e) = end_scroll_capture(&app, deliver) {
+            println!("❌ Could not end scrolling capture: {}", e);
+        }
+    });
+}
+
+// Shared by the overlay's keys and the tray. The overlay closes itself when it hears
+// scroll-capture-finished, whoever ended the capture. Blocks until the worker stops,
+// so it runs off the event loop.
+fn end_scroll_capture(app: &tauri::AppHandle, deliver: bool) -> Result<bool, String> {
+    let capture = app.state::<SharedScrollCapture>().lock().unwrap().take();
+    set_overlay_click_through(app, false);
+    update_scroll_tray(app, false);
+    let capture = capture.ok_or("No scrolling capture in progress")?;
+    
+    let delivered = if deliver {
+        let bounds = capture.bounds().clone();
+        deliver_selection(app, &bounds, None, capture.finish(), &app.state::<SharedCaptureRegistry>(), true).is_ok()
+    } else {
+        println!("❌ Scrolling capture cancelled");
+        false
+    };
+    let _ = app.emit("scroll-capture-finished", serde_json::json!({ "delivered": delivered }));
+    Ok(delivered)
+}
+
+fn update_scroll_tray(app: &tauri::AppHandle, active: bool) {
+    if let Some(menu) = app.try_state::<ScrollMenu>() {
+        let _ = menu.finish.set_enabled(active);
+        let _ = menu.cancel.set_enabled(active);
+    }
+}
+
+#[tauri::command]
+fn get_scroll_capture_status(scroll_capture: tauri::State<'_, SharedScrollCapture>) -> Result<Option<ScrollStatus>, String> {
+    Ok(scroll_capture.lock().unwrap().as_ref().map(|capture| capture.status()))
+}
+
+// Let mouse events through the overlay (to scroll what is under it) or catch them again
+fn set_overlay_click_through(app: &tauri::AppHandle, click_through: bool) {
+    if let Some(window) = app.get_webview_window("overlay") {
+        if let Err(e) = window.set_ignore_cursor_events(click_through) {
+            println!("⚠️ Could not change overlay cursor events: {}", e);
+        }
+    }
+}
+
+// Schedule captures of a region selected in the overlay (overlay coordinates)
+#[tauri::command]
+async fn schedule_screen_selection(
+    app: tauri::AppHandle,
+    bounds: CaptureBounds,
+    mode: ScheduleMode,
+    overlay_manager: tauri::State<'_, SharedOverlayManager>,
+    screenshot_cache: tauri::State<'_, SharedScreenshotCache>
+) -> Result<ScheduleInfo, String> {
+    let source = screenshot_cache.lock().unwrap().source();
+    let bounds = overlay_selection_to_desktop(&app, source.as_ref(), bounds);
+    
+    // Only the overlay goes away; the main window would end up in the capture
+    overlay_manager.lock().unwrap().hide_overlay()?;
+    schedule_capture(&app, Some(bounds), mode)
+}
+
+// Schedule captures of a desktop region, or of the whole desktop without bounds
+#[tauri::command]
+fn start_capture_schedule(app: tauri::AppHandle, bounds: Option<CaptureBounds>, mode: ScheduleMode) -> Result<ScheduleInfo, String> {
+    schedule_capture(&app, bounds, mode)
+}
+
+#[tauri::command]
+fn list_capture_schedules(scheduler: tauri::State<'_, SharedCaptureScheduler>) -> Result<Vec<ScheduleInfo>, String> {
+    Ok(scheduler.lock().unwrap().list())
+}
+
+#[tauri::command]
+fn cancel_capture_schedule(id: u64, scheduler: tauri::State<'_, SharedCaptureScheduler>) -> Result<bool, String> {
+    Ok(scheduler.lock().unwrap().cancel(id))
+}
+
+#[tauri::command]
+fn cancel_all_capture_schedules(scheduler: tauri::State<'_, SharedCaptureScheduler>) -> Result<usize, String> {
+    let cancelled = scheduler.lock().unwrap().cancel_all();
+    println!("⏹️ Cancelled {} scheduled capture(s)", cancelled);
+    Ok(cancelled)
+}
+
+fn schedule_capture(app: &tauri::AppHandle, bounds: Option<CaptureBounds>, mode: ScheduleMode) -> Result<ScheduleInfo, String> {
+    let source = app.state::<SharedScreenshotCache>().lock().unwrap().source();
+    // A delayed capture is a one-off the user is waiting for, so bring up the result
+    let show_result = matches!(mode, ScheduleMode::Delayed { .. });
+    let sink_app = app.clone();
+    let sink: overlay::capture_scheduler::ScheduleSink = Arc::new(move |event| handle_schedule_event(&sink_app, event, show_result));
+    
+    app.state::<SharedCaptureScheduler>().lock().unwrap()
+        .start(CaptureService::with_source(source), bounds, mode, sink)
+}
+
+// Scheduled captures go through the same selection-result pipeline as drag
+// selections; progress is broadcast as capture-schedule events
+fn handle_schedule_event(app: &tauri::AppHandle, event: ScheduleEvent, show_result: bool) {
+    let payload = match event {
+        ScheduleEvent::Countdown { id, remaining_secs } => {
+            println!("⏱️ Scheduled capture {} in {}s", id, remaining_secs);
+            serde_json::json!({ "type": "countdown", "id": id, "remainingSecs": remaining_secs })
+        },
+        ScheduleEvent::Captured { id, sequence, bounds, image, changed_percent } => {
+            println!("📸 Scheduled capture {} #{} ({}x{})", id, sequence, image.width(), image.height());
+            let registry = app.state::<SharedCaptureRegistry>();
+            let delivered = deliver_selection(app, &bounds, None, Ok((bounds.clone(), image)), &registry, false).is_ok();
+            if delivered && show_result {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            serde_json::json!({ "type": "captured", "id": id, "sequence": sequence, "changedPercent": changed_percent })
+        },
+        ScheduleEvent::Failed { id, error } => {
+            println!("❌ Scheduled capture {} failed: {}", id, error);
+            serde_json::json!({ "type": "failed", "id": id, "error": error })
+        },
+        ScheduleEvent::Finished { id, reason } => {
+            println!("⏹️ Scheduled capture {} finished ({:?})", id, reason);
+            serde_json::json!({ "type": "finished", "id": id, "reason": reason })
+        },
+    };
+    let _ = app.emit("capture-schedule", payload);
+}
+
+// Record a region selected in the overlay (overlay coordinates). The overlay is
+// hidden first so it stays out of the clip.
+#[tauri::command]
+async fn record_screen_selection(
+    app: tauri::AppHandle,
+    bounds: CaptureBounds,
+    options: Option<RecordingOptions>,
+    overlay_manager: tauri::State<'_, SharedOverlayManager>,
+    screenshot_cache: tauri::State<'_, SharedScreenshotCache>
+) -> Result<RecordingStatus, CaptureError> {
+    let source = screenshot_cache.lock().unwrap().source();
+    let bounds = overlay_selection_to_desktop(&app, source.as_ref(), bounds);
+    
+    overlay_manager.lock().unwrap().hide_overlay().map_err(CaptureError::Backend)?;
+    begin_recording(&app, bounds, options.unwrap_or_default())
+}
+
+// Record a desktop region
+#[tauri::command]
+fn start_recording(app: tauri::AppHandle, bounds: CaptureBounds, options: Option<RecordingOptions>) -> Result<RecordingStatus, CaptureError> {
+    begin_recording(&app, bounds, options.unwrap_or_default())
+}
+
+#[tauri::command]
+fn pause_recording(app: tauri::AppHandle, recorder: tauri::State<'_, SharedRecorder>) -> Result<RecordingStatus, String> {
+    let status = recorder.lock().unwrap().as_ref().ok_or("No recording in progress")?.pause();
+    update_recording_tray(&app, Some(status.state));
+    Ok(status)
+}
+
+#[tauri::command]
+fn resume_recording(app: tauri::AppHandle, recorder: tauri::State<'_, SharedRecorder>) -> Result<RecordingStatus, String> {
+    let status = recorder.lock().unwrap().as_ref().ok_or("No recording in progress")?.resume();
+    update_recording_tray(&app, Some(status.state));
+    Ok(status)
+}
+
+// Stop, encode and save the recording; the clip is also served by handle. Encoding
+// a long clip takes a while, so it runs off the async runtime
+#[tauri::command]
+async fn stop_recording(app: tauri::AppHandle) -> Result<RecordingResult, String> {
+    tokio::task::spawn_blocking(move || finish_recording(&app, true))
+        .await
+        .map_err(|e| format!("Task error: {}", e))?
+}
+
+#[tauri::command]
+fn cancel_recording(app: tauri::AppHandle, recorder: tauri::State<'_, SharedRecorder>) -> Result<bool, String> {
+    let cancelled = recorder.lock().unwrap().take().is_some();
+    update_recording_tray(&app, None);
+    if cancelled {
+        println!("❌ Recording cancelled");
+    }
+    Ok(cancelled)
+}
+
+#[tauri::command]
+fn get_recording_status(recorder: tauri::State<'_, SharedRecorder>) -> Result<Option<RecordingStatus>, String> {
+    Ok(recorder.lock().unwrap().as_ref().map(|recorder| recorder.status()))
+}
+
+fn begin_recording(app: &tauri::AppHandle, bounds: CaptureBounds, options: RecordingOptions) -> Result<RecordingStatus, CaptureError> {
+    let recorder = app.state::<SharedRecorder>();
+    let mut recorder = recorder.lock().unwrap();
+    if recorder.is_some() {
+        return Err(CaptureError::Backend("A recording is already in progress".to_string()));
+    }
+    
+    let source = app.state::<SharedScreenshotCache>().lock().unwrap().source();
+    let limit_app = app.clone();
+    // Runs on the recording thread, which stopping joins, so stop from another one
+    let on_limit = Box::new(move || finish_recording_in_background(limit_app));
+    let started = ScreenRecorder::start(CaptureService::with_source(source), &bounds, options, on_limit)?;
+    let status = started.status();
+    *recorder = Some(started);
+    drop(recorder);
+    
+    update_recording_tray(app, Some(status.state));
+    let _ = app.emit("recording-started", &status);
+    Ok(status)
+}
+
+fn finish_recording(app: &tauri::AppHandle, serve: bool) -> Result<RecordingResult, String> {
+    let recorder = app.state::<SharedRecorder>().lock().unwrap().take().ok_or("No recording in progress")?;
+    update_recording_tray(app, None);
+    
+    let result = recorder.finish(&app.state::<SharedCaptureRegistry>(), serve, |recording| match save_recording(app, recording) {
+        Ok(path) => {
+            println!("💾 Recording saved to {}", path.display());
+            Some(path.to_string_lossy().into_owned())
+        },
+        Err(e) => {
+            println!("⚠️ Could not save recording: {}", e);
+            None
+        },
+    })?;
+    let _ = app.emit("recording-finished", &result);
+    Ok(result)
+}
+
+// Stops started outside the UI (tray, length limit) report back with a notification.
+// No one would release a served clip, so it is only saved.
+fn finish_recording_in_background(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        use tauri_plugin_notification::NotificationExt;
+        let body = match finish_recording(&app, false) {
+            Ok(result) => result.path.unwrap_or_else(|| "The clip could not be saved to disk".to_string()),
+            Err(e) => {
+                println!("❌ Recording failed: {}", e);
+                e
+            },
+        };
+        let _ = app.notification().builder().title("⏹️ Recording finished").body(body).show();
+    });
+}
+
+// Clips go where the OS keeps videos, e.g. ~/Videos/FrameSense
+fn save_recording(app: &tauri::AppHandle, recording: &Recording) -> Result<PathBuf, String> {
+    let dir = match dirs::video_dir() {
+        Some(videos) => videos.join("FrameSense"),
+        None => app.path().app_data_dir().map_err(|e| e.to_string())?.join("recordings"),
+    };
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let name = format!("Recording {}.{}", chrono::Local::now().format("%Y-%m-%d at %H.%M.%S"), recording.format.extension());
+    let path = dir.join(name);
+    fs::write(&path, &recording.bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+// Recording indicator: tray title/tooltip plus the pause and stop items
+fn update_recording_tray(app: &tauri::AppHandle, state: Option<RecordingState>) {
+    let (title, tooltip) = match state {
+        Some(RecordingState::Paused) => (Some("⏸ REC"), "FrameSense - recording paused"),
+        Some(_) => (Some("🔴 REC"), "FrameSense - recording"),
+        None => (None, "FrameSense"),
+    };
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_title(title);
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+    if let Some(menu) = app.try_state::<RecordingMenu>() {
+        let paused = state == Some(RecordingState::Paused);
+        let _ = menu.pause.set_text(if paused { "Resume Recording" } else { "Pause Recording" });
+        let _ = menu.pause.set_enabled(matches!(state, Some(RecordingState::Recording | RecordingState::Paused)));
+        let _ = menu.stop.set_enabled(state.is_some());
+    }
+}
+
+// Pin a desktop region: its text is read every `interval_secs` and a region-watch
+// event (plus a notification) goes out when it changes or starts matching `pattern`
+#[tauri::command]
+fn add_region_watch(watch: NewRegionWatch, region_watches: tauri::State<'_, SharedRegionWatches>) -> Result<RegionWatch, String> {
+    region_watches.lock().unwrap().add(watch)
+}
+
+// Pin a region selected in the overlay (overlay coordinates)
+#[tauri::command]
+async fn watch_screen_selection(
+    app: tauri::AppHandle,
+    bounds: CaptureBounds,
+    name: Option<String>,
+    pattern: Option<String>,
+    overlay_manager: tauri::State<'_, SharedOverlayManager>,
+    screenshot_cache: tauri::State<'_, SharedScreenshotCache>,
+    region_watches: tauri::State<'_, SharedRegionWatches>
+) -> Result<RegionWatch, String> {
+    let source = screenshot_cache.lock().unwrap().source();
+    let bounds = overlay_selection_to_desktop(&app, source.as_ref(), bounds);
+    
+    // Hide first, or the watch's baseline would be read through the overlay
+    overlay_manager.lock().unwrap().hide_overlay()?;
+    region_watches.lock().unwrap().add(NewRegionWatch { name, bounds, interval_secs: None, pattern, notify: None })
+}
+
+#[tauri::command]
+fn list_region_watches(region_watches: tauri::State<'_, SharedRegionWatches>) -> Result<Vec<WatchStatus>, String> {
+    Ok(region_watches.lock().unwrap().list())
+}
+
+#[tauri::command]
+fn remove_region_watch(id: String, region_watches: tauri::State<'_, SharedRegionWatches>) -> Result<bool, String> {
+    region_watches.lock().unwrap().remove(&id)
+}
+
+// Watch events go to every window as region-watch; alerts also become desktop
+// notifications for watches that asked for them
+fn handle_watch_event(app: &tauri::AppHandle, watch: &RegionWatch, event: WatchEvent) {
+    if let WatchEvent::Changed { text, matched, alert: true, .. } = &event {
+        println!("🔔 Region watch '{}' changed: {}", watch.name, text.replace('\n', " | "));
+        if watch.notify {
+            use tauri_plugin_notification::NotificationExt;
+            let body = match matched {
+                Some(matched) => format!("Matched \"{}\"", matched),
+                None => text.lines().next().unwrap_or("The text is gone").to_string(),
+            };
+            if let Err(e) = app.notification().builder().title(format!("📌 {}", watch.name)).body(body).show() {
+                println!("⚠️ Could not show watch notification: {}", e);
+            }
+        }
+    }
+    let _ = app.emit("region-watch", event);
+}
+
 // Cleanup old overlays periodically
 #[tauri::command]
 fn cleanup_overlay_manager(overlay_manager: tauri::State<'_, SharedOverlayManager>) -> Result<(), String> {
@@ -979,15 +1945,25 @@ async fn debug_coordinates(app: tauri::AppHandle) -> Result<serde_json::Value, S
     
 
     
-    // Screen info
-    if let Ok(screens) = screenshots::Screen::all() {
-        if let Some(screen) = screens.first() {
-            debug_info.insert("screen_size".to_string(), 
+    // Screen layout: desktop position, scale and framebuffer size of every screen
+    if let Ok(layout) = overlay::ScreenLayout::current() {
+        let screens: Vec<serde_json::Value> = layout.screens().iter()
+            .map(|screen| {
+                let (physical_width, physical_height) = overlay::coordinates::physical_size(screen, overlay::coordinates::screen_scale(screen));
                 serde_json::json!({
-                    "width": screen.display_info.width,
-                    "height": screen.display_info.height
-                }));
-        }
+                    "id": screen.id,
+                    "x": screen.x,
+                    "y": screen.y,
+                    "width": screen.width,
+                    "height": screen.height,
+                    "scale_factor": screen.scale_factor,
+                    "physical_width": physical_width,
+                    "physical_height": physical_height,
+                    "is_primary": screen.is_primary
+                })
+            })
+            .collect();
+        debug_info.insert("screens".to_string(), serde_json::json!(screens));
     }
     
     println!("🔍 DEBUG INFO: {}", serde_json::to_string_pretty(&debug_info).unwrap());
@@ -1195,6 +2171,21 @@ fn main() {
     // FAS 3: Initialize screenshot cache for optimization
     let shared_screenshot_cache: SharedScreenshotCache = Arc::new(Mutex::new(ScreenshotCache::new()));
     
+    // Captures handed to the webview by handle instead of as data URLs
+    let shared_capture_registry: SharedCaptureRegistry = Arc::new(Mutex::new(CaptureRegistry::new()));
+    
+    // No scrolling capture until the overlay starts one
+    let shared_scroll_capture: SharedScrollCapture = Arc::new(Mutex::new(None));
+    
+    // Timed and repeating captures, cancellable from the tray
+    let shared_capture_scheduler: SharedCaptureScheduler = Arc::new(Mutex::new(CaptureScheduler::new()));
+    
+    // Nothing is recorded until the overlay or the UI starts a recording
+    let shared_recorder: SharedRecorder = Arc::new(Mutex::new(None));
+    
+    // The clipboard is only opened when a clipboard command needs it
+    let shared_clipboard: SharedClipboard = Arc::new(Mutex::new(ClipboardService::system()));
+    
     // Initialize authentication service with storage path
     let app_data_dir = dirs::home_dir()
         .unwrap_or_else(|| std::path::PathBuf::from("/tmp"))
@@ -1209,7 +2200,50 @@ fn main() {
         .manage(shared_overlay_manager)
         .manage(shared_permission_cache)
         .manage(shared_screenshot_cache)
+        .manage(shared_capture_registry)
+        .manage(shared_scroll_capture)
+        .manage(shared_capture_scheduler)
+        .manage(shared_recorder)
+        .manage(shared_clipboard)
         .manage(shared_auth_service)
+        // framesense://capture/<id> serves registered captures to the webview
+        // Resolved under the lock, copied and answered off the event loop
+        .register_asynchronous_uri_scheme_protocol(overlay::capture_registry::SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            let uri = request.uri().to_string();
+            let origin = request.headers().get("Origin")
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            std::thread::spawn(move || {
+                let registry = app.state::<SharedCaptureRegistry>();
+                let resolved = registry.lock().unwrap().resolve(&uri)
+                    .map(|capture| (capture.bytes.clone(), capture.mime_type));
+                let response = match resolved {
+                    Ok((bytes, mime_type)) => {
+                        let mut builder = tauri::http::Response::builder()
+                            .header("Content-Type", mime_type)
+                            .header("Cache-Control", "private, max-age=31536000, immutable")
+                            .header("Vary", "Origin");
+                        // fetch() from the app's own pages needs CORS; other pages get no header
+                        let dev_url = app.config().build.dev_url.as_ref()
+                            .filter(|_| cfg!(debug_assertions))
+                            .map(|url| url.origin().ascii_serialization());
+                        if let Some(origin) = origin.filter(|origin| overlay::capture_registry::is_app_origin(origin, dev_url.as_deref())) {
+                            builder = builder.header("Access-Control-Allow-Origin", origin);
+                        }
+                        builder.body(bytes.to_vec())
+                    },
+                    Err(e) => {
+                        println!("⚠️ Capture request failed: {}", e);
+                        tauri::http::Response::builder()
+                            .status(404)
+                            .header("Content-Type", "text/plain")
+                            .body(e.into_bytes())
+                    },
+                };
+                responder.respond(response.unwrap_or_else(|_| tauri::http::Response::new(Vec::new())));
+            });
+        })
         .plugin(tauri_plugin_global_shortcut::Builder::new()
             .with_handler(|app, shortcut, event| {
                 println!("🔥 GLOBAL SHORTCUT: {:?} - State: {:?}", shortcut, event.state());
@@ -1264,15 +2298,57 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
         .setup(|app| {
+            // Capture history lives next to app_state.json
+            let data_dir = app.path().app_data_dir()
+                .unwrap_or_else(|_| std::env::temp_dir().join("framesense"));
+            let shared_history: SharedHistory = Arc::new(Mutex::new(HistoryStore::open(data_dir.join("history"))));
+            app.manage(shared_history);
+
+            // Privacy policies (privacy.json); everything is redacted until configured otherwise
+            let shared_privacy: SharedPrivacy = Arc::new(Mutex::new(PrivacyStore::open(data_dir.clone())));
+            app.manage(shared_privacy);
+
+            // Pinned regions (watches.json) resume polling right away
+            let source = app.state::<SharedScreenshotCache>().lock().unwrap().source();
+            let reader: watch::TextReader = Arc::new(|image: &image::RgbaImage| {
+                let image = image::DynamicImage::ImageRgba8(image.clone());
+                Ok(ocr::get_ocr_service()?.extract_text_from_image(&image)?.text)
+            });
+            let sink_app = app.handle().clone();
+            let sink: watch::WatchSink = Arc::new(move |watch: &RegionWatch, event| handle_watch_event(&sink_app, watch, event));
+            let mut region_watches = RegionWatches::new(WatchStore::open(data_dir), source, reader, sink);
+            let resumed = region_watches.start_all();
+            if resumed > 0 {
+                println!("📌 Resumed {} region watch(es)", resumed);
+            }
+            let shared_region_watches: SharedRegionWatches = Arc::new(Mutex::new(region_watches));
+            app.manage(shared_region_watches);
+
             // Continue with existing setup...
             // Create tray menu items inside setup where we have access to app
             let quit_item = MenuItem::with_id(app, "quit", "Quit FrameSense", true, None::<&str>)?;
             let capture_item = MenuItem::with_id(app, "capture", "Start Capture", true, None::<&str>)?;
             let test_item = MenuItem::with_id(app, "test", "Test Command", true, None::<&str>)?;
+            let delayed_menu = Submenu::with_items(app, "Delayed Capture", true, &[
+                &MenuItem::with_id(app, "delay_3", "Capture Desktop in 3s", true, None::<&str>)?,
+                &MenuItem::with_id(app, "delay_5", "Capture Desktop in 5s", true, None::<&str>)?,
+                &MenuItem::with_id(app, "delay_10", "Capture Desktop in 10s", true, None::<&str>)?,
+            ])?;
+            let cancel_schedules_item = MenuItem::with_id(app, "cancel_schedules", "Cancel Scheduled Captures", true, None::<&str>)?;
+            let clipboard_item = MenuItem::with_id(app, "analyze_clipboard", "Analyze Clipboard", true, None::<&str>)?;
+            // Only enabled while recording
+            let pause_recording_item = MenuItem::with_id(app, "pause_recording", "Pause Recording", false, None::<&str>)?;
+            let stop_recording_item = MenuItem::with_id(app, "stop_recording", "Stop Recording", false, None::<&str>)?;
+            app.manage(RecordingMenu { pause: pause_recording_item.clone(), stop: stop_recording_item.clone() });
+            // Only enabled while a scrolling capture runs
+            let finish_scroll_item = MenuItem::with_id(app, "finish_scroll", "Finish Scrolling Capture", false, None::<&str>)?;
+            let cancel_scroll_item = MenuItem::with_id(app, "cancel_scroll", "Cancel Scrolling Capture", false, None::<&str>)?;
+            app.manage(ScrollMenu { finish: finish_scroll_item.clone(), cancel: cancel_scroll_item.clone() });
             
-            let menu = Menu::with_items(app, &[&capture_item, &test_item, &quit_item])?;
+            let menu = Menu::with_items(app, &[&capture_item, &delayed_menu, &cancel_schedules_item, &clipboard_item, &finish_scroll_item, &cancel_scroll_item, &pause_recording_item, &stop_recording_item, &test_item, &quit_item])?;
             
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
+                .tooltip("FrameSense")
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
                 .on_menu_event(|app, event| {
@@ -1293,6 +2369,46 @@ fn main() {
                         "test" => {
                             println!("🧪 Test command triggered");
                         },
+                        id @ ("delay_3" | "delay_5" | "delay_10") => {
+                            let delay_secs = id.trim_start_matches("delay_").parse().unwrap_or(3);
+                            if let Err(e) = schedule_capture(app, None, ScheduleMode::Delayed { delay_secs }) {
+                                println!("❌ Could not schedule capture: {}", e);
+                            }
+                        },
+                        "cancel_schedules" => {
+                            let cancelled = app.state::<SharedCaptureScheduler>().lock().unwrap().cancel_all();
+                            println!("⏹️ Cancelled {} scheduled capture(s) from tray", cancelled);
+                        },
+                        "analyze_clipboard" => {
+                            match analyze_clipboard_contents(app) {
+                                Ok(_) => if let Some(window) = app.get_webview_window("main") {
+                                    let _ = window.show();
+                                    let _ = window.set_focus();
+                                },
+                                Err(e) => println!("❌ Could not analyze clipboard: {}", e),
+                            }
+                        },
+                        "pause_recording" => {
+                            let recorder = app.state::<SharedRecorder>();
+                            let status = recorder.lock().unwrap().as_ref().map(|recorder| match recorder.status().state {
+                                RecordingState::Paused => recorder.resume(),
+                                _ => recorder.pause(),
+                            });
+                            if let Some(status) = status {
+                                update_recording_tray(app, Some(status.state));
+                            }
+                        },
+                        "finish_scroll" => {
+                            println!("📜 Scrolling capture finished from tray");
+                            end_scroll_capture_in_background(app.clone(), true);
+                        },
+                        "cancel_scroll" => {
+                            end_scroll_capture_in_background(app.clone(), false);
+                        },
+                        "stop_recording" => {
+                            println!("⏹️ Recording stopped from tray");
+                            finish_recording_in_background(app.clone());
+                        },
                         _ => {}
                     }
                 })
@@ -1329,7 +2445,10 @@ fn main() {
             test_command,
             test_ocr,
             run_ocr_verification,
+            run_capture_verification,
             extract_text_ocr,
+            get_ocr_languages,
+            process_selection,
             check_permissions,
             test_screen_capture,
             capture_screen_area,
@@ -1342,6 +2461,28 @@ fn main() {
             create_transparent_overlay_optimized,
             close_transparent_overlay_optimized,
             process_screen_selection_optimized,
+            list_capture_windows,
+            process_window_selection_optimized,
+            start_scroll_capture,
+            finish_scroll_capture,
+            cancel_scroll_capture,
+            get_scroll_capture_status,
+            schedule_screen_selection,
+            start_capture_schedule,
+            list_capture_schedules,
+            cancel_capture_schedule,
+            cancel_all_capture_schedules,
+            add_region_watch,
+            watch_screen_selection,
+            list_region_watches,
+            remove_region_watch,
+            record_screen_selection,
+            start_recording,
+            pause_recording,
+            resume_recording,
+            stop_recording,
+            cancel_recording,
+            get_recording_status,
             cleanup_overlay_manager,
             // FAS 2: Optimized permission commands
             check_permissions_cached,
@@ -1352,8 +2493,38 @@ fn main() {
             capture_screen_area_optimized,
             clear_screenshot_cache,
             get_screenshot_cache_stats,
+            set_screenshot_cache_budget,
+            run_capture_benchmark,
             cleanup_screenshot_cache,
             resize_screenshot_buffer,
+            // Capture handle commands
+            capture_screen_area_handle,
+            get_capture_bytes,
+            retain_capture,
+            release_capture,
+            get_capture_registry_stats,
+            // Capture history commands
+            save_to_history,
+            update_history_entry,
+            list_history,
+            search_history,
+            open_history_entry,
+            delete_history_entry,
+            clear_history,
+            get_history_stats,
+            set_history_retention,
+            save_history_annotations,
+            get_history_annotations,
+            export_history_entry,
+            annotate_capture,
+            filter_for_upload,
+            get_privacy_settings,
+            set_privacy_policy,
+            remove_team_privacy_policy,
+            copy_to_clipboard,
+            analyze_clipboard,
+            process_file,
+            run_history_verification,
             // Authentication commands
             login_user,
             logout_user,
@@ -0,0 +1,59 @@
+// Text recognition backends
+// Tesseract is only linked with `--features tesseract-ocr`, so default builds
+// (and GitHub Actions) don't need the native Tesseract/Leptonica libraries.
+use image::DynamicImage;
+
+pub struct Recognition {
+    pub text: String,
+    pub confidence: f32, // 0.0 - 1.0
+    pub tsv: Option<String>, // Word boxes, only when layout was requested
+}
+
+/// Whether this build contains a real recognition engine
+pub fn is_available() -> bool {
+    cfg!(feature = "tesseract-ocr")
+}
+
+/// Name of the compiled-in engine, for status messages
+pub fn engine_name() -> &'static str {
+    if is_available() {
+        "Tesseract"
+    } else {
+        "none"
+    }
+}
+
+#[cfg(feature = "tesseract-ocr")]
+pub fn recognize(img: &DynamicImage, language: &str, with_layout: bool) -> Result<Recognition, String> {
+    use tesseract::Tesseract;
+
+    // Tesseract works on grayscale internally, so hand it 8-bit luma directly
+    let gray = img.to_luma8();
+    let (width, height) = gray.dimensions();
+
+    let mut tess = Tesseract::new(None, Some(language))
+        .map_err(|e| format!("Failed to initialize Tesseract ({}): {}", language, e))?
+        .set_frame(gray.as_raw(), width as i32, height as i32, 1, width as i32)
+        .map_err(|e| format!("Failed to pass image to Tesseract: {}", e))?
+        .recognize()
+        .map_err(|e| format!("Tesseract recognition failed: {}", e))?;
+
+    let text = tess.get_text()
+        .map_err(|e| format!("Failed to read recognized text: {}", e))?;
+
+    // mean_text_conf is 0-100, and -1 when nothing was recognized
+    let confidence = tess.mean_text_conf().clamp(0, 100) as f32 / 100.0;
+
+    let tsv = if with_layout {
+        Some(tess.get_tsv_text(0).map_err(|e| format!("Failed to read word boxes: {}", e))?)
+    } else {
+        None
+    };
+
+    Ok(Recognition { text, confidence, tsv })
+}
+
+#[cfg(not(feature = "tesseract-ocr"))]
+pub fn recognize(_img: &DynamicImage, _language: &str, _with_layout: bool) -> Result<Recognition, String> {
+    Err("OCR engine not available in this build (compile with --features tesseract-ocr)".to_string())
+}
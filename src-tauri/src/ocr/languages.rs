@@ -0,0 +1,210 @@
+// OCR language packs - discovery of installed Tesseract traineddata and
+// per-call language selection (explicit list or automatic detection)
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+pub const AUTO: &str = "auto";
+const TRAINEDDATA_EXT: &str = "traineddata";
+// Packs that are not recognition languages
+const NON_LANGUAGE_PACKS: &[&str] = &["osd", "equ"];
+// Upper bound for the detection pass - every extra pack slows Tesseract down
+const MAX_PROBE_LANGUAGES: usize = 6;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct LanguagePack {
+    pub code: String, // Tesseract code, e.g. "swe"
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LanguageSelection {
+    /// Recognize with exactly these packs, e.g. ["swe", "eng"]
+    Explicit(Vec<String>),
+    /// Detect the language of the capture and pick the matching pack
+    Auto,
+}
+
+impl LanguageSelection {
+    /// Build from codes sent by the frontend; "auto" anywhere selects detection
+    pub fn from_codes(codes: &[String]) -> Result<Self, String> {
+        if codes.iter().any(|code| code.eq_ignore_ascii_case(AUTO)) {
+            return Ok(Self::Auto);
+        }
+
+        let mut unique: Vec<String> = Vec::new();
+        for code in codes {
+            let code = code.trim();
+            if code.is_empty() || code.contains(['+', '/', '\\', '.']) {
+                return Err(format!("Invalid OCR language code: '{}'", code));
+            }
+            if !unique.iter().any(|c| c == code) {
+                unique.push(code.to_string());
+            }
+        }
+
+        if unique.is_empty() {
+            return Err("No OCR languages selected".to_string());
+        }
+        Ok(Self::Explicit(unique))
+    }
+
+    /// Fail early with a readable error instead of a Tesseract init failure
+    pub fn validate(&self, installed: &[LanguagePack]) -> Result<(), String> {
+        // Nothing discovered (e.g. custom TESSDATA layout) - let Tesseract decide
+        if installed.is_empty() {
+            return Ok(());
+        }
+
+        if let Self::Explicit(codes) = self {
+            for code in codes {
+                if !installed.iter().any(|pack| &pack.code == code) {
+                    return Err(format!("OCR language pack '{}' is not installed", code));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tesseract's multi-language syntax, e.g. "swe+eng"
+pub fn tesseract_language_string(codes: &[String]) -> String {
+    codes.join("+")
+}
+
+/// Directories that may contain *.traineddata, most specific first
+pub fn tessdata_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Ok(prefix) = std::env::var("TESSDATA_PREFIX") {
+        let prefix = PathBuf::from(prefix);
+        dirs.push(prefix.join("tessdata"));
+        dirs.push(prefix);
+    }
+
+    for dir in [
+        "/usr/share/tesseract-ocr/5/tessdata",
+        "/usr/share/tesseract-ocr/4.00/tessdata",
+        "/usr/share/tessdata",
+        "/usr/local/share/tessdata",
+        "/opt/homebrew/share/tessdata",
+        "C:\\Program Files\\Tesseract-OCR\\tessdata",
+    ] {
+        dirs.push(PathBuf::from(dir));
+    }
+
+    dirs.retain(|dir| dir.is_dir());
+    dirs
+}
+
+/// All recognition languages found in the tessdata directories
+pub fn installed_languages() -> Vec<LanguagePack> {
+    installed_languages_in(&tessdata_dirs())
+}
+
+/// Recognition languages in `dirs`, sorted by name
+pub fn installed_languages_in(dirs: &[PathBuf]) -> Vec<LanguagePack> {
+    let mut packs: Vec<LanguagePack> = Vec::new();
+
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(TRAINEDDATA_EXT) {
+                continue;
+            }
+            let Some(code) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+
+            // First directory wins, like Tesseract's own lookup
+            if NON_LANGUAGE_PACKS.contains(&code) || packs.iter().any(|p| p.code == code) {
+                continue;
+            }
+
+            packs.push(LanguagePack {
+                code: code.to_string(),
+                name: language_name(code).to_string(),
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+    }
+
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+    packs
+}
+
+/// Languages for the first (detection) pass of automatic mode.
+/// English first since most UI text is English, then the other installed packs.
+pub fn probe_languages(installed: &[LanguagePack], default: &[String]) -> Vec<String> {
+    let mut codes: Vec<String> = default.to_vec();
+    for pack in installed {
+        if codes.len() >= MAX_PROBE_LANGUAGES {
+            break;
+        }
+        if !codes.contains(&pack.code) {
+            codes.push(pack.code.clone());
+        }
+    }
+    codes
+}
+
+/// Detect the language of recognized text, as a Tesseract code
+#[cfg(feature = "tesseract-ocr")]
+pub fn detect_language(text: &str) -> Option<String> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() {
+        return None;
+    }
+
+    // whatlang uses ISO 639-3, which matches Tesseract except for a few packs
+    let code = match info.lang() {
+        whatlang::Lang::Cmn => "chi_sim",
+        whatlang::Lang::Nob => "nor",
+        whatlang::Lang::Pes => "fas",
+        lang => lang.code(),
+    };
+    Some(code.to_string())
+}
+
+#[cfg(not(feature = "tesseract-ocr"))]
+pub fn detect_language(_text: &str) -> Option<String> {
+    None
+}
+
+/// Display name for the settings dialog
+pub fn language_name(code: &str) -> &str {
+    match code {
+        "eng" => "English",
+        "swe" => "Swedish",
+        "nor" => "Norwegian",
+        "dan" => "Danish",
+        "fin" => "Finnish",
+        "isl" => "Icelandic",
+        "deu" => "German",
+        "fra" => "French",
+        "spa" => "Spanish",
+        "ita" => "Italian",
+        "por" => "Portuguese",
+        "nld" => "Dutch",
+        "pol" => "Polish",
+        "ces" => "Czech",
+        "est" => "Estonian",
+        "lav" => "Latvian",
+        "lit" => "Lithuanian",
+        "rus" => "Russian",
+        "ukr" => "Ukrainian",
+        "ell" => "Greek",
+        "tur" => "Turkish",
+        "ara" => "Arabic",
+        "fas" => "Persian",
+        "heb" => "Hebrew",
+        "hin" => "Hindi",
+        "tha" => "Thai",
+        "vie" => "Vietnamese",
+        "jpn" => "Japanese",
+        "kor" => "Korean",
+        "chi_sim" => "Chinese (Simplified)",
+        "chi_tra" => "Chinese (Traditional)",
+        other => other,
+    }
+}
@@ -0,0 +1,250 @@
+// Structured OCR output - blocks, lines and words with bounding boxes
+// Built from Tesseract's TSV output and mapped into capture coordinates
+use serde::{Deserialize, Serialize};
+use crate::overlay::CaptureBounds;
+use super::preprocess::{PreprocessStep, PreprocessedImage};
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct OCRLayout {
+    pub blocks: Vec<OCRBlock>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct OCRBlock {
+    pub bounds: CaptureBounds,
+    pub lines: Vec<OCRLine>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct OCRLine {
+    pub text: String,
+    pub bounds: CaptureBounds,
+    pub words: Vec<OCRWord>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct OCRWord {
+    pub text: String,
+    pub bounds: CaptureBounds,
+    pub confidence: f32, // 0.0 - 1.0
+}
+
+/// Maps image pixels back to the coordinates of the captured region
+/// (the capture may be larger than its bounds on HiDPI screens)
+#[derive(Clone, Debug)]
+pub struct CoordinateMapping {
+    origin_x: i32,
+    origin_y: i32,
+    scale_x: f64,
+    scale_y: f64,
+    deskew: Option<Rotation>, // Undone before scaling
+}
+
+// The rotation `preprocess::rotate` applied, as it maps output pixels to input ones
+#[derive(Clone, Debug)]
+struct Rotation {
+    sin: f64,
+    cos: f64,
+    center_x: f64,
+    center_y: f64,
+}
+
+impl CoordinateMapping {
+    /// Pixels of a `image_width`x`image_height` image that shows `bounds`
+    pub fn for_capture(bounds: &CaptureBounds, image_width: u32, image_height: u32) -> Self {
+        let scale = |bound: u32, image: u32| if bound == 0 || image == 0 { 1.0 } else { bound as f64 / image as f64 };
+
+        Self {
+            origin_x: bounds.x,
+            origin_y: bounds.y,
+            scale_x: scale(bounds.width, image_width),
+            scale_y: scale(bounds.height, image_height),
+            deskew: None,
+        }
+    }
+
+    /// Pixels of a preprocessed capture of `bounds`: the deskew rotation is undone,
+    /// then the upscale. A rotated box maps to the upright rectangle around it.
+    pub fn for_preprocessed(bounds: &CaptureBounds, processed: &PreprocessedImage) -> Self {
+        let (width, height) = (processed.image.width(), processed.image.height());
+        let mut mapping = Self::for_capture(bounds, width, height);
+        // Rotation keeps the image size, so the upscaled image is as large as the result
+        mapping.deskew = processed.steps.iter().find_map(|step| match step {
+            PreprocessStep::Deskewed { angle_degrees } => {
+                let (sin, cos) = (*angle_degrees as f64).to_radians().sin_cos();
+                Some(Rotation { sin, cos, center_x: width as f64 / 2.0, center_y: height as f64 / 2.0 })
+            },
+            _ => None,
+        });
+        mapping
+    }
+
+    pub fn map(&self, left: i32, top: i32, width: i32, height: i32) -> CaptureBounds {
+        let (left, top) = (left as f64, top as f64);
+        let (right, bottom) = (left + width.max(0) as f64, top + height.max(0) as f64);
+        let (left, top, right, bottom) = match &self.deskew {
+            Some(rotation) => {
+                let corners = [(left, top), (right, top), (left, bottom), (right, bottom)].map(|(x, y)| rotation.apply(x, y));
+                corners.iter().fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |(x1, y1, x2, y2), &(x, y)| {
+                    (x1.min(x), y1.min(y), x2.max(x), y2.max(y))
+                })
+            },
+            None => (left, top, right, bottom),
+        };
+        let x1 = left * self.scale_x;
+        let y1 = top * self.scale_y;
+        let x2 = right * self.scale_x;
+        let y2 = bottom * self.scale_y;
+
+        CaptureBounds {
+            x: self.origin_x + x1.floor() as i32,
+            y: self.origin_y + y1.floor() as i32,
+            width: (x2.ceil() - x1.floor()).max(0.0) as u32,
+            height: (y2.ceil() - y1.floor()).max(0.0) as u32,
+        }
+    }
+}
+
+impl Rotation {
+    fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let (dx, dy) = (x - self.center_x, y - self.center_y);
+        (dx * self.cos - dy * self.sin + self.center_x, dx * self.sin + dy * self.cos + self.center_y)
+    }
+}
+
+// Tesseract TSV levels
+const LEVEL_BLOCK: u32 = 2;
+const LEVEL_LINE: u32 = 4;
+const LEVEL_WORD: u32 = 5;
+
+/// Parse Tesseract TSV output (level, page, block, par, line, word, left, top, width, height, conf, text)
+pub fn parse_tsv(tsv: &str, mapping: &CoordinateMapping) -> OCRLayout {
+    let mut layout = OCRLayout::default();
+    // Lines are keyed by (block, paragraph, line) since line numbers restart per paragraph
+    let mut block_ids: Vec<u32> = Vec::new();
+    let mut line_ids: Vec<(u32, u32, u32)> = Vec::new();
+
+    for row in tsv.lines() {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 11 {
+            continue;
+        }
+
+        // Skips the header row as well
+        let nums: Vec<i32> = match cols[..10].iter().map(|c| c.trim().parse::<i32>()).collect() {
+            Ok(nums) => nums,
+            Err(_) => continue,
+        };
+        let level = nums[0] as u32;
+        let (block, par, line) = (nums[2] as u32, nums[3] as u32, nums[4] as u32);
+        let bounds = mapping.map(nums[6], nums[7], nums[8], nums[9]);
+
+        match level {
+            LEVEL_BLOCK => {
+                block_ids.push(block);
+                layout.blocks.push(OCRBlock { bounds, lines: Vec::new() });
+            },
+            LEVEL_LINE => {
+                let Some(block_index) = block_ids.iter().position(|id| *id == block) else { continue };
+                line_ids.push((block, par, line));
+                layout.blocks[block_index].lines.push(OCRLine {
+                    text: String::new(),
+                    bounds,
+                    words: Vec::new(),
+                });
+            },
+            LEVEL_WORD => {
+                let text = cols.get(11).map(|t| t.trim()).unwrap_or("");
+                if text.is_empty() {
+                    continue;
+                }
+                let Some(block_index) = block_ids.iter().position(|id| *id == block) else { continue };
+                let Some(line_index) = line_ids.iter()
+                    .filter(|(b, _, _)| *b == block)
+                    .position(|key| *key == (block, par, line)) else { continue };
+
+                let confidence = cols[10].trim().parse::<f32>().unwrap_or(0.0).clamp(0.0, 100.0) / 100.0;
+                layout.blocks[block_index].lines[line_index].words.push(OCRWord {
+                    text: text.to_string(),
+                    bounds,
+                    confidence,
+                });
+            },
+            _ => {}
+        }
+    }
+
+    // Fill line text and drop empty lines/blocks (Tesseract reports image regions too)
+    for block in layout.blocks.iter_mut() {
+        for line in block.lines.iter_mut() {
+            line.text = line.words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+        }
+        block.lines.retain(|line| !line.words.is_empty());
+    }
+    layout.blocks.retain(|block| !block.lines.is_empty());
+
+    layout
+}
+
+// The parser runs without Tesseract; the privacy filter depends on it in every build
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+
+    fn bounds(x: i32, y: i32, width: u32, height: u32) -> CaptureBounds {
+        CaptureBounds { x, y, width, height }
+    }
+
+    #[test]
+    fn parses_blocks_lines_and_words_into_capture_coordinates() {
+        // A 400x200 image of a 200x100 region at (100, 50): half a unit per pixel
+        let tsv = [
+            HEADER,
+            "1\t1\t0\t0\t0\t0\t0\t0\t400\t200\t-1\t",
+            "2\t1\t1\t0\t0\t0\t20\t10\t300\t120\t-1\t",
+            "3\t1\t1\t1\t0\t0\t20\t10\t300\t40\t-1\t",
+            "4\t1\t1\t1\t1\t0\t20\t10\t300\t40\t-1\t",
+            "5\t1\t1\t1\t1\t1\t20\t10\t120\t40\t96.5\tHello",
+            "5\t1\t1\t1\t1\t2\t161\t11\t159\t39\t90\tWorld",
+            "5\t1\t1\t1\t1\t3\t330\t10\t10\t40\t-1\t ",
+            // Line numbers restart per paragraph
+            "3\t1\t1\t2\t0\t0\t20\t80\t200\t50\t-1\t",
+            "4\t1\t1\t2\t1\t0\t20\t80\t200\t50\t-1\t",
+            "5\t1\t1\t2\t1\t1\t20\t80\t200\t50\t-1\tagain",
+            // An image region: a block without words
+            "2\t1\t2\t0\t0\t0\t0\t150\t400\t50\t-1\t",
+        ].join("\n");
+        let mapping = CoordinateMapping::for_capture(&bounds(100, 50, 200, 100), 400, 200);
+        let layout = parse_tsv(&tsv, &mapping);
+
+        assert_eq!(layout.blocks.len(), 1);
+        let block = &layout.blocks[0];
+        assert_eq!(block.bounds, bounds(110, 55, 150, 60));
+        assert_eq!(block.lines.len(), 2);
+
+        let line = &block.lines[0];
+        assert_eq!(line.text, "Hello World");
+        assert_eq!(line.bounds, bounds(110, 55, 150, 20));
+        assert_eq!(line.words[0].bounds, bounds(110, 55, 60, 20));
+        assert!((line.words[0].confidence - 0.965).abs() < 1e-6);
+        // Odd pixels widen the box outward rather than cutting glyphs off
+        assert_eq!(line.words[1].bounds, bounds(180, 55, 80, 20));
+        assert!((line.words[1].confidence - 0.9).abs() < 1e-6);
+
+        let again = &block.lines[1];
+        assert_eq!((again.text.as_str(), again.words.len()), ("again", 1));
+        // conf -1 (nothing to score) reads as zero confidence
+        assert_eq!(again.words[0].confidence, 0.0);
+    }
+
+    #[test]
+    fn without_bounds_boxes_stay_in_image_pixels() {
+        let tsv = [HEADER, "2\t1\t1\t0\t0\t0\t5\t6\t70\t8\t-1\t", "4\t1\t1\t1\t1\t0\t5\t6\t70\t8\t-1\t", "5\t1\t1\t1\t1\t1\t5\t6\t70\t8\t88\tword"].join("\n");
+        let mapping = CoordinateMapping::for_capture(&bounds(0, 0, 80, 20), 80, 20);
+        let layout = parse_tsv(&tsv, &mapping);
+        assert_eq!(layout.blocks[0].lines[0].words[0].bounds, bounds(5, 6, 70, 8));
+        assert!(parse_tsv("garbage\nrows", &mapping).blocks.is_empty());
+    }
+}
@@ -1,58 +1,205 @@
-// OCR module - simplified version for GitHub Actions compatibility
-// use tesseract::Tesseract; // Disabled for GitHub Actions
+// OCR module - Tesseract-backed text recognition (see engine.rs for the feature gate)
 use image::{DynamicImage, GenericImageView};
 use base64::Engine;
+use crate::overlay::CaptureBounds;
+use std::sync::OnceLock;
 
-pub struct OCRService;
+mod engine;
+pub mod layout;
+pub mod languages;
+pub mod preprocess;
+
+pub use layout::{OCRLayout, CoordinateMapping};
+pub use languages::{LanguagePack, LanguageSelection};
+pub use preprocess::{PreprocessOptions, PreprocessStep};
+pub use engine::is_available as engine_available;
+
+const DEFAULT_LANGUAGE: &str = "eng";
+
+// Service shared by the Tauri commands and the selection pipeline
+static OCR_SERVICE: OnceLock<OCRService> = OnceLock::new();
+
+/// Get the global OCR service instance
+pub fn get_ocr_service() -> Result<&'static OCRService, String> {
+    if let Some(service) = OCR_SERVICE.get() {
+        return Ok(service);
+    }
+
+    let service = OCRService::new()?;
+    println!("✅ OCR service initialized successfully");
+    Ok(OCR_SERVICE.get_or_init(|| service))
+}
+
+#[derive(Clone)]
+pub struct OCRService {
+    languages: LanguageSelection,
+    preprocessing: PreprocessOptions,
+}
 
 impl OCRService {
     pub fn new() -> Result<Self, String> {
-        // Simplified version without Tesseract dependency
-        Ok(Self)
+        Ok(Self {
+            languages: LanguageSelection::Explicit(vec![DEFAULT_LANGUAGE.to_string()]),
+            preprocessing: PreprocessOptions::default(),
+        })
+    }
+
+    /// Same service recognizing with `selection` instead of the default language
+    pub fn with_languages(&self, selection: LanguageSelection) -> Result<Self, String> {
+        selection.validate(&languages::installed_languages())?;
+        Ok(Self { languages: selection, ..self.clone() })
+    }
+
+    /// Same service with a different preprocessing pipeline
+    pub fn with_preprocessing(&self, options: PreprocessOptions) -> Self {
+        Self { preprocessing: options, ..self.clone() }
     }
-    
+
     pub fn extract_text(&self, image_data: &str) -> Result<OCRResult, String> {
+        let img = Self::decode_image(image_data)?;
+        self.extract_text_from_image(&img)
+    }
+
+    /// Extract text plus block/line/word boxes. Boxes are in the coordinates of
+    /// `bounds` when given (the region the image was captured from), else image pixels.
+    pub fn extract_layout(&self, image_data: &str, bounds: Option<&CaptureBounds>) -> Result<OCRResult, String> {
+        let img = Self::decode_image(image_data)?;
+        self.extract_layout_from_image(&img, bounds)
+    }
+
+    /// Run recognition on an already decoded image
+    pub fn extract_text_from_image(&self, img: &DynamicImage) -> Result<OCRResult, String> {
+        self.recognize(img, false, None)
+    }
+
+    pub fn extract_layout_from_image(&self, img: &DynamicImage, bounds: Option<&CaptureBounds>) -> Result<OCRResult, String> {
+        self.recognize(img, true, bounds)
+    }
+
+    fn recognize(&self, img: &DynamicImage, with_layout: bool, bounds: Option<&CaptureBounds>) -> Result<OCRResult, String> {
+        // Check image dimensions
+        let (width, height) = img.dimensions();
+        if width < 10 || height < 10 {
+            return Err(format!("Image too small for OCR: {}x{} pixels", width, height));
+        }
+
+        println!("📏 Image dimensions: {}x{} pixels", width, height);
+
+        let processed = preprocess::preprocess(img, &self.preprocessing);
+        let img = &processed.image;
+        if !processed.steps.is_empty() {
+            println!("🧼 Preprocessing: {:?}", processed.steps);
+        }
+
+        let (recognition, languages, detected_language) = match &self.languages {
+            LanguageSelection::Explicit(codes) => {
+                let language = languages::tesseract_language_string(codes);
+                (engine::recognize(img, &language, with_layout)?, codes.clone(), None)
+            },
+            LanguageSelection::Auto => Self::recognize_auto(img, with_layout)?,
+        };
+        let text = recognition.text.trim().to_string();
+
+        // Boxes come back in preprocessed pixels; undo the deskew and the upscale so
+        // they land on the capture
+        let layout = recognition.tsv.as_ref().map(|tsv| {
+            let target = bounds.cloned().unwrap_or(CaptureBounds { x: 0, y: 0, width, height });
+            layout::parse_tsv(tsv, &CoordinateMapping::for_preprocessed(&target, &processed))
+        });
+
+        Ok(OCRResult {
+            has_text: !text.is_empty(),
+            text,
+            confidence: recognition.confidence,
+            layout,
+            languages,
+            detected_language,
+            preprocessing: processed.steps,
+        })
+    }
+
+    /// Automatic mode: a probe pass over the installed packs, language detection on
+    /// the probe text, then a second pass with the detected pack (plus English)
+    fn recognize_auto(img: &DynamicImage, with_layout: bool) -> Result<(engine::Recognition, Vec<String>, Option<String>), String> {
+        let installed = languages::installed_languages();
+        let probe = languages::probe_languages(&installed, &[DEFAULT_LANGUAGE.to_string()]);
+        println!("🌍 Auto language probe: {}", probe.join("+"));
+
+        let first = engine::recognize(img, &languages::tesseract_language_string(&probe), with_layout)?;
+        let detected = languages::detect_language(&first.text);
+
+        match detected {
+            Some(code) if installed.iter().any(|pack| pack.code == code) => {
+                println!("🌍 Detected language: {}", code);
+                let mut codes = vec![code.clone()];
+                if code != DEFAULT_LANGUAGE && installed.iter().any(|pack| pack.code == DEFAULT_LANGUAGE) {
+                    codes.push(DEFAULT_LANGUAGE.to_string());
+                }
+
+                // The dedicated model usually wins, but keep whichever pass was more confident
+                let second = engine::recognize(img, &languages::tesseract_language_string(&codes), with_layout)?;
+                if second.confidence >= first.confidence {
+                    Ok((second, codes, Some(code)))
+                } else {
+                    Ok((first, probe, Some(code)))
+                }
+            },
+            _ => {
+                println!("🌍 No reliable language detected, keeping probe result");
+                Ok((first, probe, detected))
+            }
+        }
+    }
+
+    /// Decode a base64 image (with or without data URL prefix)
+    pub fn decode_image(image_data: &str) -> Result<DynamicImage, String> {
         // Remove data:image/png;base64, prefix if exists
         let base64_data = if image_data.starts_with("data:image") {
             image_data.split(',').nth(1).unwrap_or(image_data)
         } else {
             image_data
         };
-        
+
         // Decode base64 image to verify it's valid
         let image_bytes = base64::engine::general_purpose::STANDARD
             .decode(base64_data)
             .map_err(|e| format!("Failed to decode image: {}", e))?;
-        
+
         // Load image to verify it's valid
-        let img = image::load_from_memory(&image_bytes)
-            .map_err(|e| format!("Failed to load image: {}", e))?;
-        
-        // Check image dimensions
-        let (width, height) = img.dimensions();
-        if width < 10 || height < 10 {
-            return Err(format!("Image too small for OCR: {}x{} pixels", width, height));
-        }
-        
-        println!("📏 Image dimensions: {}x{} pixels", width, height);
-        
-        // Return placeholder result (Tesseract disabled for GitHub Actions)
-        Ok(OCRResult {
-            text: "OCR functionality temporarily disabled for this build".to_string(),
-            confidence: 0.0,
-            has_text: false,
-        })
+        image::load_from_memory(&image_bytes)
+            .map_err(|e| format!("Failed to load image: {}", e))
     }
-    
-    // Legacy test function - simplified version
+
+    // Quick engine check - recognizes a tiny generated image
     pub fn test_ocr() -> Result<String, String> {
-        Ok("✅ OCR service ready (Tesseract disabled for GitHub Actions)".to_string())
+        if !engine::is_available() {
+            return Err("OCR engine not compiled in (build with --features tesseract-ocr)".to_string());
+        }
+
+        let service = Self::new()?;
+        let blank = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 32, image::Luma([255])));
+        service.extract_text_from_image(&blank)?;
+
+        let installed = languages::installed_languages();
+        Ok(format!("✅ OCR service ready ({}, {} language pack(s) installed)", engine::engine_name(), installed.len()))
     }
-    
-    // Simple integration test function - simplified version
-    pub fn run_integration_test() -> Result<String, String> {
-        println!("🧪 Running simplified OCR test...");
-        Ok("🎉 OCR service test passed (simplified version)!".to_string())
+
+    // Integration test - runs the engine on a known image
+    pub fn run_integration_test(png_bytes: &[u8], expected: &str) -> Result<String, String> {
+        println!("🧪 Running OCR integration test...");
+
+        let service = Self::new()?;
+        let img = image::load_from_memory(png_bytes)
+            .map_err(|e| format!("Failed to load test image: {}", e))?;
+        let result = service.extract_text_from_image(&img)?;
+
+        let normalize = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if normalize(&result.text) != normalize(expected) {
+            return Err(format!("Expected '{}', got '{}' ({:.0}% confidence)",
+                               expected.trim(), result.text, result.confidence * 100.0));
+        }
+
+        Ok(format!("🎉 Recognized '{}' ({:.0}% confidence)", result.text, result.confidence * 100.0))
     }
 }
 
@@ -61,4 +208,16 @@ pub struct OCRResult {
     pub text: String,
     pub confidence: f32,
     pub has_text: bool,
-} 
\ No newline at end of file
+    // Opt-in word/line/block boxes (see OCRService::extract_layout)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<OCRLayout>,
+    // Tesseract packs used for this result, e.g. ["swe", "eng"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    // Set in automatic language mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<String>,
+    // Preprocessing steps applied before recognition
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preprocessing: Vec<PreprocessStep>,
+}
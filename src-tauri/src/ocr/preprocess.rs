@@ -0,0 +1,298 @@
+// Image preprocessing before OCR
+// Screen captures differ from scanned pages: small UI fonts, dark themes and
+// low-DPI displays. Each step is optional and reported back in the OCR result.
+use image::{imageops, DynamicImage, GrayImage, Luma};
+use serde::{Deserialize, Serialize};
+
+// Effective pixel density (relative to a 1x display) Tesseract reads best at
+const TARGET_DENSITY: f32 = 2.0;
+const MAX_UPSCALE: f32 = 3.0;
+// Largest skew we try to correct, and the search step, in degrees
+const MAX_SKEW_DEGREES: f32 = 10.0;
+const SKEW_STEP_DEGREES: f32 = 0.5;
+// Dark pixels sampled for skew estimation (keeps big captures fast)
+const MAX_SKEW_SAMPLES: usize = 20_000;
+// Bradley threshold: a pixel is ink if it is this much darker than its neighborhood
+const THRESHOLD_SENSITIVITY: f32 = 0.15;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct PreprocessOptions {
+    pub grayscale: bool,
+    /// Invert light-on-dark captures (dark mode) to dark-on-light
+    pub invert_dark: bool,
+    /// Upscale low-density captures, see `scale_factor`
+    pub upscale: bool,
+    /// Display scale factor of the captured screen (ScreenInfo::scale_factor), 1.0 when unknown
+    pub scale_factor: Option<f32>,
+    pub deskew: bool,
+    pub adaptive_threshold: bool,
+}
+
+impl Default for PreprocessOptions {
+    fn default() -> Self {
+        Self {
+            grayscale: true,
+            invert_dark: true,
+            upscale: true,
+            scale_factor: None,
+            deskew: true,
+            adaptive_threshold: true,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PreprocessStep {
+    Grayscale,
+    Inverted { background_luma: u8 },
+    Upscaled { factor: f32 },
+    Deskewed { angle_degrees: f32 },
+    Thresholded { window: u32 },
+}
+
+pub struct PreprocessedImage {
+    pub image: DynamicImage,
+    pub steps: Vec<PreprocessStep>,
+}
+
+/// Run the enabled steps: grayscale → invert → upscale → deskew → threshold
+pub fn preprocess(img: &DynamicImage, options: &PreprocessOptions) -> PreprocessedImage {
+    let mut steps = Vec::new();
+
+    // Every later step works on luma, so they imply grayscale
+    let needs_gray = options.grayscale || options.invert_dark || options.upscale
+        || options.deskew || options.adaptive_threshold;
+    if !needs_gray {
+        return PreprocessedImage { image: img.clone(), steps };
+    }
+
+    let mut gray = img.to_luma8();
+    steps.push(PreprocessStep::Grayscale);
+
+    if options.invert_dark {
+        let background = background_luma(&gray);
+        if background < 128 {
+            imageops::invert(&mut gray);
+            steps.push(PreprocessStep::Inverted { background_luma: background });
+        }
+    }
+
+    if options.upscale {
+        let factor = upscale_factor(options.scale_factor.unwrap_or(1.0));
+        if factor > 1.0 {
+            let width = (gray.width() as f32 * factor).round() as u32;
+            let height = (gray.height() as f32 * factor).round() as u32;
+            gray = imageops::resize(&gray, width, height, imageops::FilterType::CatmullRom);
+            steps.push(PreprocessStep::Upscaled { factor });
+        }
+    }
+
+    if options.deskew {
+        let angle = estimate_skew(&gray);
+        if angle.abs() >= SKEW_STEP_DEGREES {
+            gray = rotate(&gray, angle);
+            steps.push(PreprocessStep::Deskewed { angle_degrees: angle });
+        }
+    }
+
+    if options.adaptive_threshold {
+        let window = threshold_window(gray.width(), gray.height());
+        gray = adaptive_threshold(&gray, window);
+        steps.push(PreprocessStep::Thresholded { window });
+    }
+
+    PreprocessedImage { image: DynamicImage::ImageLuma8(gray), steps }
+}
+
+/// How much to enlarge a capture from a screen with `scale_factor`.
+/// Retina captures already have enough pixels per glyph; 1x captures get doubled.
+pub fn upscale_factor(scale_factor: f32) -> f32 {
+    if !scale_factor.is_finite() || scale_factor <= 0.0 {
+        return 1.0;
+    }
+
+    let factor = (TARGET_DENSITY / scale_factor).clamp(1.0, MAX_UPSCALE);
+    // Round to quarter steps so small fractional differences don't resample
+    let factor = (factor * 4.0).round() / 4.0;
+    if factor < 1.25 { 1.0 } else { factor }
+}
+
+/// Most common luma value, in 8 buckets - the background of a UI capture
+fn background_luma(gray: &GrayImage) -> u8 {
+    let mut buckets = [0u64; 8];
+    let mut sums = [0u64; 8];
+    for pixel in gray.pixels() {
+        let bucket = (pixel[0] / 32) as usize;
+        buckets[bucket] += 1;
+        sums[bucket] += pixel[0] as u64;
+    }
+
+    let (bucket, count) = buckets.iter().enumerate()
+        .max_by_key(|(index, count)| (**count, std::cmp::Reverse(*index)))
+        .map(|(index, count)| (index, *count))
+        .unwrap_or((7, 0));
+    if count == 0 {
+        return 255;
+    }
+    (sums[bucket] / count) as u8
+}
+
+/// Skew of the text lines in degrees (positive when lines slope down to the right),
+/// found by maximizing the variance of the row projection of ink pixels
+pub fn estimate_skew(gray: &GrayImage) -> f32 {
+    let threshold = otsu_level(gray);
+    let (width, height) = gray.dimensions();
+
+    let mut ink: Vec<(f32, f32)> = Vec::new();
+    for (x, y, pixel) in gray.enumerate_pixels() {
+        if pixel[0] < threshold {
+            ink.push((x as f32 - width as f32 / 2.0, y as f32 - height as f32 / 2.0));
+        }
+    }
+    if ink.len() < 50 {
+        return 0.0;
+    }
+    if ink.len() > MAX_SKEW_SAMPLES {
+        let stride = ink.len() / MAX_SKEW_SAMPLES + 1;
+        ink = ink.into_iter().step_by(stride).collect();
+    }
+
+    let diagonal = (width as f32).hypot(height as f32);
+    let bins = diagonal.ceil() as usize + 1;
+    let mut profile = vec![0u32; bins];
+
+    let mut best_angle = 0.0;
+    let mut best_score = f64::MIN;
+    let steps = (MAX_SKEW_DEGREES / SKEW_STEP_DEGREES).round() as i32;
+    for step in -steps..=steps {
+        let angle = step as f32 * SKEW_STEP_DEGREES;
+        let (sin, cos) = angle.to_radians().sin_cos();
+
+        profile.iter_mut().for_each(|bin| *bin = 0);
+        for &(x, y) in &ink {
+            // Row of this pixel after undoing a rotation by `angle`
+            let row = y * cos - x * sin + diagonal / 2.0;
+            if row >= 0.0 && (row as usize) < bins {
+                profile[row as usize] += 1;
+            }
+        }
+
+        let score: f64 = profile.iter().map(|&count| (count as f64) * (count as f64)).sum();
+        // Prefer the smallest angle on ties so straight text stays untouched
+        if score > best_score || (score == best_score && angle.abs() < f32::abs(best_angle)) {
+            best_score = score;
+            best_angle = angle;
+        }
+    }
+
+    best_angle
+}
+
+/// Rotate so that text at `angle_degrees` becomes horizontal (bilinear, white fill)
+pub fn rotate(gray: &GrayImage, angle_degrees: f32) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let (sin, cos) = angle_degrees.to_radians().sin_cos();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+    GrayImage::from_fn(width, height, |x, y| {
+        let dx = x as f32 - cx;
+        let dy = y as f32 - cy;
+        let sx = dx * cos - dy * sin + cx;
+        let sy = dx * sin + dy * cos + cy;
+        Luma([sample_bilinear(gray, sx, sy)])
+    })
+}
+
+fn sample_bilinear(gray: &GrayImage, x: f32, y: f32) -> u8 {
+    let (width, height) = gray.dimensions();
+    if x < 0.0 || y < 0.0 || x > (width - 1) as f32 || y > (height - 1) as f32 {
+        return 255;
+    }
+
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let p = |px: u32, py: u32| gray.get_pixel(px, py)[0] as f32;
+    let top = p(x0, y0) * (1.0 - fx) + p(x1, y0) * fx;
+    let bottom = p(x0, y1) * (1.0 - fx) + p(x1, y1) * fx;
+    (top * (1.0 - fy) + bottom * fy).round() as u8
+}
+
+/// Global Otsu threshold, used for ink detection during skew estimation
+fn otsu_level(gray: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in gray.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+
+    let total = gray.pixels().len() as f64;
+    let sum_all: f64 = histogram.iter().enumerate().map(|(v, &c)| v as f64 * c as f64).sum();
+
+    let (mut weight_bg, mut sum_bg) = (0.0, 0.0);
+    let (mut best_level, mut best_variance) = (128u8, 0.0);
+    for (level, &count) in histogram.iter().enumerate() {
+        weight_bg += count as f64;
+        if weight_bg == 0.0 {
+            continue;
+        }
+        let weight_fg = total - weight_bg;
+        if weight_fg == 0.0 {
+            break;
+        }
+
+        sum_bg += level as f64 * count as f64;
+        let mean_bg = sum_bg / weight_bg;
+        let mean_fg = (sum_all - sum_bg) / weight_fg;
+        let variance = weight_bg * weight_fg * (mean_bg - mean_fg).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_level = level as u8;
+        }
+    }
+
+    best_level.saturating_add(1)
+}
+
+/// Odd window size of roughly 1/16 of the shorter side
+fn threshold_window(width: u32, height: u32) -> u32 {
+    let window = (width.min(height) / 16).clamp(15, 101);
+    window | 1
+}
+
+/// Bradley adaptive threshold using an integral image
+pub fn adaptive_threshold(gray: &GrayImage, window: u32) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let stride = width as usize + 1;
+
+    // integral[(y + 1) * stride + (x + 1)] = sum of pixels above and left of (x, y) inclusive
+    let mut integral = vec![0u64; stride * (height as usize + 1)];
+    for y in 0..height as usize {
+        let mut row_sum = 0u64;
+        for x in 0..width as usize {
+            row_sum += gray.get_pixel(x as u32, y as u32)[0] as u64;
+            integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row_sum;
+        }
+    }
+
+    let half = (window / 2) as i64;
+    GrayImage::from_fn(width, height, |x, y| {
+        let x1 = (x as i64 - half).max(0) as usize;
+        let y1 = (y as i64 - half).max(0) as usize;
+        let x2 = (x as i64 + half + 1).min(width as i64) as usize;
+        let y2 = (y as i64 + half + 1).min(height as i64) as usize;
+
+        let area = ((x2 - x1) * (y2 - y1)) as f32;
+        let sum = integral[y2 * stride + x2] + integral[y1 * stride + x1]
+            - integral[y1 * stride + x2] - integral[y2 * stride + x1];
+
+        let value = gray.get_pixel(x, y)[0] as f32;
+        if value * area <= sum as f32 * (1.0 - THRESHOLD_SENSITIVITY) {
+            Luma([0])
+        } else {
+            Luma([255])
+        }
+    })
+}
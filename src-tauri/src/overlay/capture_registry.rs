@@ -0,0 +1,167 @@
+// Encoded captures held by the backend and handed to the webview by handle
+// The webview loads `framesense://capture/<id>` (or fetches raw IPC bytes) instead of
+// receiving base64 data URLs inside JSON. Entries are reference counted and dropped
+// once the last holder releases them.
+use super::image_encoding::EncodedBytes;
+use image::{DynamicImage, RgbaImage};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SCHEME: &str = "framesense";
+const CAPTURE_PATH: &str = "capture";
+
+/// What the webview gets instead of the image itself
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CaptureHandle {
+    pub id: String,
+    pub url: String, // Loadable from <img>, fetch() etc.
+    pub mime_type: &'static str,
+    pub byte_size: usize,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct StoredCapture {
+    pub bytes: Arc<[u8]>, // Cheap to clone for readers outside the lock
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+    refs: usize,
+}
+
+impl StoredCapture {
+    /// Decoded pixels (raw RGBA captures carry no header, so their size comes from the entry)
+    pub fn decode(&self) -> Result<DynamicImage, String> {
+        if self.mime_type == "application/octet-stream" {
+            return RgbaImage::from_raw(self.width, self.height, self.bytes.to_vec())
+                .map(DynamicImage::ImageRgba8)
+                .ok_or_else(|| format!("Raw capture is not {}x{} RGBA", self.width, self.height));
+        }
+        image::load_from_memory(&self.bytes).map_err(|e| format!("Failed to decode capture: {}", e))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RegistryStats {
+    pub captures: usize,
+    pub references: usize,
+    pub bytes: usize,
+}
+
+#[derive(Default)]
+pub struct CaptureRegistry {
+    entries: HashMap<String, StoredCapture>,
+    next_id: u64,
+    bytes: usize,
+}
+
+impl CaptureRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `capture` and return its handle; the caller holds the one reference
+    pub fn insert(&mut self, capture: EncodedBytes) -> CaptureHandle {
+        // Unique within the process and not reused after a restart
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        self.next_id += 1;
+        let id = format!("{}-{}", now, self.next_id);
+
+        let handle = CaptureHandle {
+            url: capture_url(&id),
+            id: id.clone(),
+            mime_type: capture.mime_type,
+            byte_size: capture.bytes.len(),
+            width: capture.width,
+            height: capture.height,
+        };
+        self.bytes += capture.bytes.len();
+        self.entries.insert(id, StoredCapture {
+            bytes: capture.bytes.into(),
+            mime_type: capture.mime_type,
+            width: capture.width,
+            height: capture.height,
+            refs: 1,
+        });
+        handle
+    }
+
+    pub fn get(&self, id: &str) -> Option<&StoredCapture> {
+        self.entries.get(id)
+    }
+
+    /// Take another reference; returns how many there are now
+    pub fn retain(&mut self, id: &str) -> Result<usize, String> {
+        let entry = self.entries.get_mut(id).ok_or_else(|| format!("Capture {} not found", id))?;
+        entry.refs += 1;
+        Ok(entry.refs)
+    }
+
+    /// Drop a reference; the capture is freed with the last one. Returns the references left.
+    pub fn release(&mut self, id: &str) -> Result<usize, String> {
+        let entry = self.entries.get_mut(id).ok_or_else(|| format!("Capture {} not found", id))?;
+        entry.refs -= 1;
+        let refs = entry.refs;
+        if refs == 0 {
+            if let Some(removed) = self.entries.remove(id) {
+                self.bytes -= removed.bytes.len();
+            }
+        }
+        Ok(refs)
+    }
+
+    /// The capture a `framesense://` request asks for
+    pub fn resolve(&self, uri: &str) -> Result<&StoredCapture, String> {
+        let id = capture_id_from_uri(uri).ok_or_else(|| format!("Not a capture URL: {}", uri))?;
+        self.get(id).ok_or_else(|| format!("Capture {} not found", id))
+    }
+
+    /// Free every capture regardless of references
+    pub fn clear(&mut self) -> usize {
+        let count = self.entries.len();
+        self.entries.clear();
+        self.bytes = 0;
+        count
+    }
+
+    pub fn stats(&self) -> RegistryStats {
+        RegistryStats {
+            captures: self.entries.len(),
+            references: self.entries.values().map(|entry| entry.refs).sum(),
+            bytes: self.bytes,
+        }
+    }
+}
+
+/// URL the webview loads a capture from
+pub fn capture_url(id: &str) -> String {
+    // WebView2 and Android only reach custom protocols through http://<scheme>.localhost
+    if cfg!(any(target_os = "windows", target_os = "android")) {
+        format!("http://{}.localhost/{}/{}", SCHEME, CAPTURE_PATH, id)
+    } else {
+        format!("{}://{}/{}", SCHEME, CAPTURE_PATH, id)
+    }
+}
+
+/// Origins of the app's own pages, the only ones allowed to read capture bytes
+/// (`dev_url` is the dev server the debug build loads from)
+pub fn is_app_origin(origin: &str, dev_url: Option<&str>) -> bool {
+    let origin = origin.trim_end_matches('/');
+    matches!(origin, "tauri://localhost" | "http://tauri.localhost" | "https://tauri.localhost")
+        || dev_url.is_some_and(|url| !origin.is_empty() && url.trim_end_matches('/') == origin)
+}
+
+/// Capture id in `framesense://capture/<id>`, `framesense://localhost/capture/<id>`
+/// or `http://framesense.localhost/capture/<id>`
+pub fn capture_id_from_uri(uri: &str) -> Option<&str> {
+    let rest = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or(rest);
+    let segments: Vec<&str> = rest.split('/').filter(|segment| !segment.is_empty()).collect();
+    match segments.iter().position(|segment| *segment == CAPTURE_PATH) {
+        Some(at) if at + 2 == segments.len() => Some(segments[at + 1]),
+        _ => None,
+    }
+}
@@ -0,0 +1,247 @@
+// Timed and repeating captures: after a countdown, on an interval, or whenever a
+// region changes. Each job runs on its own thread and reports through a sink, so
+// the scheduler knows nothing about Tauri; main.rs turns the events into
+// selection results.
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::capture_service::{CaptureError, CaptureService};
+use super::screen_capture::{CaptureBounds, ScreenCapture};
+
+/// How often a watched region is compared against the last capture
+pub const CHANGE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Longest countdown for a delayed capture
+pub const MAX_DELAY_SECS: u32 = 60;
+// Channel difference below which a pixel counts as unchanged (anti-aliasing, dithering)
+const PIXEL_TOLERANCE: u8 = 24;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ScheduleMode {
+    /// One capture after a countdown, so menus can be opened first
+    Delayed { delay_secs: u32 },
+    /// A capture every `interval_secs`; `count` captures, or until cancelled
+    Interval { interval_secs: u32, count: Option<u32> },
+    /// A capture whenever the region differs from the last one by more than
+    /// `threshold_percent` of its pixels
+    OnChange { threshold_percent: f32 },
+}
+
+impl ScheduleMode {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ScheduleMode::Delayed { delay_secs } if *delay_secs == 0 || *delay_secs > MAX_DELAY_SECS =>
+                Err(format!("Delay must be 1-{} seconds, got {}", MAX_DELAY_SECS, delay_secs)),
+            ScheduleMode::Interval { interval_secs: 0, .. } => Err("Interval must be at least 1 second".to_string()),
+            ScheduleMode::Interval { count: Some(0), .. } => Err("Interval capture count must be at least 1".to_string()),
+            ScheduleMode::OnChange { threshold_percent } if !(*threshold_percent > 0.0 && *threshold_percent <= 100.0) =>
+                Err(format!("Change threshold must be above 0 and at most 100%, got {}", threshold_percent)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScheduleInfo {
+    pub id: u64,
+    #[serde(flatten)]
+    pub mode: ScheduleMode,
+    pub bounds: Option<CaptureBounds>, // None: the whole desktop
+    pub started_at: u64,               // Unix seconds
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    Completed,
+    Cancelled,
+}
+
+/// What a job reports while it runs
+#[derive(Debug)]
+pub enum ScheduleEvent {
+    Countdown { id: u64, remaining_secs: u32 },
+    Captured {
+        id: u64,
+        sequence: u32, // 1 for the job's first capture
+        bounds: CaptureBounds,
+        image: RgbaImage,
+        changed_percent: Option<f32>, // OnChange only
+    },
+    Failed { id: u64, error: CaptureError },
+    Finished { id: u64, reason: FinishReason },
+}
+
+pub type ScheduleSink = Arc<dyn Fn(ScheduleEvent) + Send + Sync>;
+
+struct Job {
+    info: ScheduleInfo,
+    // Dropping the sender cancels the job
+    _cancel: Sender<()>,
+    worker: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct CaptureScheduler {
+    jobs: HashMap<u64, Job>,
+    next_id: u64,
+}
+
+impl CaptureScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a job capturing `bounds` (or the whole desktop) through `service`
+    pub fn start(&mut self, service: CaptureService, bounds: Option<CaptureBounds>, mode: ScheduleMode, sink: ScheduleSink) -> Result<ScheduleInfo, String> {
+        mode.validate()?;
+        self.prune();
+
+        self.next_id += 1;
+        let info = ScheduleInfo {
+            id: self.next_id,
+            mode: mode.clone(),
+            bounds: bounds.clone(),
+            started_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        };
+        let (cancel, cancelled) = mpsc::channel();
+        let id = info.id;
+        let worker = std::thread::spawn(move || {
+            let job = JobRunner { id, service, bounds, sink, cancelled };
+            let reason = job.run(&mode);
+            (job.sink)(ScheduleEvent::Finished { id, reason });
+        });
+
+        println!("⏱️ Scheduled capture {} started: {:?}", info.id, info.mode);
+        self.jobs.insert(info.id, Job { info: info.clone(), _cancel: cancel, worker });
+        Ok(info)
+    }
+
+    /// Cancel one job; false if it already finished
+    pub fn cancel(&mut self, id: u64) -> bool {
+        self.prune();
+        self.jobs.remove(&id).is_some()
+    }
+
+    /// Cancel every job, returning how many were running
+    pub fn cancel_all(&mut self) -> usize {
+        self.prune();
+        let count = self.jobs.len();
+        self.jobs.clear();
+        count
+    }
+
+    /// Jobs still running, oldest first
+    pub fn list(&mut self) -> Vec<ScheduleInfo> {
+        self.prune();
+        let mut jobs: Vec<ScheduleInfo> = self.jobs.values().map(|job| job.info.clone()).collect();
+        jobs.sort_by_key(|info| info.id);
+        jobs
+    }
+
+    fn prune(&mut self) {
+        self.jobs.retain(|_, job| !job.worker.is_finished());
+    }
+}
+
+struct JobRunner {
+    id: u64,
+    service: CaptureService,
+    bounds: Option<CaptureBounds>,
+    sink: ScheduleSink,
+    cancelled: mpsc::Receiver<()>,
+}
+
+impl JobRunner {
+    fn run(&self, mode: &ScheduleMode) -> FinishReason {
+        match *mode {
+            ScheduleMode::Delayed { delay_secs } => {
+                for remaining_secs in (1..=delay_secs).rev() {
+                    (self.sink)(ScheduleEvent::Countdown { id: self.id, remaining_secs });
+                    if self.wait(Duration::from_secs(1)) {
+                        return FinishReason::Cancelled;
+                    }
+                }
+                self.deliver(1, self.capture(), None);
+                FinishReason::Completed
+            },
+            ScheduleMode::Interval { interval_secs, count } => {
+                let mut sequence = 0;
+                loop {
+                    sequence += 1;
+                    self.deliver(sequence, self.capture(), None);
+                    if count.is_some_and(|count| sequence >= count) {
+                        return FinishReason::Completed;
+                    }
+                    if self.wait(Duration::from_secs(interval_secs as u64)) {
+                        return FinishReason::Cancelled;
+                    }
+                }
+            },
+            ScheduleMode::OnChange { threshold_percent } => {
+                // The region as it is now is the baseline, not a capture
+                let mut baseline = match self.capture() {
+                    Ok((_, image)) => image,
+                    Err(error) => {
+                        (self.sink)(ScheduleEvent::Failed { id: self.id, error });
+                        return FinishReason::Completed;
+                    },
+                };
+                let mut sequence = 0;
+                loop {
+                    if self.wait(self.service.frame_interval(CHANGE_POLL_INTERVAL)) {
+                        return FinishReason::Cancelled;
+                    }
+                    match self.capture() {
+                        Ok((area, image)) => {
+                            let changed = changed_percent(&baseline, &image);
+                            if changed > threshold_percent {
+                                sequence += 1;
+                                baseline = image.clone();
+                                self.deliver(sequence, Ok((area, image)), Some(changed));
+                            }
+                        },
+                        Err(error) => (self.sink)(ScheduleEvent::Failed { id: self.id, error }),
+                    }
+                }
+            },
+        }
+    }
+
+    /// Sleep for `duration`; true if the job was cancelled meanwhile
+    fn wait(&self, duration: Duration) -> bool {
+        !matches!(self.cancelled.recv_timeout(duration), Err(RecvTimeoutError::Timeout))
+    }
+
+    fn capture(&self) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        let area = match &self.bounds {
+            Some(bounds) => bounds.clone(),
+            None => ScreenCapture::desktop_bounds(&self.service.screens()?).ok_or(CaptureError::NoScreens)?,
+        };
+        self.service.capture_pixels(&area)
+    }
+
+    fn deliver(&self, sequence: u32, captured: Result<(CaptureBounds, RgbaImage), CaptureError>, changed_percent: Option<f32>) {
+        let event = match captured {
+            Ok((bounds, image)) => ScheduleEvent::Captured { id: self.id, sequence, bounds, image, changed_percent },
+            Err(error) => ScheduleEvent::Failed { id: self.id, error },
+        };
+        (self.sink)(event);
+    }
+}
+
+/// Percentage of pixels that differ between two captures of a region (100 when
+/// the sizes differ)
+pub fn changed_percent(before: &RgbaImage, after: &RgbaImage) -> f32 {
+    if before.dimensions() != after.dimensions() || before.width() == 0 || before.height() == 0 {
+        return 100.0;
+    }
+    let changed = before.pixels().zip(after.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0.iter()).any(|(x, y)| x.abs_diff(*y) > PIXEL_TOLERANCE))
+        .count();
+    changed as f32 * 100.0 / (before.width() * before.height()) as f32
+}
@@ -0,0 +1,210 @@
+// The capture API every command goes through: clip the area to the screens, stitch
+// across monitors, check the size and encode. Failures are a CaptureError, so the
+// webview can tell a denied permission from an empty selection.
+use image::RgbaImage;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::capture_source::{default_source, CaptureSource};
+use super::image_encoding::{self, EncodedCapture, OutputOptions};
+use super::screen_capture::{CaptureBounds, ScreenCapture, ScreenInfo};
+use super::window_capture::{self, WindowInfo};
+
+/// Smallest capture edge, in desktop units
+pub const MIN_CAPTURE_EDGE: u32 = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptureError {
+    NoScreens,
+    /// The area does not touch any screen
+    OutsideScreens(CaptureBounds),
+    /// What is left after clipping to the screens is below MIN_CAPTURE_EDGE
+    AreaTooSmall { width: u32, height: u32 },
+    /// The OS or compositor refused (macOS screen recording, a dismissed portal dialog)
+    PermissionDenied(String),
+    /// The capture backend failed otherwise
+    Backend(String),
+    EncodeFailed(String),
+    /// The picked window has closed (or never existed)
+    WindowNotFound(u64),
+    /// The backend can't do this on this platform (e.g. listing windows)
+    Unsupported(String),
+}
+
+impl CaptureError {
+    /// Stable name for the frontend to switch on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CaptureError::NoScreens => "no_screens",
+            CaptureError::OutsideScreens(_) => "outside_screens",
+            CaptureError::AreaTooSmall { .. } => "area_too_small",
+            CaptureError::PermissionDenied(_) => "permission_denied",
+            CaptureError::Backend(_) => "backend",
+            CaptureError::EncodeFailed(_) => "encode_failed",
+            CaptureError::WindowNotFound(_) => "window_not_found",
+            CaptureError::Unsupported(_) => "unsupported",
+        }
+    }
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::NoScreens => write!(f, "No screens found"),
+            CaptureError::OutsideScreens(bounds) => write!(f, "Capture area {:?} is outside all screens", bounds),
+            CaptureError::AreaTooSmall { width, height } => write!(f, "Capture area too small after adjustment: {}x{}", width, height),
+            CaptureError::PermissionDenied(reason) => write!(f, "Screen capture permission denied: {}", reason),
+            CaptureError::Backend(reason) => write!(f, "{}", reason),
+            CaptureError::EncodeFailed(reason) => write!(f, "Image encoding failed: {}", reason),
+            CaptureError::WindowNotFound(id) => write!(f, "Window {} not found", id),
+            CaptureError::Unsupported(feature) => write!(f, "{} is not supported on this platform", feature),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+// Commands return it as { kind, message }
+impl Serialize for CaptureError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Wire<'a> {
+            kind: &'a str,
+            message: String,
+        }
+        Wire { kind: self.kind(), message: self.to_string() }.serialize(serializer)
+    }
+}
+
+// Lets `?` hand a CaptureError to code that reports plain strings
+impl From<CaptureError> for String {
+    fn from(error: CaptureError) -> Self {
+        error.to_string()
+    }
+}
+
+/// An encoded capture and where on the desktop it came from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CaptureResult {
+    pub image_data: String, // Data URL
+    pub mime_type: String,
+    pub byte_size: usize,   // Encoded image size, before base64
+    pub width: u32,         // Encoded pixels (after any downscaling)
+    pub height: u32,
+    pub bounds: CaptureBounds, // Area actually captured, clipped to the screens
+    pub timestamp: u64,        // Unix seconds
+}
+
+impl CaptureResult {
+    pub fn new(bounds: CaptureBounds, encoded: EncodedCapture) -> Self {
+        Self {
+            image_data: encoded.image_data,
+            mime_type: encoded.mime_type.to_string(),
+            byte_size: encoded.byte_size,
+            width: encoded.width,
+            height: encoded.height,
+            bounds,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        }
+    }
+}
+
+/// Uncached capture against one backend (ScreenshotCache adds frame and content caching on top)
+pub struct CaptureService {
+    source: Arc<dyn CaptureSource>,
+}
+
+impl CaptureService {
+    pub fn new() -> Self {
+        Self::with_source(default_source())
+    }
+
+    pub fn with_source(source: Arc<dyn CaptureSource>) -> Self {
+        Self { source }
+    }
+
+    pub fn source(&self) -> Arc<dyn CaptureSource> {
+        self.source.clone()
+    }
+
+    /// Attached screens; an empty list is an error
+    pub fn screens(&self) -> Result<Vec<ScreenInfo>, CaptureError> {
+        let screens = self.source.screens()?;
+        if screens.is_empty() {
+            return Err(CaptureError::NoScreens);
+        }
+        Ok(screens)
+    }
+
+    /// `wanted` between continuous frames, or longer where the backend can't keep up
+    pub fn frame_interval(&self, wanted: Duration) -> Duration {
+        match self.source.max_frame_rate() {
+            Some(fps) if fps > 0 => wanted.max(Duration::from_secs(1) / fps),
+            _ => wanted,
+        }
+    }
+
+    /// Pixels of `bounds`, clipped to the screens and stitched across them
+    pub fn capture_pixels(&self, bounds: &CaptureBounds) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        let (area, image) = ScreenCapture::capture_global_from(self.source.as_ref(), bounds)?;
+        check_size(&area)?;
+        Ok((area, image))
+    }
+
+    pub fn capture(&self, bounds: &CaptureBounds, output: &OutputOptions) -> Result<CaptureResult, CaptureError> {
+        let (area, image) = self.capture_pixels(bounds)?;
+        encode(area, &image, output)
+    }
+
+    /// Top-level windows, topmost first
+    pub fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError> {
+        self.source.windows()
+    }
+
+    /// Topmost window under a point in desktop units
+    pub fn window_at(&self, x: i32, y: i32) -> Result<Option<WindowInfo>, CaptureError> {
+        Ok(window_capture::window_at(&self.windows()?, x, y).cloned())
+    }
+
+    /// Pixels of one window: its own contents where the backend can read them (so
+    /// covered parts are included), otherwise what the screens show in its bounds
+    pub fn capture_window_pixels(&self, id: u64) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        let window = self.windows()?.into_iter()
+            .find(|window| window.id == id)
+            .ok_or(CaptureError::WindowNotFound(id))?;
+
+        match self.source.capture_window(&window)? {
+            Some(image) => {
+                check_size(&window.bounds)?;
+                Ok((window.bounds, image))
+            },
+            None => self.capture_pixels(&window.bounds),
+        }
+    }
+
+    pub fn capture_window(&self, id: u64, output: &OutputOptions) -> Result<CaptureResult, CaptureError> {
+        let (area, image) = self.capture_window_pixels(id)?;
+        encode(area, &image, output)
+    }
+}
+
+impl Default for CaptureService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reject areas too small to be a deliberate selection
+pub fn check_size(area: &CaptureBounds) -> Result<(), CaptureError> {
+    if area.width < MIN_CAPTURE_EDGE || area.height < MIN_CAPTURE_EDGE {
+        return Err(CaptureError::AreaTooSmall { width: area.width, height: area.height });
+    }
+    Ok(())
+}
+
+/// `image`, captured from `area`, encoded as `output` asks
+pub fn encode(area: CaptureBounds, image: &RgbaImage, output: &OutputOptions) -> Result<CaptureResult, CaptureError> {
+    let encoded = image_encoding::encode(image, output).map_err(CaptureError::EncodeFailed)?;
+    Ok(CaptureResult::new(area, encoded))
+}
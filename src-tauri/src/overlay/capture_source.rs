@@ -0,0 +1,241 @@
+// Capture backends - where screen pixels come from
+// ScreenCapture, ScreenLayout and ScreenshotCache only talk to a CaptureSource,
+// so the capture → OCR path can run headless against fixture images.
+use image::{imageops, RgbaImage};
+use screenshots::Screen;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use super::capture_service::CaptureError;
+use super::coordinates;
+use super::screen_capture::{CaptureBounds, ScreenInfo};
+use super::window_capture::WindowInfo;
+use crate::system::permission_cache;
+
+pub trait CaptureSource: Send + Sync {
+    /// Backend name for logs
+    fn name(&self) -> &str;
+
+    /// Attached screens, positioned in desktop units
+    fn screens(&self) -> Result<Vec<ScreenInfo>, CaptureError>;
+
+    /// The whole framebuffer of `screen`, in physical pixels
+    fn capture_screen(&self, screen: &ScreenInfo) -> Result<RgbaImage, CaptureError>;
+
+    /// Physical pixels of `area` (desktop units, inside `screen`)
+    fn capture_area(&self, screen: &ScreenInfo, area: &CaptureBounds) -> Result<RgbaImage, CaptureError> {
+        crop_to_area(self.capture_screen(screen)?, screen, area)
+    }
+
+    /// Frames per second the backend can sustain for recordings, scrolling captures
+    /// and change polling (None when it isn't limited)
+    fn max_frame_rate(&self) -> Option<u32> {
+        None
+    }
+
+    /// Top-level windows, topmost first (Unsupported where the backend can't list them)
+    fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError> {
+        Err(CaptureError::Unsupported("Window picking".to_string()))
+    }
+
+    /// The window's own pixels, including parts other windows cover. None when the
+    /// backend only has what is on screen; the caller then captures the window's bounds.
+    fn capture_window(&self, _window: &WindowInfo) -> Result<Option<RgbaImage>, CaptureError> {
+        Ok(None)
+    }
+}
+
+/// `area` cut out of the whole framebuffer of `screen`
+fn crop_to_area(frame: RgbaImage, screen: &ScreenInfo, area: &CaptureBounds) -> Result<RgbaImage, CaptureError> {
+    // Crop using the density the capture actually has
+    let scale = frame.width() as f32 / screen.width.max(1) as f32;
+    let region = coordinates::logical_to_physical(area, screen, scale, frame.dimensions());
+    if region.width == 0 || region.height == 0 {
+        return Err(CaptureError::OutsideScreens(area.clone()));
+    }
+    if (region.x, region.y, region.width, region.height) == (0, 0, frame.width(), frame.height()) {
+        return Ok(frame);
+    }
+    Ok(imageops::crop_imm(&frame, region.x, region.y, region.width, region.height).to_image())
+}
+
+static DEFAULT_SOURCE: OnceLock<Arc<dyn CaptureSource>> = OnceLock::new();
+
+/// Backend used by the capture commands: the screenshot portal under Wayland
+/// (falling back to X11 when no portal answers), `screenshots` everywhere else
+pub fn default_source() -> Arc<dyn CaptureSource> {
+    DEFAULT_SOURCE.get_or_init(|| {
+        #[cfg(target_os = "linux")]
+        if super::portal_capture::is_wayland_session() {
+            println!("🪟 Wayland session, capturing through xdg-desktop-portal");
+            return Arc::new(super::portal_capture::PortalSource::new());
+        }
+        Arc::new(ScreenshotsSource)
+    }).clone()
+}
+
+/// Live capture through the `screenshots` crate
+pub struct ScreenshotsSource;
+
+impl ScreenshotsSource {
+    fn attached(screen: &ScreenInfo) -> Result<Screen, CaptureError> {
+        // Without access macOS hands back the wallpaper instead of failing
+        if !permission_cache::screen_recording_granted() {
+            return Err(Self::permission_denied());
+        }
+        let screens = Screen::all().map_err(|e| CaptureError::Backend(format!("Failed to get screens: {}", e)))?;
+        screens.into_iter()
+            .find(|s| s.display_info.id == screen.id)
+            .ok_or_else(|| CaptureError::Backend(format!("Screen {} is no longer attached", screen.id)))
+    }
+
+    fn permission_denied() -> CaptureError {
+        CaptureError::PermissionDenied("FrameSense needs Screen Recording access (System Settings → Privacy & Security)".to_string())
+    }
+
+    // A failed capture is a permission problem when access was revoked meanwhile
+    fn capture_failed(message: String) -> CaptureError {
+        if permission_cache::screen_recording_granted() {
+            CaptureError::Backend(message)
+        } else {
+            Self::permission_denied()
+        }
+    }
+
+    fn to_rgba(image: screenshots::Image) -> Result<RgbaImage, CaptureError> {
+        RgbaImage::from_raw(image.width(), image.height(), image.rgba().to_vec())
+            .ok_or_else(|| CaptureError::Backend("Failed to create RGBA image from screenshot".to_string()))
+    }
+}
+
+impl CaptureSource for ScreenshotsSource {
+    fn name(&self) -> &str {
+        "screenshots"
+    }
+
+    fn screens(&self) -> Result<Vec<ScreenInfo>, CaptureError> {
+        let screens = Screen::all().map_err(|e| CaptureError::Backend(format!("Failed to get screens: {}", e)))?;
+        Ok(screens.iter().map(|screen| ScreenInfo::from(&screen.display_info)).collect())
+    }
+
+    fn capture_screen(&self, screen: &ScreenInfo) -> Result<RgbaImage, CaptureError> {
+        let image = Self::attached(screen)?.capture()
+            .map_err(|e| Self::capture_failed(format!("Failed to capture screen {}: {}", screen.id, e)))?;
+        Self::to_rgba(image)
+    }
+
+    // Only the selected rectangle is read, so region captures on 4K/5K panels (each
+    // recording or scroll frame) don't copy the whole framebuffer
+    fn capture_area(&self, screen: &ScreenInfo, area: &CaptureBounds) -> Result<RgbaImage, CaptureError> {
+        let scale = coordinates::screen_scale(screen);
+        let region = coordinates::logical_to_physical(area, screen, scale, coordinates::physical_size(screen, scale));
+        if region.width == 0 || region.height == 0 {
+            return Err(CaptureError::OutsideScreens(area.clone()));
+        }
+
+        // The backend takes units relative to the screen and truncates when scaling,
+        // where the region rounds its far edge up: ask for one more unit, then crop
+        let (x, y) = (area.x - screen.x, area.y - screen.y);
+        let width = (area.width + 1).min((screen.width as i32 - x).max(1) as u32);
+        let height = (area.height + 1).min((screen.height as i32 - y).max(1) as u32);
+        let image = Self::attached(screen)?.capture_area(x, y, width, height)
+            .map_err(|e| Self::capture_failed(format!("Failed to capture area of screen {}: {}", screen.id, e)))?;
+        let image = Self::to_rgba(image)?;
+
+        let left = region.x.saturating_sub((x.max(0) as f32 * scale) as u32);
+        let top = region.y.saturating_sub((y.max(0) as f32 * scale) as u32);
+        if left + region.width > image.width() || top + region.height > image.height() {
+            // The platform rounded differently; the whole screen always has the region
+            println!("⚠️ Area capture returned {}x{} for a {}x{} region, cropping from the full screen",
+                     image.width(), image.height(), region.width, region.height);
+            return crop_to_area(self.capture_screen(screen)?, screen, area);
+        }
+        Ok(imageops::crop_imm(&image, left, top, region.width, region.height).to_image())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError> {
+        // X11 has one scale for every screen (Xft.dpi)
+        let scale = self.screens()?.first().map(coordinates::screen_scale).unwrap_or(1.0);
+        super::x11_windows::windows(scale)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn capture_window(&self, window: &WindowInfo) -> Result<Option<RgbaImage>, CaptureError> {
+        super::x11_windows::capture_window(window)
+    }
+}
+
+/// Serves fixed images as screens - for verification runs and CI without a display
+pub struct FixtureSource {
+    screens: Vec<(ScreenInfo, RgbaImage)>,
+    windows: Vec<(WindowInfo, Option<RgbaImage>)>,
+}
+
+// One entry of a fixture directory's screens.json
+#[derive(Deserialize)]
+struct FixtureScreen {
+    #[serde(flatten)]
+    info: ScreenInfo,
+    image: String, // PNG path relative to the directory
+}
+
+impl FixtureSource {
+    /// Each image is the framebuffer of its screen (width * scale_factor pixels wide)
+    pub fn new(screens: Vec<(ScreenInfo, RgbaImage)>) -> Self {
+        Self { screens, windows: Vec::new() }
+    }
+
+    /// Windows to report, topmost first, each with its own pixels if it should
+    /// capture as an uncovered window
+    pub fn with_windows(mut self, windows: Vec<(WindowInfo, Option<RgbaImage>)>) -> Self {
+        self.windows = windows;
+        self
+    }
+
+    /// Load `screens.json` (ScreenInfo fields plus an `image` path) from `dir`
+    pub fn from_dir(dir: &Path) -> Result<Self, String> {
+        let manifest_path = dir.join("screens.json");
+        let manifest = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e))?;
+        let entries: Vec<FixtureScreen> = serde_json::from_str(&manifest)
+            .map_err(|e| format!("Invalid {}: {}", manifest_path.display(), e))?;
+
+        let mut screens = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let image_path = dir.join(&entry.image);
+            let image = image::open(&image_path)
+                .map_err(|e| format!("Failed to load {}: {}", image_path.display(), e))?
+                .to_rgba8();
+            screens.push((entry.info, image));
+        }
+        Ok(Self::new(screens))
+    }
+}
+
+impl CaptureSource for FixtureSource {
+    fn name(&self) -> &str {
+        "fixture"
+    }
+
+    fn screens(&self) -> Result<Vec<ScreenInfo>, CaptureError> {
+        Ok(self.screens.iter().map(|(info, _)| info.clone()).collect())
+    }
+
+    fn capture_screen(&self, screen: &ScreenInfo) -> Result<RgbaImage, CaptureError> {
+        self.screens.iter()
+            .find(|(info, _)| info.id == screen.id)
+            .map(|(_, image)| image.clone())
+            .ok_or_else(|| CaptureError::Backend(format!("No fixture image for screen {}", screen.id)))
+    }
+
+    fn windows(&self) -> Result<Vec<WindowInfo>, CaptureError> {
+        Ok(self.windows.iter().map(|(info, _)| info.clone()).collect())
+    }
+
+    fn capture_window(&self, window: &WindowInfo) -> Result<Option<RgbaImage>, CaptureError> {
+        Ok(self.windows.iter()
+            .find(|(info, _)| info.id == window.id)
+            .and_then(|(_, image)| image.clone()))
+    }
+}
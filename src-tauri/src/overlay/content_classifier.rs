@@ -0,0 +1,525 @@
+// Content classification for captured regions
+// Decides what a capture shows (text, code, table, chart, photo, UI...) from
+// image statistics alone, so the result is deterministic and needs no OCR pass.
+use image::{imageops, DynamicImage, GrayImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use super::interactive_overlay::ContentType;
+
+// Large captures are analyzed at this size - plenty for layout cues
+const MAX_ANALYSIS_EDGE: u32 = 1024;
+const MIN_ANALYSIS_EDGE: u32 = 8;
+// A pixel is ink when it differs this much (luma) from its neighborhood mean
+const MIN_INK_CONTRAST: f32 = 24.0;
+const INK_CONTRAST_RATIO: f32 = 0.15;
+// Gradient (|dx| + |dy| in luma) that counts as an edge
+const EDGE_THRESHOLD: i32 = 48;
+// Glyph-sized components, in analysis pixels
+const MIN_GLYPH_HEIGHT: u32 = 6;
+const MAX_GLYPH_HEIGHT: u32 = 64;
+const MIN_GLYPH_PIXELS: u32 = 8;
+// Components per row needed before a row counts as a text line
+const MIN_LINE_GLYPHS: usize = 3;
+// A thin rule spans this fraction of the image and is at most this thick
+const RULE_SPAN_RATIO: f32 = 0.4;
+const MAX_RULE_THICKNESS: u32 = 4;
+// Flat color regions (bars, buttons, title bars) cover at least this fraction
+const MIN_REGION_RATIO: f32 = 0.003;
+// Colors covering less than this fraction of pixels don't count as distinct
+const MIN_COLOR_RATIO: f32 = 0.001;
+const PHOTO_MIN_COLORS: usize = 60;
+const PHOTO_MAX_BACKGROUND: f32 = 0.3;
+const HUE_BINS: usize = 12;
+
+/// Image statistics the classification is based on
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ContentFeatures {
+    /// Fraction of pixels on a strong luma edge
+    pub edge_density: f32,
+    /// Quantized colors covering a meaningful share of the image
+    pub color_count: usize,
+    /// Share of the most common color
+    pub background_ratio: f32,
+    pub dark_background: bool,
+    /// Glyph-sized ink components
+    pub text_components: usize,
+    /// Rows of at least `MIN_LINE_GLYPHS` glyphs
+    pub text_lines: usize,
+    /// Distinct left edges of the text lines
+    pub indent_levels: usize,
+    /// Text lines split into three or more widely separated columns
+    pub column_lines: usize,
+    /// Distinct saturated hues used by text
+    pub ink_hues: usize,
+    pub horizontal_rules: usize,
+    pub vertical_rules: usize,
+    /// Large single-color regions other than the background
+    pub flat_regions: usize,
+    /// Distinct hues among saturated flat regions (bars, slices)
+    pub saturated_region_hues: usize,
+    /// Fraction of the image covered by saturated flat regions
+    pub saturated_area: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Classification {
+    pub content_type: ContentType,
+    pub confidence: f32,
+    pub features: ContentFeatures,
+}
+
+/// Classify a capture; the same image always gives the same result
+pub fn classify(img: &DynamicImage) -> Classification {
+    let rgba = analysis_image(img);
+    if rgba.width() < MIN_ANALYSIS_EDGE || rgba.height() < MIN_ANALYSIS_EDGE {
+        return Classification {
+            content_type: ContentType::Unknown,
+            confidence: 0.0,
+            features: ContentFeatures::default(),
+        };
+    }
+
+    let features = extract_features(&rgba);
+    let (content_type, confidence) = decide(&features);
+    Classification { content_type, confidence, features }
+}
+
+fn analysis_image(img: &DynamicImage) -> RgbaImage {
+    let (width, height) = (img.width(), img.height());
+    if width.max(height) <= MAX_ANALYSIS_EDGE {
+        return img.to_rgba8();
+    }
+    let scale = MAX_ANALYSIS_EDGE as f32 / width.max(height) as f32;
+    let new_width = ((width as f32 * scale).round() as u32).max(1);
+    let new_height = ((height as f32 * scale).round() as u32).max(1);
+    imageops::resize(&img.to_rgba8(), new_width, new_height, imageops::FilterType::Triangle)
+}
+
+/// Decision rules, most specific layout first
+fn decide(f: &ContentFeatures) -> (ContentType, f32) {
+    let confidence = |base: f32, support: f32| (base + support).min(0.95);
+
+    if f.horizontal_rules >= 3 && f.vertical_rules >= 2 {
+        return (ContentType::Table, confidence(0.75, 0.05 * (f.horizontal_rules + f.vertical_rules - 5) as f32));
+    }
+    if f.column_lines >= 3 {
+        return (ContentType::Table, confidence(0.6, 0.05 * (f.column_lines - 3) as f32));
+    }
+
+    if f.text_lines >= 3 && f.indent_levels >= 2 && (f.dark_background || f.ink_hues >= 2) {
+        let support = if f.dark_background && f.ink_hues >= 2 { 0.15 } else { 0.0 };
+        return (ContentType::Code, confidence(0.7, support + 0.02 * f.text_lines as f32));
+    }
+
+    let has_axes = f.horizontal_rules >= 1 && f.vertical_rules >= 1;
+    if f.saturated_area >= 0.05 && (has_axes || f.saturated_region_hues >= 3) && f.saturated_region_hues >= 2 {
+        let support = if has_axes && f.saturated_region_hues >= 3 { 0.15 } else { 0.0 };
+        return (ContentType::Chart, confidence(0.7, support));
+    }
+
+    let photo_like = f.color_count >= PHOTO_MIN_COLORS && f.background_ratio < PHOTO_MAX_BACKGROUND;
+    if photo_like {
+        if f.text_lines >= 1 {
+            return (ContentType::ImageWithText, confidence(0.6, 0.1 * f.text_lines as f32));
+        }
+        return (ContentType::Photo, confidence(0.6, (PHOTO_MAX_BACKGROUND - f.background_ratio).max(0.0)));
+    }
+
+    if f.text_lines >= 1 && (f.flat_regions >= 2 || f.horizontal_rules + f.vertical_rules >= 2) {
+        return (ContentType::UiScreenshot, confidence(0.6, 0.05 * f.flat_regions as f32));
+    }
+    if f.text_lines >= 1 {
+        return (ContentType::PlainText, confidence(0.65, 0.05 * f.text_lines as f32));
+    }
+
+    if f.flat_regions >= 1 || f.edge_density > 0.0 {
+        return (ContentType::PureImage, confidence(0.6, 0.05 * f.flat_regions as f32));
+    }
+
+    // Blank capture - nothing to go on
+    (ContentType::Unknown, 0.3)
+}
+
+pub fn extract_features(rgba: &RgbaImage) -> ContentFeatures {
+    let (width, height) = rgba.dimensions();
+    let gray = DynamicImage::ImageRgba8(rgba.clone()).to_luma8();
+    let total = (width * height) as f32;
+
+    let mut features = ContentFeatures {
+        edge_density: edge_density(&gray),
+        ..Default::default()
+    };
+
+    // Color histogram on 4 bits per channel
+    let bins: Vec<u16> = rgba.pixels().map(|p| quantize(p.0)).collect();
+    let mut histogram = vec![0u32; 4096];
+    for &bin in &bins {
+        histogram[bin as usize] += 1;
+    }
+    let (background_bin, background_count) = histogram.iter().enumerate()
+        .max_by_key(|(bin, count)| (**count, std::cmp::Reverse(*bin)))
+        .map(|(bin, count)| (bin as u16, *count))
+        .unwrap_or((0, 0));
+    features.color_count = histogram.iter().filter(|&&c| c as f32 >= total * MIN_COLOR_RATIO).count();
+    features.background_ratio = background_count as f32 / total;
+    features.dark_background = bin_luma(background_bin) < 96.0;
+
+    // Ink is darker than its surroundings, or lighter on dark backgrounds
+    let ink = ink_mask(&gray, features.dark_background);
+    let (labels, components) = label_components(&ink, width, height);
+
+    let glyphs: Vec<&Component> = components.iter().filter(|c| c.is_glyph()).collect();
+    features.text_components = glyphs.len();
+
+    let lines = group_lines(&glyphs);
+    features.text_lines = lines.len();
+    features.indent_levels = indent_levels(&lines);
+    features.column_lines = lines.iter().filter(|line| line.segments >= 3).count();
+    features.ink_hues = ink_hues(rgba, &labels, &components);
+
+    let (horizontal, vertical) = count_rules(&ink, width, height);
+    features.horizontal_rules = horizontal;
+    features.vertical_rules = vertical;
+
+    let regions = flat_regions(&bins, background_bin, width, height);
+    features.flat_regions = regions.len();
+    let saturated: Vec<&FlatRegion> = regions.iter().filter(|r| r.hue.is_some()).collect();
+    let mut hues: Vec<usize> = saturated.iter().filter_map(|r| r.hue).collect();
+    hues.sort_unstable();
+    hues.dedup();
+    features.saturated_region_hues = hues.len();
+    features.saturated_area = saturated.iter().map(|r| r.pixels).sum::<u32>() as f32 / total;
+
+    features
+}
+
+fn quantize(p: [u8; 4]) -> u16 {
+    ((p[0] as u16 >> 4) << 8) | ((p[1] as u16 >> 4) << 4) | (p[2] as u16 >> 4)
+}
+
+fn bin_rgb(bin: u16) -> [f32; 3] {
+    // Center of the bin
+    [((bin >> 8) & 0xF) as f32 * 16.0 + 8.0, ((bin >> 4) & 0xF) as f32 * 16.0 + 8.0, (bin & 0xF) as f32 * 16.0 + 8.0]
+}
+
+fn bin_luma(bin: u16) -> f32 {
+    let [r, g, b] = bin_rgb(bin);
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+/// Hue bin of a saturated color, None for grays and near-black
+fn hue_bin(r: f32, g: f32, b: f32) -> Option<usize> {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    if max < 64.0 || chroma / max < 0.35 {
+        return None;
+    }
+
+    let hue = if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    } * 60.0;
+    Some(((hue / 360.0 * HUE_BINS as f32) as usize).min(HUE_BINS - 1))
+}
+
+fn edge_density(gray: &GrayImage) -> f32 {
+    let (width, height) = gray.dimensions();
+    let mut edges = 0u32;
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let p = gray.get_pixel(x, y)[0] as i32;
+            let dx = (gray.get_pixel(x + 1, y)[0] as i32 - p).abs();
+            let dy = (gray.get_pixel(x, y + 1)[0] as i32 - p).abs();
+            if dx + dy >= EDGE_THRESHOLD {
+                edges += 1;
+            }
+        }
+    }
+    edges as f32 / ((width - 1) * (height - 1)).max(1) as f32
+}
+
+/// Local-contrast ink mask (neighborhood mean from an integral image)
+fn ink_mask(gray: &GrayImage, light_ink: bool) -> Vec<bool> {
+    let (width, height) = gray.dimensions();
+    let stride = width as usize + 1;
+    let mut integral = vec![0u64; stride * (height as usize + 1)];
+    for y in 0..height as usize {
+        let mut row_sum = 0u64;
+        for x in 0..width as usize {
+            row_sum += gray.get_pixel(x as u32, y as u32)[0] as u64;
+            integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row_sum;
+        }
+    }
+
+    let half = ((width.min(height) / 8).clamp(15, 61) / 2) as i64;
+    let mut mask = vec![false; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let x1 = (x as i64 - half).max(0) as usize;
+            let y1 = (y as i64 - half).max(0) as usize;
+            let x2 = (x as i64 + half + 1).min(width as i64) as usize;
+            let y2 = (y as i64 + half + 1).min(height as i64) as usize;
+            let area = ((x2 - x1) * (y2 - y1)) as f32;
+            let sum = integral[y2 * stride + x2] + integral[y1 * stride + x1]
+                - integral[y1 * stride + x2] - integral[y2 * stride + x1];
+            let mean = sum as f32 / area;
+
+            let value = gray.get_pixel(x, y)[0] as f32;
+            let (contrast, reference) = if light_ink { (value - mean, 255.0 - mean) } else { (mean - value, mean) };
+            mask[(y * width + x) as usize] = contrast >= MIN_INK_CONTRAST.max(reference * INK_CONTRAST_RATIO);
+        }
+    }
+    mask
+}
+
+struct Component {
+    min_x: u32,
+    min_y: u32,
+    max_x: u32,
+    max_y: u32,
+    pixels: u32,
+}
+
+impl Component {
+    fn width(&self) -> u32 {
+        self.max_x - self.min_x + 1
+    }
+
+    fn height(&self) -> u32 {
+        self.max_y - self.min_y + 1
+    }
+
+    /// Sized and shaped like a character
+    fn is_glyph(&self) -> bool {
+        let (width, height) = (self.width(), self.height());
+        let fill = self.pixels as f32 / (width * height) as f32;
+        (MIN_GLYPH_HEIGHT..=MAX_GLYPH_HEIGHT).contains(&height)
+            && width <= height * 3
+            && self.pixels >= MIN_GLYPH_PIXELS
+            && fill >= 0.08
+    }
+}
+
+/// 8-connected components; labels are component index + 1, 0 for background
+fn label_components(mask: &[bool], width: u32, height: u32) -> (Vec<u32>, Vec<Component>) {
+    let mut labels = vec![0u32; mask.len()];
+    let mut components = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..mask.len() {
+        if !mask[start] || labels[start] != 0 {
+            continue;
+        }
+
+        let label = components.len() as u32 + 1;
+        let (sx, sy) = ((start as u32) % width, (start as u32) / width);
+        let mut component = Component { min_x: sx, min_y: sy, max_x: sx, max_y: sy, pixels: 0 };
+        labels[start] = label;
+        stack.push(start);
+
+        while let Some(index) = stack.pop() {
+            let (x, y) = ((index as u32) % width, (index as u32) / width);
+            component.pixels += 1;
+            component.min_x = component.min_x.min(x);
+            component.max_x = component.max_x.max(x);
+            component.min_y = component.min_y.min(y);
+            component.max_y = component.max_y.max(y);
+
+            for dy in -1i32..=1 {
+                for dx in -1i32..=1 {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                        continue;
+                    }
+                    let neighbor = (ny as u32 * width + nx as u32) as usize;
+                    if mask[neighbor] && labels[neighbor] == 0 {
+                        labels[neighbor] = label;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+        components.push(component);
+    }
+
+    (labels, components)
+}
+
+struct TextLine {
+    left: u32,
+    segments: usize,
+}
+
+/// Group glyphs into rows; a row needs a few glyphs to count as a line of text
+fn group_lines(glyphs: &[&Component]) -> Vec<TextLine> {
+    let mut sorted: Vec<&Component> = glyphs.to_vec();
+    sorted.sort_by_key(|c| (c.min_y + c.max_y, c.min_x));
+
+    // (top, bottom, members)
+    let mut rows: Vec<(u32, u32, Vec<&Component>)> = Vec::new();
+    for glyph in sorted {
+        let center = (glyph.min_y + glyph.max_y) / 2;
+        let row = rows.iter_mut().find(|(top, bottom, members)| {
+            let row_height = (bottom - top + 1) as f32;
+            center >= *top && center <= *bottom
+                && (glyph.height() as f32) < row_height * 2.5
+                && (glyph.height() as f32) * 2.5 > row_height
+                && !members.is_empty()
+        });
+        match row {
+            Some((top, bottom, members)) => {
+                *top = (*top).min(glyph.min_y);
+                *bottom = (*bottom).max(glyph.max_y);
+                members.push(glyph);
+            },
+            None => rows.push((glyph.min_y, glyph.max_y, vec![glyph])),
+        }
+    }
+
+    rows.into_iter()
+        .filter(|(_, _, members)| members.len() >= MIN_LINE_GLYPHS)
+        .map(|(_, _, mut members)| {
+            members.sort_by_key(|c| c.min_x);
+            let mut heights: Vec<u32> = members.iter().map(|c| c.height()).collect();
+            heights.sort_unstable();
+            let column_gap = heights[heights.len() / 2] * 5 / 2;
+
+            // Gaps much wider than a word space separate columns
+            let mut segments = 1;
+            let mut right = members[0].max_x;
+            for glyph in &members[1..] {
+                if glyph.min_x > right + column_gap {
+                    segments += 1;
+                }
+                right = right.max(glyph.max_x);
+            }
+
+            TextLine { left: members[0].min_x, segments }
+        })
+        .collect()
+}
+
+/// Number of distinct left edges (within a few pixels) - indentation in code
+fn indent_levels(lines: &[TextLine]) -> usize {
+    let mut lefts: Vec<u32> = lines.iter().map(|line| line.left).collect();
+    lefts.sort_unstable();
+
+    let mut levels = 0;
+    let mut last: Option<u32> = None;
+    for left in lefts {
+        if last.is_none_or(|l| left > l + 4) {
+            levels += 1;
+        }
+        last = Some(left);
+    }
+    levels
+}
+
+/// Saturated hues used by glyph pixels (syntax highlighting)
+fn ink_hues(rgba: &RgbaImage, labels: &[u32], components: &[Component]) -> usize {
+    let mut hue_counts = [0u32; HUE_BINS];
+    let mut glyph_pixels = 0u32;
+    for (index, pixel) in rgba.pixels().enumerate() {
+        let label = labels[index];
+        if label == 0 || !components[label as usize - 1].is_glyph() {
+            continue;
+        }
+        glyph_pixels += 1;
+        if let Some(bin) = hue_bin(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32) {
+            hue_counts[bin] += 1;
+        }
+    }
+
+    let saturated: u32 = hue_counts.iter().sum();
+    if glyph_pixels == 0 || (saturated as f32) < glyph_pixels as f32 * 0.1 {
+        return 0;
+    }
+    hue_counts.iter().filter(|&&count| count as f32 >= saturated as f32 * 0.05).count()
+}
+
+/// Thin ink lines spanning much of the image (table grids, chart axes)
+fn count_rules(mask: &[bool], width: u32, height: u32) -> (usize, usize) {
+    let longest_run = |cells: &mut dyn Iterator<Item = bool>| {
+        let (mut best, mut run) = (0u32, 0u32);
+        for ink in cells {
+            run = if ink { run + 1 } else { 0 };
+            best = best.max(run);
+        }
+        best
+    };
+
+    let rows: Vec<bool> = (0..height)
+        .map(|y| longest_run(&mut (0..width).map(|x| mask[(y * width + x) as usize])) as f32 >= width as f32 * RULE_SPAN_RATIO)
+        .collect();
+    let columns: Vec<bool> = (0..width)
+        .map(|x| longest_run(&mut (0..height).map(|y| mask[(y * width + x) as usize])) as f32 >= height as f32 * RULE_SPAN_RATIO)
+        .collect();
+
+    (count_thin_runs(&rows), count_thin_runs(&columns))
+}
+
+/// Runs of consecutive spanning rows/columns that are thin enough to be a rule
+fn count_thin_runs(spanning: &[bool]) -> usize {
+    let mut rules = 0;
+    let mut run = 0u32;
+    for &span in spanning.iter().chain(std::iter::once(&false)) {
+        if span {
+            run += 1;
+        } else {
+            if run > 0 && run <= MAX_RULE_THICKNESS {
+                rules += 1;
+            }
+            run = 0;
+        }
+    }
+    rules
+}
+
+struct FlatRegion {
+    pixels: u32,
+    hue: Option<usize>,
+}
+
+/// Large connected areas of one quantized color, excluding the background
+fn flat_regions(bins: &[u16], background: u16, width: u32, height: u32) -> Vec<FlatRegion> {
+    let min_pixels = ((width * height) as f32 * MIN_REGION_RATIO).max(16.0) as u32;
+    let mut visited = vec![false; bins.len()];
+    let mut regions = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..bins.len() {
+        if visited[start] || bins[start] == background {
+            continue;
+        }
+
+        let bin = bins[start];
+        let mut pixels = 0u32;
+        visited[start] = true;
+        stack.push(start);
+        while let Some(index) = stack.pop() {
+            pixels += 1;
+            let (x, y) = ((index as u32) % width, (index as u32) / width);
+            let neighbors = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width as usize),
+                (y + 1 < height).then(|| index + width as usize),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !visited[neighbor] && bins[neighbor] == bin {
+                    visited[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        if pixels >= min_pixels {
+            let [r, g, b] = bin_rgb(bin);
+            regions.push(FlatRegion { pixels, hue: hue_bin(r, g, b) });
+        }
+    }
+    regions
+}
@@ -0,0 +1,181 @@
+// Coordinate conversion between the spaces a capture passes through:
+//   overlay viewport (webview CSS px) → desktop units (ScreenInfo x/y/width/height)
+//   → physical pixels of one screen's framebuffer (what a screen capture contains)
+// Every capture command goes through ScreenLayout so HiDPI and fractional
+// scaling are handled in one place.
+use serde::{Deserialize, Serialize};
+use super::capture_source::{default_source, CaptureSource};
+use super::screen_capture::{CaptureBounds, ScreenCapture, ScreenInfo};
+
+/// Rectangle in one screen's physical pixels, relative to that screen's top-left
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PhysicalRegion {
+    pub screen_id: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The screens of the desktop, with their origins and scale factors
+#[derive(Clone, Debug)]
+pub struct ScreenLayout {
+    screens: Vec<ScreenInfo>,
+}
+
+impl ScreenLayout {
+    pub fn new(screens: Vec<ScreenInfo>) -> Self {
+        Self { screens }
+    }
+
+    /// Layout of the screens currently attached
+    pub fn current() -> Result<Self, String> {
+        Self::from_source(default_source().as_ref())
+    }
+
+    /// Layout of the screens a capture backend reports
+    pub fn from_source(source: &dyn CaptureSource) -> Result<Self, String> {
+        let screens = source.screens()?;
+        if screens.is_empty() {
+            return Err("No screens available".to_string());
+        }
+        Ok(Self::new(screens))
+    }
+
+    pub fn screens(&self) -> &[ScreenInfo] {
+        &self.screens
+    }
+
+    /// Screen containing a point in desktop units
+    pub fn screen_at(&self, x: i32, y: i32) -> Option<&ScreenInfo> {
+        self.screens.iter().find(|screen| {
+            x >= screen.x && y >= screen.y
+                && x < screen.x + screen.width as i32
+                && y < screen.y + screen.height as i32
+        })
+    }
+
+    /// Screen showing the center of `bounds`, falling back to the primary screen
+    pub fn screen_for(&self, bounds: &CaptureBounds) -> Option<&ScreenInfo> {
+        let center_x = bounds.x + (bounds.width / 2) as i32;
+        let center_y = bounds.y + (bounds.height / 2) as i32;
+        self.screen_at(center_x, center_y).or_else(|| ScreenCapture::find_primary(&self.screens))
+    }
+
+    /// Map a selection reported by the overlay webview to desktop units.
+    /// `overlay_origin` is where the overlay window was placed (desktop units) and
+    /// `webview_scale` is the webview's device pixel ratio. Where the webview and the
+    /// screen disagree on the scale (fractional scaling, X11 Xft.dpi), the webview's
+    /// CSS pixels are converted through physical pixels of the screen under the point.
+    pub fn overlay_to_desktop(&self, viewport: &CaptureBounds, overlay_origin: (i32, i32), webview_scale: f64) -> CaptureBounds {
+        let webview_scale = if webview_scale.is_finite() && webview_scale > 0.0 { webview_scale } else { 1.0 };
+        let (origin_x, origin_y) = (overlay_origin.0 as f64, overlay_origin.1 as f64);
+        let (vx, vy) = (viewport.x as f64, viewport.y as f64);
+
+        // Try each screen's ratio and keep the one that lands on that same screen;
+        // the whole selection uses it so it stays a single rectangle
+        let ratio = self.screens.iter()
+            .map(|screen| (screen, webview_scale / screen_scale(screen) as f64))
+            .find(|(screen, ratio)| {
+                let x = (origin_x + vx * ratio).floor() as i32;
+                let y = (origin_y + vy * ratio).floor() as i32;
+                self.screen_at(x, y).map(|s| s.id) == Some(screen.id)
+            })
+            .map(|(_, ratio)| ratio)
+            .unwrap_or(1.0);
+
+        let x1 = origin_x + vx * ratio;
+        let y1 = origin_y + vy * ratio;
+        let x2 = origin_x + (vx + viewport.width as f64) * ratio;
+        let y2 = origin_y + (vy + viewport.height as f64) * ratio;
+
+        CaptureBounds {
+            x: x1.floor() as i32,
+            y: y1.floor() as i32,
+            width: (x2.ceil() - x1.floor()).max(0.0) as u32,
+            height: (y2.ceil() - y1.floor()).max(0.0) as u32,
+        }
+    }
+
+    /// Where `bounds` (desktop units) appears in the overlay webview - the inverse of
+    /// `overlay_to_desktop`, using the ratio of the screen showing its center
+    pub fn desktop_to_overlay(&self, bounds: &CaptureBounds, overlay_origin: (i32, i32), webview_scale: f64) -> CaptureBounds {
+        let webview_scale = if webview_scale.is_finite() && webview_scale > 0.0 { webview_scale } else { 1.0 };
+        let ratio = self.screen_for(bounds)
+            .map(|screen| webview_scale / screen_scale(screen) as f64)
+            .unwrap_or(1.0);
+
+        let x1 = (bounds.x - overlay_origin.0) as f64 / ratio;
+        let y1 = (bounds.y - overlay_origin.1) as f64 / ratio;
+        let x2 = (bounds.x - overlay_origin.0 + bounds.width as i32) as f64 / ratio;
+        let y2 = (bounds.y - overlay_origin.1 + bounds.height as i32) as f64 / ratio;
+        CaptureBounds {
+            x: x1.round() as i32,
+            y: y1.round() as i32,
+            width: (x2.round() - x1.round()).max(0.0) as u32,
+            height: (y2.round() - y1.round()).max(0.0) as u32,
+        }
+    }
+
+    /// Physical pixels covered by `bounds` on each screen it touches
+    pub fn to_physical(&self, bounds: &CaptureBounds) -> Vec<PhysicalRegion> {
+        self.screens.iter()
+            .filter_map(|screen| {
+                let area = bounds.intersection(&screen.bounds())?;
+                let scale = screen_scale(screen);
+                let framebuffer = physical_size(screen, scale);
+                Some(logical_to_physical(&area, screen, scale, framebuffer))
+            })
+            .collect()
+    }
+
+    /// Desktop-unit rectangle covered by a physical region (inverse of `to_physical`)
+    pub fn to_desktop(&self, region: &PhysicalRegion) -> Option<CaptureBounds> {
+        let screen = self.screens.iter().find(|screen| screen.id == region.screen_id)?;
+        let scale = screen_scale(screen) as f64;
+
+        let x1 = screen.x as f64 + region.x as f64 / scale;
+        let y1 = screen.y as f64 + region.y as f64 / scale;
+        let x2 = screen.x as f64 + (region.x + region.width) as f64 / scale;
+        let y2 = screen.y as f64 + (region.y + region.height) as f64 / scale;
+        Some(CaptureBounds {
+            x: x1.floor() as i32,
+            y: y1.floor() as i32,
+            width: (x2.ceil() - x1.floor()) as u32,
+            height: (y2.ceil() - y1.floor()) as u32,
+        })
+    }
+}
+
+/// Scale factor to use for a screen (1.0 when the platform reports nothing sensible)
+pub fn screen_scale(screen: &ScreenInfo) -> f32 {
+    if screen.scale_factor.is_finite() && screen.scale_factor > 0.0 {
+        screen.scale_factor
+    } else {
+        1.0
+    }
+}
+
+/// Framebuffer size of a screen at `scale`
+pub fn physical_size(screen: &ScreenInfo, scale: f32) -> (u32, u32) {
+    (
+        (screen.width as f32 * scale).round() as u32,
+        (screen.height as f32 * scale).round() as u32,
+    )
+}
+
+/// Physical pixels of `area` (desktop units, inside `screen`) in a framebuffer of
+/// `framebuffer` pixels. The origin rounds down and the far edge up, so the region
+/// always contains the whole selection; it is clamped to the framebuffer.
+pub fn logical_to_physical(area: &CaptureBounds, screen: &ScreenInfo, scale: f32, framebuffer: (u32, u32)) -> PhysicalRegion {
+    let scale = scale as f64;
+    let to_pixels = |offset: i32, size: u32, limit: u32| -> (u32, u32) {
+        let start = (offset as f64 * scale).floor().clamp(0.0, limit as f64);
+        let end = ((offset as f64 + size as f64) * scale).ceil().clamp(start, limit as f64);
+        (start as u32, (end - start) as u32)
+    };
+
+    let (x, width) = to_pixels(area.x - screen.x, area.width, framebuffer.0);
+    let (y, height) = to_pixels(area.y - screen.y, area.height, framebuffer.1);
+    PhysicalRegion { screen_id: screen.id, x, y, width, height }
+}
@@ -0,0 +1,313 @@
+// Images and PDFs from disk as the input of an analysis, instead of a screen
+// selection. The type is taken from the file's first bytes, not its extension.
+// PDFs are not rendered: a page is read through what it embeds, the scanned
+// image of a scanned page or the text of a digital one.
+use image::{ImageFormat, RgbaImage};
+use lopdf::xobject::PdfImage;
+use lopdf::{Document, Object, ObjectId};
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::Path;
+use super::capture_service;
+use super::screen_capture::CaptureBounds;
+
+/// Largest file that is read
+pub const MAX_FILE_BYTES: u64 = 50 * 1024 * 1024;
+/// Largest image decoded (about an 8K screen, or an A4 page scanned at 600 dpi)
+pub const MAX_IMAGE_PIXELS: u64 = 40_000_000;
+
+// An embedded image this close to the page's aspect ratio is taken to be the page
+const PAGE_ASPECT_TOLERANCE: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Png,
+    Jpeg,
+    WebP,
+    Bmp,
+    Pdf,
+}
+
+impl FileKind {
+    /// The kind of file `bytes` start like, if it is one we read
+    pub fn sniff(bytes: &[u8]) -> Option<FileKind> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(FileKind::Png)
+        } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(FileKind::Jpeg)
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some(FileKind::WebP)
+        } else if bytes.starts_with(b"BM") {
+            Some(FileKind::Bmp)
+        } else if bytes.starts_with(b"%PDF-") {
+            Some(FileKind::Pdf)
+        } else {
+            None
+        }
+    }
+
+    fn image_format(self) -> Option<ImageFormat> {
+        match self {
+            FileKind::Png => Some(ImageFormat::Png),
+            FileKind::Jpeg => Some(ImageFormat::Jpeg),
+            FileKind::WebP => Some(ImageFormat::WebP),
+            FileKind::Bmp => Some(ImageFormat::Bmp),
+            FileKind::Pdf => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileContent {
+    Image(RgbaImage),
+    Text(String), // A PDF page's own text, no OCR needed
+}
+
+pub struct LoadedFile {
+    pub name: String,
+    pub kind: FileKind,
+    pub page: u32, // 1-based; always 1 for images
+    pub page_count: u32,
+    pub content: FileContent,
+}
+
+/// Read `path` for analysis; `page` picks a PDF page (from 1, default the first)
+pub fn load(path: &Path, page: Option<u32>) -> Result<LoadedFile, String> {
+    let name = path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string());
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err(format!("{} is {} MB; files up to {} MB can be analyzed",
+                           name, metadata.len().div_ceil(1024 * 1024), MAX_FILE_BYTES / (1024 * 1024)));
+    }
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    load_bytes(&name, &bytes, page)
+}
+
+/// `load` for a file already in memory
+pub fn load_bytes(name: &str, bytes: &[u8], page: Option<u32>) -> Result<LoadedFile, String> {
+    if bytes.is_empty() {
+        return Err(format!("{} is empty", name));
+    }
+    if bytes.len() as u64 > MAX_FILE_BYTES {
+        return Err(format!("{} is larger than {} MB", name, MAX_FILE_BYTES / (1024 * 1024)));
+    }
+    let kind = FileKind::sniff(bytes)
+        .ok_or_else(|| format!("{} is not a supported file; PNG, JPEG, WebP, BMP and PDF files can be analyzed", name))?;
+
+    let (page, page_count, content) = match kind.image_format() {
+        Some(format) => {
+            if let Some(page) = page.filter(|page| *page != 1) {
+                return Err(format!("{} is an image and has no page {}", name, page));
+            }
+            (1, 1, FileContent::Image(decode_image(name, bytes, format)?))
+        },
+        None => load_pdf_page(name, bytes, page.unwrap_or(1))?,
+    };
+    if let FileContent::Image(image) = &content {
+        capture_service::check_size(&CaptureBounds { x: 0, y: 0, width: image.width(), height: image.height() })
+            .map_err(|e| format!("{}: {}", name, e))?;
+    }
+    println!("📂 Loaded {} ({:?}, page {}/{})", name, kind, page, page_count);
+    Ok(LoadedFile { name: name.to_string(), kind, page, page_count, content })
+}
+
+fn check_pixels(name: &str, width: u64, height: u64) -> Result<(), String> {
+    if width * height > MAX_IMAGE_PIXELS {
+        return Err(format!("{} is {}x{} pixels; images up to {} megapixels can be analyzed",
+                           name, width, height, MAX_IMAGE_PIXELS / 1_000_000));
+    }
+    Ok(())
+}
+
+// The header is checked before decoding, so a small file claiming a huge image
+// is refused without allocating it
+fn decode_image(name: &str, bytes: &[u8], format: ImageFormat) -> Result<RgbaImage, String> {
+    let (width, height) = image::io::Reader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .map_err(|e| format!("{} is not a valid {:?} image: {}", name, format, e))?;
+    check_pixels(name, width as u64, height as u64)?;
+    image::load_from_memory_with_format(bytes, format)
+        .map(|image| image.to_rgba8())
+        .map_err(|e| format!("Failed to decode {}: {}", name, e))
+}
+
+/// (page, page count, content) of one page of a PDF
+fn load_pdf_page(name: &str, bytes: &[u8], page: u32) -> Result<(u32, u32, FileContent), String> {
+    let document = Document::load_mem(bytes)
+        .map_err(|e| format!("{} is not a readable PDF: {}", name, e))?;
+    if document.is_encrypted() {
+        return Err(format!("{} is encrypted", name));
+    }
+    let pages = document.get_pages();
+    let page_count = pages.len() as u32;
+    let page_id = *pages.get(&page).ok_or_else(|| match page_count {
+        0 => format!("{} has no pages", name),
+        count => format!("Page {} does not exist; {} has {} page(s)", page, name, count),
+    })?;
+
+    let scan = page_image(&document, page_id, name)?;
+    let text = document.extract_text(&[page]).ok()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty());
+
+    // A scanned page is analyzed as the image even when it has an OCR text layer;
+    // a logo on a digital page is not, its text is what the page is about
+    let covers_page = |image: &RgbaImage| page_aspect_ratio(&document, page_id).is_some_and(|page_ratio| {
+        let ratio = image.width() as f32 / image.height() as f32;
+        (ratio - page_ratio).abs() / page_ratio <= PAGE_ASPECT_TOLERANCE
+    });
+    let content = match (scan, text) {
+        (Some(image), _) if covers_page(&image) => FileContent::Image(image),
+        (_, Some(text)) => FileContent::Text(text),
+        (Some(image), None) => FileContent::Image(image),
+        (None, None) => return Err(format!(
+            "Page {} of {} has no text or embedded image; drawn pages need a PDF renderer", page, name)),
+    };
+    Ok((page, page_count, content))
+}
+
+/// The largest image on the page that can be decoded, if any
+fn page_image(document: &Document, page_id: ObjectId, name: &str) -> Result<Option<RgbaImage>, String> {
+    // Fails when the page has no XObject resources at all
+    let mut images = document.get_page_images(page_id).unwrap_or_default();
+    images.retain(|image| !matches!(image.origin_dict.get(b"ImageMask").and_then(Object::as_bool), Ok(true)));
+    images.sort_by_key(|image| std::cmp::Reverse(image.width.max(0) * image.height.max(0)));
+
+    for image in &images {
+        check_pixels(name, image.width.max(0) as u64, image.height.max(0) as u64)?;
+        match decode_pdf_image(document, image) {
+            Ok(decoded) => return Ok(Some(decoded)),
+            Err(e) => println!("⚠️ Skipping image in {}: {}", name, e),
+        }
+    }
+    Ok(None)
+}
+
+fn decode_pdf_image(document: &Document, image: &PdfImage) -> Result<RgbaImage, String> {
+    let filters = image.filters.clone().unwrap_or_default();
+    if filters.iter().any(|filter| filter == "DCTDecode") {
+        if filters.len() != 1 {
+            return Err(format!("{} images are not supported", filters.join("+")));
+        }
+        return image::load_from_memory_with_format(image.content, ImageFormat::Jpeg)
+            .map(|decoded| decoded.to_rgba8())
+            .map_err(|e| format!("Invalid JPEG: {}", e));
+    }
+
+    let samples = if filters.is_empty() {
+        image.content.to_vec()
+    } else {
+        // lopdf refuses to decompress image streams, but the filters are the same
+        let mut stream = document.get_object(image.id)
+            .and_then(Object::as_stream)
+            .map_err(|e| format!("Invalid image: {}", e))?
+            .clone();
+        stream.dict.remove(b"Subtype");
+        stream.decompressed_content()
+            .map_err(|_| format!("{} images are not supported", filters.join("+")))?
+    };
+
+    let components = color_components(document, image.origin_dict.get(b"ColorSpace").ok())?;
+    let inverted = image.origin_dict.get(b"Decode").and_then(Object::as_array).ok()
+        .and_then(|decode| decode.first())
+        .and_then(|first| first.as_float().ok())
+        .is_some_and(|first| first >= 1.0);
+    let bits = image.bits_per_component.unwrap_or(8) as u32;
+    samples_to_rgba(&samples, image.width as u32, image.height as u32, components, bits, inverted)
+}
+
+// Samples per pixel of a color space; palettes, Lab and the like are not read
+fn color_components(document: &Document, color_space: Option<&Object>) -> Result<u32, String> {
+    let Some(color_space) = color_space else {
+        return Err("Image has no color space".to_string());
+    };
+    let (_, color_space) = document.dereference(color_space).map_err(|e| format!("Invalid color space: {}", e))?;
+    let (family, parameters) = match color_space {
+        Object::Name(name) => (name.as_slice(), None),
+        Object::Array(array) => (array.first().and_then(|first| first.as_name().ok()).unwrap_or_default(), array.get(1)),
+        _ => return Err("Invalid color space".to_string()),
+    };
+    match family {
+        b"DeviceGray" | b"CalGray" | b"G" => Ok(1),
+        b"DeviceRGB" | b"CalRGB" | b"RGB" => Ok(3),
+        b"DeviceCMYK" | b"CMYK" => Ok(4),
+        // ICC profiles say how many components they take
+        b"ICCBased" => parameters
+            .and_then(|profile| document.dereference(profile).ok())
+            .and_then(|(_, profile)| profile.as_stream().ok())
+            .and_then(|profile| profile.dict.get(b"N").and_then(Object::as_i64).ok())
+            .filter(|n| matches!(n, 1 | 3 | 4))
+            .map(|n| n as u32)
+            .ok_or_else(|| "Invalid ICC color space".to_string()),
+        other => Err(format!("{} color space is not supported", String::from_utf8_lossy(other))),
+    }
+}
+
+/// Raw PDF samples (rows padded to whole bytes) as RGBA. Gray, RGB and CMYK at 8
+/// bits, and 1-bit gray (bilevel scans), are supported.
+pub fn samples_to_rgba(samples: &[u8], width: u32, height: u32, components: u32, bits: u32, inverted: bool) -> Result<RgbaImage, String> {
+    if width == 0 || height == 0 {
+        return Err("Image is empty".to_string());
+    }
+    if !(bits == 8 || (bits == 1 && components == 1)) {
+        return Err(format!("{}-bit images with {} component(s) are not supported", bits, components));
+    }
+    let row_bytes = (width as usize * components as usize * bits as usize).div_ceil(8);
+    if samples.len() < row_bytes * height as usize {
+        return Err(format!("Image data is truncated ({} of {} bytes)", samples.len(), row_bytes * height as usize));
+    }
+
+    let mut image = RgbaImage::new(width, height);
+    for (y, row) in samples.chunks(row_bytes).take(height as usize).enumerate() {
+        for x in 0..width as usize {
+            let sample = |i: usize| {
+                let value = if bits == 1 {
+                    if row[i / 8] & (0x80 >> (i % 8)) != 0 { 255 } else { 0 }
+                } else {
+                    row[i]
+                };
+                if inverted { 255 - value } else { value }
+            };
+            let pixel = match components {
+                1 => {
+                    let gray = sample(x);
+                    [gray, gray, gray, 255]
+                },
+                3 => [sample(x * 3), sample(x * 3 + 1), sample(x * 3 + 2), 255],
+                _ => {
+                    let k = 255 - sample(x * 4 + 3) as u32;
+                    let channel = |i: usize| ((255 - sample(x * 4 + i) as u32) * k / 255) as u8;
+                    [channel(0), channel(1), channel(2), 255]
+                },
+            };
+            image.put_pixel(x as u32, y as u32, image::Rgba(pixel));
+        }
+    }
+    Ok(image)
+}
+
+// Width / height of the page's media box, which pages may inherit from their parents
+fn page_aspect_ratio(document: &Document, page_id: ObjectId) -> Option<f32> {
+    let mut node = document.get_dictionary(page_id).ok()?;
+    for _ in 0..16 {
+        if let Ok(media_box) = node.get(b"MediaBox") {
+            let (_, media_box) = document.dereference(media_box).ok()?;
+            let values: Vec<f32> = media_box.as_array().ok()?.iter()
+                .filter_map(|value| value.as_float().ok())
+                .collect();
+            let [x1, y1, x2, y2] = values[..] else { return None };
+            let (width, height) = ((x2 - x1).abs(), (y2 - y1).abs());
+            return (width > 0.0 && height > 0.0).then_some(width / height);
+        }
+        node = document.get_dictionary(node.get(b"Parent").ok()?.as_reference().ok()?).ok()?;
+    }
+    None
+}
@@ -0,0 +1,165 @@
+// Output encoding for captures: format, compression and downscaling are picked per
+// request, so uploads to the analysis backend can be smaller than the webview's PNGs
+use base64::Engine;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::{imageops, ColorType, ImageEncoder, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Webp, // Lossless
+    Jpeg,
+    Rgba, // Raw pixels, row by row; width and height come with the result
+}
+
+impl OutputFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Webp => "image/webp",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::Rgba => "application/octet-stream",
+        }
+    }
+}
+
+/// PNG compression effort; the others trade encode time for size
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PngCompression {
+    #[default]
+    Fast,
+    Default,
+    Best,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    pub quality: u8, // JPEG quality, 1-100
+    pub compression: PngCompression,
+    pub max_edge: Option<u32>, // Downscale so the longer side is at most this many pixels
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Png,
+            quality: 85,
+            compression: PngCompression::Fast,
+            max_edge: None,
+        }
+    }
+}
+
+/// An encoded capture as a data URL, with what it contains
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EncodedCapture {
+    pub image_data: String,
+    pub mime_type: &'static str,
+    pub byte_size: usize, // Encoded bytes, before base64
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An encoded capture as plain bytes, for handing out without base64
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodedBytes {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn encode(image: &RgbaImage, options: &OutputOptions) -> Result<EncodedCapture, String> {
+    encode_with_buffer(image, options, &mut Vec::new())
+}
+
+/// Like `encode`, but encodes into `buffer` so repeated captures reuse its allocation
+pub fn encode_with_buffer(image: &RgbaImage, options: &OutputOptions, buffer: &mut Vec<u8>) -> Result<EncodedCapture, String> {
+    let (width, height) = write_encoded(image, options, buffer)?;
+    Ok(EncodedCapture {
+        image_data: data_url(options.format.mime_type(), buffer)?,
+        mime_type: options.format.mime_type(),
+        byte_size: buffer.len(),
+        width,
+        height,
+    })
+}
+
+/// Encode without the data URL
+pub fn encode_bytes(image: &RgbaImage, options: &OutputOptions) -> Result<EncodedBytes, String> {
+    let mut bytes = Vec::new();
+    let (width, height) = write_encoded(image, options, &mut bytes)?;
+    Ok(EncodedBytes { bytes, mime_type: options.format.mime_type(), width, height })
+}
+
+// Encodes into `buffer` (cleared first) and returns the encoded dimensions
+fn write_encoded(image: &RgbaImage, options: &OutputOptions, buffer: &mut Vec<u8>) -> Result<(u32, u32), String> {
+    let image = downscale(image, options.max_edge);
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return Err("Cannot encode an empty capture".to_string());
+    }
+
+    buffer.clear();
+    match options.format {
+        OutputFormat::Png => {
+            let compression = match options.compression {
+                PngCompression::Fast => CompressionType::Fast,
+                PngCompression::Default => CompressionType::Default,
+                PngCompression::Best => CompressionType::Best,
+            };
+            PngEncoder::new_with_quality(&mut *buffer, compression, FilterType::Adaptive)
+                .write_image(image.as_raw(), width, height, ColorType::Rgba8)
+                .map_err(|e| format!("PNG encoding failed: {}", e))?;
+        },
+        OutputFormat::Webp => {
+            WebPEncoder::new_lossless(&mut *buffer)
+                .write_image(image.as_raw(), width, height, ColorType::Rgba8)
+                .map_err(|e| format!("WebP encoding failed: {}", e))?;
+        },
+        OutputFormat::Jpeg => {
+            // JPEG has no alpha; screen captures are opaque anyway
+            let rgb = image::DynamicImage::ImageRgba8(image.into_owned()).to_rgb8();
+            JpegEncoder::new_with_quality(&mut *buffer, options.quality.clamp(1, 100))
+                .write_image(rgb.as_raw(), width, height, ColorType::Rgb8)
+                .map_err(|e| format!("JPEG encoding failed: {}", e))?;
+        },
+        OutputFormat::Rgba => buffer.extend_from_slice(image.as_raw()),
+    }
+    Ok((width, height))
+}
+
+/// `image` shrunk so its longer side fits `max_edge`, or as it is when it already does
+pub fn downscale(image: &RgbaImage, max_edge: Option<u32>) -> Cow<'_, RgbaImage> {
+    let (width, height) = image.dimensions();
+    let longest = width.max(height);
+    match max_edge {
+        Some(max_edge) if max_edge > 0 && longest > max_edge => {
+            let scale = max_edge as f64 / longest as f64;
+            let target_width = ((width as f64 * scale).round() as u32).max(1);
+            let target_height = ((height as f64 * scale).round() as u32).max(1);
+            Cow::Owned(imageops::resize(image, target_width, target_height, imageops::FilterType::Triangle))
+        },
+        _ => Cow::Borrowed(image),
+    }
+}
+
+// Data URL allocated once at its final size
+fn data_url(mime_type: &str, bytes: &[u8]) -> Result<String, String> {
+    let base64_len = base64::encoded_len(bytes.len(), true).ok_or("Capture too large to encode")?;
+    let mut data = String::with_capacity("data:;base64,".len() + mime_type.len() + base64_len);
+    data.push_str("data:");
+    data.push_str(mime_type);
+    data.push_str(";base64,");
+    base64::engine::general_purpose::STANDARD.encode_string(bytes, &mut data);
+    Ok(data)
+}
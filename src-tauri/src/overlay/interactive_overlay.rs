@@ -1,8 +1,12 @@
 use tauri::{AppHandle, Manager, WebviewWindow, WebviewWindowBuilder, WebviewUrl};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex, mpsc};
-use super::screen_capture::{ScreenCapture, CaptureBounds, ScreenInfo};
+use super::capture_source::{default_source, CaptureSource};
+use super::screen_capture::{ScreenCapture, CaptureBounds};
 use super::selection_overlay::SelectionResult;
+use super::content_classifier;
+use crate::ocr::{get_ocr_service, OCRResult, OCRService, PreprocessOptions};
+use image::DynamicImage;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct DragState {
@@ -33,12 +37,39 @@ pub struct ContentAnalysis {
     pub needs_ocr: bool,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum ContentType {
     PlainText,
     ImageWithText,
     PureImage,
     Unknown,
+    Code,
+    Table,
+    Chart,
+    Photo,
+    UiScreenshot,
+}
+
+impl ContentType {
+    /// Whether the content carries text worth extracting
+    pub fn needs_ocr(&self) -> bool {
+        !matches!(self, ContentType::PureImage | ContentType::Photo | ContentType::Unknown)
+    }
+
+    /// Display name for summaries
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContentType::PlainText => "Plain text",
+            ContentType::ImageWithText => "Image with text",
+            ContentType::PureImage => "Image",
+            ContentType::Unknown => "Unknown content",
+            ContentType::Code => "Code",
+            ContentType::Table => "Table",
+            ContentType::Chart => "Chart",
+            ContentType::Photo => "Photo",
+            ContentType::UiScreenshot => "UI screenshot",
+        }
+    }
 }
 
 pub struct InteractiveOverlay {
@@ -68,18 +99,18 @@ impl InteractiveOverlay {
             return Err("No screens available".to_string());
         }
         
-        let primary_screen = &screen_info[0];
-        println!("📺 Primary screen: {}x{}", primary_screen.width, primary_screen.height);
+        let desktop = ScreenCapture::desktop_bounds(&screen_info).ok_or("No screens available")?;
+        println!("📺 Desktop: {}x{} across {} screen(s)", desktop.width, desktop.height, screen_info.len());
         
-        // Create the transparent overlay window that covers entire screen
-        let overlay_window = Self::create_fullscreen_overlay(&app_handle, primary_screen).await?;
+        // Create the transparent overlay window that covers every screen
+        let overlay_window = Self::create_fullscreen_overlay(&app_handle, &desktop).await?;
         
         // Start the selection process
         Self::handle_fullscreen_selection(overlay_window, &app_handle).await
     }
 
     /// Create a transparent fullscreen overlay window
-    async fn create_fullscreen_overlay(app_handle: &AppHandle, screen_info: &ScreenInfo) -> Result<WebviewWindow, String> {
+    async fn create_fullscreen_overlay(app_handle: &AppHandle, desktop: &CaptureBounds) -> Result<WebviewWindow, String> {
         println!("🖼️ Creating transparent fullscreen overlay window...");
         
         // Create overlay window configuration  
@@ -89,8 +120,8 @@ impl InteractiveOverlay {
             WebviewUrl::App("selection-overlay.html".into())
         )
         .title("FrameSense Selection Overlay")
-        .inner_size(screen_info.width as f64, screen_info.height as f64)
-        .position(0.0, 0.0)
+        .inner_size(desktop.width as f64, desktop.height as f64)
+        .position(desktop.x as f64, desktop.y as f64)
         .resizable(false)
         .maximizable(false)
         .minimizable(false)
@@ -158,98 +189,116 @@ impl InteractiveOverlay {
     }
 
     /// Analyze captured content to determine if it's text, image, etc.
-    fn analyze_content(image_data: &str) -> ContentAnalysis {
+    fn analyze_content(img: &DynamicImage) -> ContentAnalysis {
         println!("🔍 Analyzing content type...");
-        
-        // Basic analysis - in full implementation this would use:
-        // 1. Text detection algorithms
-        // 2. Image analysis
-        // 3. OCR confidence scoring
-        
-        // For MVP: Simulate intelligent detection
-        let has_text_patterns = image_data.len() > 1000; // Rough heuristic
-        
-        if has_text_patterns {
-            ContentAnalysis {
-                content_type: ContentType::ImageWithText,
-                confidence: 0.8,
-                text_content: None,
-                needs_ocr: true,
-            }
-        } else {
-            ContentAnalysis {
-                content_type: ContentType::PureImage,
-                confidence: 0.9,
-                text_content: None,
-                needs_ocr: false,
-            }
+
+        let classification = content_classifier::classify(img);
+        println!("✅ Content classified as {:?} ({:.0}% confidence)",
+                 classification.content_type, classification.confidence * 100.0);
+
+        ContentAnalysis {
+            needs_ocr: classification.content_type.needs_ocr(),
+            content_type: classification.content_type,
+            confidence: classification.confidence,
+            text_content: None,
         }
     }
 
     /// Process the selection based on content type
     pub async fn process_selection(result: &SelectionResult) -> Result<ProcessedContent, String> {
+        Self::process_selection_from(default_source(), result).await
+    }
+
+    /// `process_selection` with screen details (scale factor) taken from `source`
+    pub async fn process_selection_from(source: Arc<dyn CaptureSource>, result: &SelectionResult) -> Result<ProcessedContent, String> {
         println!("⚙️ Processing selection based on content type...");
-        
-        let content_analysis = Self::analyze_content(&result.image_data);
-        
-        match content_analysis.content_type {
-            ContentType::PlainText => {
-                println!("📝 Plain text detected - direct processing");
-                // Direct text processing
-                Ok(ProcessedContent {
-                    content_type: ContentType::PlainText,
-                    extracted_text: Some("Detected plain text content".to_string()),
-                    ai_analysis: Some("This appears to be plain text content that can be directly processed.".to_string()),
-                })
-            },
-            ContentType::ImageWithText => {
-                println!("🔍 Image with text detected - running OCR");
-                // OCR processing
-                let ocr_text = Self::run_ocr(&result.image_data).await?;
-                Ok(ProcessedContent {
-                    content_type: ContentType::ImageWithText,
-                    extracted_text: Some(ocr_text.clone()),
-                    ai_analysis: Some(format!("OCR extracted text: {}", ocr_text)),
-                })
+
+        if result.cancelled {
+            return Err("Selection was cancelled".to_string());
+        }
+
+        let img = OCRService::decode_image(&result.image_data)?;
+        Self::process(img, || ScreenCapture::scale_factor_from(source.as_ref(), &result.bounds)).await
+    }
+
+    /// Classify an image that did not come from the screen (a file, the clipboard)
+    /// and OCR it like a selection; its pixels are taken to be physical ones
+    pub async fn process_image(img: DynamicImage) -> Result<ProcessedContent, String> {
+        println!("⚙️ Processing image based on content type...");
+        Self::process(img, || 1.0).await
+    }
+
+    // `scale_factor` is only asked for when the content needs OCR
+    async fn process(img: DynamicImage, scale_factor: impl FnOnce() -> f32) -> Result<ProcessedContent, String> {
+        let content_analysis = Self::analyze_content(&img);
+
+        // Text-bearing content goes through OCR; photos and plain images skip it
+        let ocr = if content_analysis.needs_ocr {
+            Some(Self::run_ocr(img, scale_factor()).await)
+        } else {
+            None
+        };
+
+        let analysis = Self::describe(&content_analysis.content_type, ocr.as_ref());
+        let (extracted_text, ocr_confidence) = match ocr {
+            Some(Ok(ocr_result)) => {
+                let text = ocr_result.text.trim().to_string();
+                (ocr_result.has_text.then_some(text), Some(ocr_result.confidence))
             },
-            ContentType::PureImage => {
-                println!("🖼️ Pure image detected - AI image analysis");
-                // AI image analysis
-                Ok(ProcessedContent {
-                    content_type: ContentType::PureImage,
-                    extracted_text: None,
-                    ai_analysis: Some("This appears to be an image without significant text content.".to_string()),
-                })
+            Some(Err(error)) => {
+                println!("⚠️ OCR failed, continuing without text: {}", error);
+                (None, None)
             },
-            ContentType::Unknown => {
-                println!("❓ Unknown content type - general analysis");
-                Ok(ProcessedContent {
-                    content_type: ContentType::Unknown,
-                    extracted_text: None,
-                    ai_analysis: Some("Content type could not be determined.".to_string()),
-                })
-            }
-        }
+            None => (None, None),
+        };
+
+        Ok(ProcessedContent {
+            content_type: content_analysis.content_type,
+            confidence: content_analysis.confidence,
+            extracted_text,
+            ocr_confidence,
+            ai_analysis: Some(analysis),
+        })
     }
 
-    /// Run OCR on the image data
-    async fn run_ocr(_image_data: &str) -> Result<String, String> {
+    /// Run OCR on the selection with the shared service
+    async fn run_ocr(img: DynamicImage, scale_factor: f32) -> Result<OCRResult, String> {
         println!("🔤 Running OCR analysis...");
-        
-        // For MVP: Simulate OCR processing
-        // In full implementation: Use Tesseract native bindings
-        
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        
-        // Simulate extracted text
-        Ok("Sample OCR extracted text from the selected region".to_string())
+
+        tokio::task::spawn_blocking(move || {
+            let preprocess = PreprocessOptions {
+                scale_factor: Some(scale_factor),
+                ..Default::default()
+            };
+            get_ocr_service()?
+                .with_preprocessing(preprocess)
+                .extract_text_from_image(&img)
+        }).await.map_err(|e| format!("Task error: {}", e))?
+    }
+
+    /// Short summary of what the selection contains
+    fn describe(content_type: &ContentType, ocr: Option<&Result<OCRResult, String>>) -> String {
+        let label = content_type.label();
+        match ocr {
+            Some(Ok(result)) if result.has_text => {
+                let lines = result.text.lines().filter(|line| !line.trim().is_empty()).count();
+                format!("{} with {} line(s) of recognized text ({:.0}% OCR confidence).",
+                        label, lines, result.confidence * 100.0)
+            },
+            Some(Ok(_)) => format!("{} detected, but no text was recognized.", label),
+            Some(Err(error)) => format!("{} detected, but text recognition failed: {}", label, error),
+            None if *content_type == ContentType::Unknown => "Content type could not be determined.".to_string(),
+            None => format!("{} without significant text content.", label),
+        }
     }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ProcessedContent {
     pub content_type: ContentType,
+    pub confidence: f32, // Classification confidence
     pub extracted_text: Option<String>,
+    pub ocr_confidence: Option<f32>,
     pub ai_analysis: Option<String>,
 }
 
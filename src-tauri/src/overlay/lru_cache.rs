@@ -0,0 +1,193 @@
+// Least-recently-used map with a byte budget
+// Entries live in a slab and are linked most → least recently used, so lookups,
+// inserts, touches and evictions are all O(1).
+use std::collections::HashMap;
+use std::hash::Hash;
+
+const NIL: usize = usize::MAX;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    bytes: usize,
+    prev: usize, // Towards the most recently used end
+    next: usize,
+}
+
+pub struct LruCache<K, V> {
+    map: HashMap<K, usize>,
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    head: usize, // Most recently used
+    tail: usize, // Least recently used
+    bytes: usize,
+    budget: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Empty cache holding at most `budget` bytes
+    pub fn new(budget: usize) -> Self {
+        Self {
+            map: HashMap::new(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: NIL,
+            tail: NIL,
+            bytes: 0,
+            budget,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Bytes charged for all entries
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Change the budget, evicting least recently used entries that no longer fit
+    pub fn set_budget(&mut self, budget: usize) -> Vec<(K, V)> {
+        self.budget = budget;
+        self.evict_to(budget)
+    }
+
+    /// Look up and mark as most recently used
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = *self.map.get(key)?;
+        self.move_to_front(index);
+        self.nodes[index].as_mut().map(|node| &mut node.value)
+    }
+
+    /// Insert (or replace) an entry charged `bytes`. Returns what had to be evicted
+    /// to stay within budget; an entry larger than the whole budget is not kept.
+    pub fn insert(&mut self, key: K, value: V, bytes: usize) -> Vec<(K, V)> {
+        self.remove(&key);
+        if bytes > self.budget {
+            return vec![(key, value)];
+        }
+
+        let evicted = self.evict_to(self.budget - bytes);
+        let node = Node { key: key.clone(), value, bytes, prev: NIL, next: NIL };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            },
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.map.insert(key, index);
+        self.link_front(index);
+        self.bytes += bytes;
+        evicted
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.map.remove(key)?;
+        self.unlink(index);
+        let node = self.nodes[index].take()?;
+        self.free.push(index);
+        self.bytes -= node.bytes;
+        Some(node.value)
+    }
+
+    /// Drop the least recently used entry
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let key = self.nodes[self.tail].as_ref()?.key.clone();
+        let value = self.remove(&key)?;
+        Some((key, value))
+    }
+
+    /// Keep only entries for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &V) -> bool) -> usize {
+        let doomed: Vec<K> = self.iter()
+            .filter(|(key, value)| !keep(key, value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &doomed {
+            self.remove(key);
+        }
+        doomed.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.head = NIL;
+        self.tail = NIL;
+        self.bytes = 0;
+    }
+
+    /// Entries from most to least recently used
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut index = self.head;
+        std::iter::from_fn(move || {
+            let node = self.nodes.get(index)?.as_ref()?;
+            index = node.next;
+            Some((&node.key, &node.value))
+        })
+    }
+
+    fn evict_to(&mut self, limit: usize) -> Vec<(K, V)> {
+        let mut evicted = Vec::new();
+        while self.bytes > limit {
+            match self.pop_lru() {
+                Some(entry) => evicted.push(entry),
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    fn move_to_front(&mut self, index: usize) {
+        if self.head != index {
+            self.unlink(index);
+            self.link_front(index);
+        }
+    }
+
+    fn link_front(&mut self, index: usize) {
+        if let Some(node) = self.nodes[index].as_mut() {
+            node.prev = NIL;
+            node.next = self.head;
+        }
+        if let Some(head) = self.nodes.get_mut(self.head).and_then(Option::as_mut) {
+            head.prev = index;
+        }
+        self.head = index;
+        if self.tail == NIL {
+            self.tail = index;
+        }
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = match self.nodes[index].as_ref() {
+            Some(node) => (node.prev, node.next),
+            None => return,
+        };
+        match self.nodes.get_mut(prev).and_then(Option::as_mut) {
+            Some(node) => node.next = next,
+            None => self.head = next,
+        }
+        match self.nodes.get_mut(next).and_then(Option::as_mut) {
+            Some(node) => node.prev = prev,
+            None => self.tail = prev,
+        }
+    }
+}
@@ -4,10 +4,39 @@ pub mod native_overlay;
 pub mod interactive_overlay;
 pub mod overlay_manager;
 pub mod screenshot_cache;
+pub mod content_classifier;
+pub mod coordinates;
+pub mod capture_source;
+pub mod lru_cache;
+pub mod image_encoding;
+pub mod capture_registry;
+pub mod capture_service;
+pub mod window_capture;
+pub mod scroll_capture;
+pub mod capture_scheduler;
+pub mod recording_encoding;
+pub mod screen_recorder;
+pub mod file_input;
+#[cfg(target_os = "linux")]
+pub mod portal_capture;
+#[cfg(target_os = "linux")]
+pub mod x11_windows;
 
-pub use screen_capture::{ScreenCapture, CaptureBounds, CaptureResult, ScreenInfo};
+pub use screen_capture::{ScreenCapture, CaptureBounds, ScreenInfo};
 pub use selection_overlay::{SelectionOverlay, SelectionResult, MousePosition, SelectionState, get_overlay};
 pub use native_overlay::{NativeOverlay, ScreenQuadrant};
 pub use interactive_overlay::{InteractiveOverlay, DragState, ContentAnalysis, ContentType, ProcessedContent, get_interactive_overlay};
 pub use overlay_manager::OverlayManager;
-pub use screenshot_cache::ScreenshotCache; 
\ No newline at end of file
+pub use screenshot_cache::ScreenshotCache;
+pub use coordinates::ScreenLayout;
+
+pub use capture_source::{CaptureSource, FixtureSource};
+pub use image_encoding::{EncodedCapture, OutputFormat, OutputOptions};
+pub use capture_registry::{CaptureHandle, CaptureRegistry};
+pub use capture_service::{CaptureError, CaptureResult, CaptureService};
+pub use window_capture::{OverlayWindow, WindowInfo};
+pub use scroll_capture::{ScrollCapture, ScrollStatus, ScrollStitcher, StitchStep};
+pub use capture_scheduler::{CaptureScheduler, ScheduleEvent, ScheduleInfo, ScheduleMode};
+pub use recording_encoding::RecordingFormat;
+pub use screen_recorder::{Recording, RecordingOptions, RecordingResult, RecordingState, RecordingStatus, ScreenRecorder};
+pub use file_input::{FileContent, FileKind};
\ No newline at end of file
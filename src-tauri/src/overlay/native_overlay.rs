@@ -10,6 +10,8 @@ use objc::runtime::{Object, Class, Sel};
 use objc::{msg_send, sel, sel_impl, class};
 
 use std::sync::{Arc, Mutex, mpsc};
+use super::capture_service::CaptureService;
+use super::image_encoding::OutputOptions;
 use super::screen_capture::{ScreenCapture, CaptureBounds, ScreenInfo};
 use super::selection_overlay::{SelectionResult, MousePosition};
 
@@ -52,7 +54,7 @@ impl NativeOverlay {
             return Err("No screens available".to_string());
         }
         
-        let primary_screen = &screen_info[0];
+        let primary_screen = ScreenCapture::find_primary(&screen_info).ok_or("No screens available")?;
         println!("📺 Screen: {}x{}", primary_screen.width, primary_screen.height);
 
         // Create channel for communication
@@ -76,8 +78,7 @@ impl NativeOverlay {
             };
             
             // Capture the area
-            let runtime = tokio::runtime::Runtime::new().unwrap();
-            match runtime.block_on(ScreenCapture::capture_region(bounds.clone())) {
+            match CaptureService::new().capture(&bounds, &OutputOptions::default()) {
                 Ok(capture_result) => {
                     let result = SelectionResult {
                         bounds: capture_result.bounds,
@@ -115,17 +116,17 @@ impl NativeOverlay {
             return Err("No screens available".to_string());
         }
         
-        let primary_screen = &screen_info[0];
+        let primary_screen = ScreenCapture::find_primary(&screen_info).ok_or("No screens available")?;
         
         // Create a selection in center of screen
         let bounds = CaptureBounds {
-            x: (primary_screen.width / 4) as i32,
-            y: (primary_screen.height / 4) as i32,
+            x: primary_screen.x + (primary_screen.width / 4) as i32,
+            y: primary_screen.y + (primary_screen.height / 4) as i32,
             width: primary_screen.width / 2,
             height: primary_screen.height / 2,
         };
         
-        match ScreenCapture::capture_region(bounds.clone()).await {
+        match CaptureService::new().capture(&bounds, &OutputOptions::default()) {
             Ok(capture_result) => Ok(SelectionResult {
                 bounds: capture_result.bounds,
                 image_data: capture_result.image_data,
@@ -139,7 +140,7 @@ impl NativeOverlay {
     pub async fn manual_selection(bounds: CaptureBounds) -> Result<SelectionResult, String> {
         println!("🎯 Manual selection: {:?}", bounds);
         
-        match ScreenCapture::capture_region(bounds.clone()).await {
+        match CaptureService::new().capture(&bounds, &OutputOptions::default()) {
             Ok(capture_result) => Ok(SelectionResult {
                 bounds: capture_result.bounds,
                 image_data: capture_result.image_data,
@@ -159,11 +160,12 @@ impl NativeOverlay {
             return Err("No screens available".to_string());
         }
         
-        let screen = &screen_info[0];
+        let screen = ScreenCapture::find_primary(&screen_info).ok_or("No screens available")?;
         let half_width = screen.width / 2;
         let half_height = screen.height / 2;
         
-        let bounds = match quadrant {
+        // Quadrants of the primary screen, offset to its desktop position
+        let local = match quadrant {
             ScreenQuadrant::TopLeft => CaptureBounds {
                 x: 0,
                 y: 0,
@@ -195,6 +197,7 @@ impl NativeOverlay {
                 height: half_height,
             },
         };
+        let bounds = CaptureBounds { x: screen.x + local.x, y: screen.y + local.y, ..local };
         
         println!("📐 Selecting {} quadrant: {:?}", quadrant.name(), bounds);
         Self::manual_selection(bounds).await
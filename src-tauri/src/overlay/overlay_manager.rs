@@ -1,6 +1,6 @@
 use tauri::{WebviewWindow, WebviewWindowBuilder, WebviewUrl};
 use std::time::{Duration, Instant};
-use screenshots;
+use super::screen_capture::ScreenCapture;
 
 pub struct OverlayManager {
     overlay_window: Option<WebviewWindow>,
@@ -72,24 +72,9 @@ impl OverlayManager {
     fn create_react_overlay_once(&self, app: &tauri::AppHandle) -> Result<WebviewWindow, String> {
         println!("🚀 Creating optimized React overlay...");
         
-        // Get screen dimensions for fullscreen overlay
-        let (screen_width, screen_height) = match screenshots::Screen::all() {
-            Ok(screens) => {
-                if let Some(screen) = screens.first() {
-                    let width = screen.display_info.width as f64;
-                    let height = screen.display_info.height as f64;
-                    println!("📺 React overlay using screen: {}x{}", width, height);
-                    (width, height)
-                } else {
-                    println!("⚠️ No screens found, using fallback 1920x1080");
-                    (1920.0, 1080.0)
-                }
-            },
-            Err(e) => {
-                println!("❌ Failed to get screen info: {}, using fallback", e);
-                (1920.0, 1080.0)
-            }
-        };
+        // Cover every screen so a selection can be made on (or across) any monitor
+        let desktop = ScreenCapture::overlay_bounds();
+        println!("📺 React overlay covering desktop: {}x{} at ({}, {})", desktop.width, desktop.height, desktop.x, desktop.y);
         
         // Create React-based overlay window (use same ID as regular overlay for consistency)
         let overlay = WebviewWindowBuilder::new(
@@ -98,8 +83,8 @@ impl OverlayManager {
             WebviewUrl::App("overlay".into())  // React route från OverlayApp.tsx
         )
         .title("FrameSense Selection")
-        .inner_size(screen_width, screen_height)
-        .position(0.0, 0.0)
+        .inner_size(desktop.width as f64, desktop.height as f64)
+        .position(desktop.x as f64, desktop.y as f64)
         .decorations(false)      // No window borders
         .transparent(true)       // Make window transparent!
         .always_on_top(true)     // Above all other windows
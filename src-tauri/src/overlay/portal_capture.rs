@@ -0,0 +1,424 @@
+// Wayland capture through xdg-desktop-portal (org.freedesktop.portal.Screenshot)
+// `screenshots` reads the X11 framebuffer, which is black or missing under Wayland
+// compositors - the portal asks the compositor for the pixels instead. When no
+// portal answers on the session bus we fall back to X11 capture.
+//
+// Only the Screenshot portal is implemented. ScreenCast (a PipeWire video stream)
+// is out of scope: it needs a PipeWire client. Every frame of a recording, scrolling
+// capture or change poll is a Screenshot call that writes a file, so this source
+// caps them at PORTAL_MAX_FPS (see `max_frame_rate`).
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::blocking::Connection;
+use dbus::message::MatchRule;
+use dbus::Message;
+use image::{imageops, RgbaImage};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use super::capture_service::CaptureError;
+use super::capture_source::{CaptureSource, ScreenshotsSource};
+use super::coordinates;
+use super::screen_capture::{ScreenCapture, ScreenInfo};
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SCREENSHOT_INTERFACE: &str = "org.freedesktop.portal.Screenshot";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+// Long enough for the one-time permission dialog some compositors show
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+// A capture spanning several screens is served from one portal frame
+const FRAME_REUSE: Duration = Duration::from_secs(1);
+// Faster continuous capture would only repeat the reused frame
+pub const PORTAL_MAX_FPS: u32 = 1;
+
+/// True when running in a Wayland session
+pub fn is_wayland_session() -> bool {
+    session_is_wayland(
+        std::env::var("XDG_SESSION_TYPE").ok().as_deref(),
+        std::env::var("WAYLAND_DISPLAY").ok().as_deref(),
+    )
+}
+
+pub fn session_is_wayland(session_type: Option<&str>, wayland_display: Option<&str>) -> bool {
+    match session_type {
+        Some(session) if !session.is_empty() => session.eq_ignore_ascii_case("wayland"),
+        _ => wayland_display.is_some_and(|display| !display.is_empty()),
+    }
+}
+
+/// Outcome of a portal request (org.freedesktop.portal.Request::Response)
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortalResponse {
+    Success { uri: String },
+    /// The user dismissed the dialog or denied the permission
+    Cancelled,
+    Failed(String),
+}
+
+/// Map a Response signal's arguments: 0 success, 1 cancelled, 2 other
+pub fn response_from_signal(code: u32, results: &PropMap) -> PortalResponse {
+    match code {
+        0 => match results.get("uri").and_then(|uri| uri.0.as_str()) {
+            Some(uri) => PortalResponse::Success { uri: uri.to_string() },
+            None => PortalResponse::Failed("Portal response has no uri".to_string()),
+        },
+        1 => PortalResponse::Cancelled,
+        code => PortalResponse::Failed(format!("Portal ended the request (response {})", code)),
+    }
+}
+
+/// Local path of a `file://` URI as returned by the portal (percent-decoded)
+pub fn file_path_from_uri(uri: &str) -> Result<PathBuf, String> {
+    let path = uri.strip_prefix("file://")
+        .ok_or_else(|| format!("Unsupported screenshot URI: {}", uri))?;
+    // Skip an authority part (file://localhost/...)
+    let path = &path[path.find('/').ok_or_else(|| format!("Invalid screenshot URI: {}", uri))?..];
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded)
+        .map(PathBuf::from)
+        .map_err(|_| format!("Screenshot URI is not valid UTF-8: {}", uri))
+}
+
+/// The D-Bus side of the portal. Errors mean the portal could not be reached
+/// (no session bus, no portal service); answers from the portal are responses.
+pub trait PortalBus: Send + Sync {
+    /// Call Screenshot.Screenshot and wait for its Request's Response signal
+    fn screenshot(&self) -> Result<PortalResponse, String>;
+}
+
+/// The real session bus
+#[derive(Default)]
+pub struct SessionBus {
+    address: Option<String>, // None: the user's session bus
+}
+
+impl SessionBus {
+    /// A bus at a given address instead of the session bus, e.g. a private dbus-daemon
+    pub fn with_address(address: &str) -> Self {
+        Self { address: Some(address.to_string()) }
+    }
+
+    fn connect(&self) -> Result<Connection, dbus::Error> {
+        let Some(address) = &self.address else {
+            return Connection::new_session();
+        };
+        let mut channel = dbus::channel::Channel::open_private(address)?;
+        channel.register()?;
+        Ok(Connection::from(channel))
+    }
+}
+
+impl PortalBus for SessionBus {
+    fn screenshot(&self) -> Result<PortalResponse, String> {
+        let conn = self.connect().map_err(|e| format!("No D-Bus session bus: {}", e))?;
+
+        // Subscribe before calling so the Response can't be missed. The portal puts
+        // the Request at .../request/<sender>/<handle_token>
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let token = format!("framesense_{}_{}", std::process::id(), nanos);
+        let sender = conn.unique_name().trim_start_matches(':').replace('.', "_");
+        let request_path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+
+        let response: Arc<Mutex<Option<PortalResponse>>> = Arc::new(Mutex::new(None));
+        let watch_request = |path: String| -> Result<(), String> {
+            let slot = response.clone();
+            let rule = MatchRule::new_signal(REQUEST_INTERFACE, "Response")
+                .with_path(dbus::Path::new(path)?);
+            conn.add_match(rule, move |(code, results): (u32, PropMap), _: &Connection, _: &Message| {
+                *slot.lock().unwrap() = Some(response_from_signal(code, &results));
+                false
+            }).map_err(|e| format!("Failed to watch portal request: {}", e))?;
+            Ok(())
+        };
+        watch_request(request_path.clone())?;
+
+        let mut options = PropMap::new();
+        options.insert("handle_token".to_string(), Variant(Box::new(token) as Box<dyn RefArg>));
+        options.insert("interactive".to_string(), Variant(Box::new(false) as Box<dyn RefArg>));
+
+        let proxy = conn.with_proxy(PORTAL_DESTINATION, PORTAL_PATH, CALL_TIMEOUT);
+        let (handle,): (dbus::Path,) = proxy.method_call(SCREENSHOT_INTERFACE, "Screenshot", ("", options))
+            .map_err(|e| format!("Screenshot portal unavailable: {}", e))?;
+        // Old portals ignore handle_token and pick their own request path
+        if *handle != *request_path {
+            watch_request(handle.to_string())?;
+        }
+
+        let deadline = Instant::now() + RESPONSE_TIMEOUT;
+        while Instant::now() < deadline {
+            conn.process(Duration::from_millis(100))
+                .map_err(|e| format!("D-Bus connection lost: {}", e))?;
+            if let Some(response) = response.lock().unwrap().take() {
+                return Ok(response);
+            }
+        }
+        Ok(PortalResponse::Failed(format!("No portal response within {}s", RESPONSE_TIMEOUT.as_secs())))
+    }
+}
+
+/// Capture through the Screenshot portal. The portal returns the whole desktop;
+/// screens are cut out of it using the layout X11/XWayland reports.
+pub struct PortalSource {
+    bus: Box<dyn PortalBus>,
+    fallback: Arc<dyn CaptureSource>,
+    portal_available: AtomicBool,
+    last_frame: Mutex<Option<(Instant, RgbaImage)>>,
+}
+
+impl PortalSource {
+    pub fn new() -> Self {
+        Self::with_bus(Box::new(SessionBus::default()), Arc::new(ScreenshotsSource))
+    }
+
+    /// `fallback` enumerates screens and captures when the portal can't be reached
+    pub fn with_bus(bus: Box<dyn PortalBus>, fallback: Arc<dyn CaptureSource>) -> Self {
+        Self {
+            bus,
+            fallback,
+            portal_available: AtomicBool::new(true),
+            last_frame: Mutex::new(None),
+        }
+    }
+
+    /// Whole-desktop frame from the portal; None once the portal is known to be missing
+    fn desktop_frame(&self) -> Result<Option<RgbaImage>, CaptureError> {
+        if !self.portal_available.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        let mut last_frame = self.last_frame.lock().unwrap();
+        if let Some((taken_at, frame)) = last_frame.as_ref() {
+            if taken_at.elapsed() < FRAME_REUSE {
+                return Ok(Some(frame.clone()));
+            }
+        }
+
+        let uri = match self.bus.screenshot() {
+            Ok(PortalResponse::Success { uri }) => uri,
+            Ok(PortalResponse::Cancelled) => return Err(CaptureError::PermissionDenied("the screenshot portal request was dismissed".to_string())),
+            Ok(PortalResponse::Failed(reason)) => return Err(CaptureError::Backend(format!("Screenshot portal failed: {}", reason))),
+            Err(e) => {
+                println!("⚠️ {}, falling back to X11 capture", e);
+                self.portal_available.store(false, Ordering::Relaxed);
+                return Ok(None);
+            }
+        };
+
+        let path = file_path_from_uri(&uri).map_err(CaptureError::Backend)?;
+        let frame = image::open(&path)
+            .map_err(|e| CaptureError::Backend(format!("Failed to load portal screenshot {}: {}", path.display(), e)))?
+            .to_rgba8();
+        // The portal saves into the user's pictures folder; the file is ours to remove
+        if let Err(e) = std::fs::remove_file(&path) {
+            println!("⚠️ Could not remove portal screenshot {}: {}", path.display(), e);
+        }
+
+        *last_frame = Some((Instant::now(), frame.clone()));
+        Ok(Some(frame))
+    }
+}
+
+impl Default for PortalSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CaptureSource for PortalSource {
+    fn name(&self) -> &str {
+        if self.portal_available.load(Ordering::Relaxed) { "xdg-desktop-portal" } else { self.fallback.name() }
+    }
+
+    fn max_frame_rate(&self) -> Option<u32> {
+        if self.portal_available.load(Ordering::Relaxed) { Some(PORTAL_MAX_FPS) } else { self.fallback.max_frame_rate() }
+    }
+
+    fn screens(&self) -> Result<Vec<ScreenInfo>, CaptureError> {
+        match self.fallback.screens() {
+            Ok(screens) if !screens.is_empty() => Ok(screens),
+            // No X11 layout at all (no XWayland): treat the portal frame as one screen
+            _ => {
+                let frame = self.desktop_frame()?.ok_or(CaptureError::NoScreens)?;
+                Ok(vec![ScreenInfo {
+                    id: 0,
+                    x: 0,
+                    y: 0,
+                    width: frame.width(),
+                    height: frame.height(),
+                    scale_factor: 1.0,
+                    is_primary: true,
+                }])
+            }
+        }
+    }
+
+    fn capture_screen(&self, screen: &ScreenInfo) -> Result<RgbaImage, CaptureError> {
+        let Some(frame) = self.desktop_frame()? else {
+            return self.fallback.capture_screen(screen);
+        };
+
+        let screens = self.screens()?;
+        let desktop = ScreenCapture::desktop_bounds(&screens).ok_or(CaptureError::NoScreens)?;
+        let desktop_screen = ScreenInfo {
+            id: screen.id,
+            x: desktop.x,
+            y: desktop.y,
+            width: desktop.width,
+            height: desktop.height,
+            scale_factor: 1.0,
+            is_primary: false,
+        };
+
+        // The frame may be at a different density than the layout (HiDPI)
+        let scale = frame.width() as f32 / desktop.width.max(1) as f32;
+        let region = coordinates::logical_to_physical(&screen.bounds(), &desktop_screen, scale, frame.dimensions());
+        if region.width == 0 || region.height == 0 {
+            return Err(CaptureError::Backend(format!("Screen {} is outside the portal screenshot", screen.id)));
+        }
+        Ok(imageops::crop_imm(&frame, region.x, region.y, region.width, region.height).to_image())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dbus::channel::{MatchingReceiver, Sender};
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::atomic::AtomicUsize;
+    use std::thread::JoinHandle;
+
+    // A dbus-daemon of our own, so the test neither needs nor disturbs the user's session
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        // The test needs dbus-daemon (the `dbus` package); without it it fails rather
+        // than passing without having checked anything
+        fn start() -> Self {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("dbus-daemon is required for the portal bus test (install dbus)");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address)
+                .expect("dbus-daemon did not print its address");
+            Self { daemon, address: address.trim().to_string() }
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    // org.freedesktop.portal.Screenshot answering each call with the next canned
+    // (response code, honours handle_token) pair
+    struct FakePortal {
+        stop: Arc<AtomicBool>,
+        server: JoinHandle<()>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl FakePortal {
+        fn serve(bus: &PrivateBus, answers: Vec<(u32, bool)>, uri: &str) -> Self {
+            let conn = SessionBus::with_address(&bus.address).connect().unwrap();
+            conn.request_name(PORTAL_DESTINATION, false, true, true).unwrap();
+
+            let calls = Arc::new(AtomicUsize::new(0));
+            // Signals for old-style requests go out late, after the caller watches the path
+            let delayed: Arc<Mutex<Vec<(Instant, Message)>>> = Arc::new(Mutex::new(Vec::new()));
+            let (counter, queue, uri) = (calls.clone(), delayed.clone(), uri.to_string());
+            let mut answers = answers.into_iter();
+            conn.start_receive(MatchRule::new_method_call(), Box::new(move |call: Message, conn: &Connection| {
+                if call.member().as_deref() != Some("Screenshot") {
+                    return true;
+                }
+                let call_number = counter.fetch_add(1, Ordering::SeqCst);
+                let (_, options): (String, PropMap) = call.read2().unwrap();
+                assert_eq!(options.get("interactive").and_then(|v| v.0.as_u64()), Some(0));
+                let (code, honours_token) = answers.next().expect("more portal calls than answers");
+
+                let sender = call.sender().unwrap().trim_start_matches(':').replace('.', "_");
+                let token = match options.get("handle_token").and_then(|v| v.0.as_str()) {
+                    Some(token) if honours_token => token.to_string(),
+                    _ => format!("old_portal_{}", call_number),
+                };
+                let path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+                let mut results = PropMap::new();
+                results.insert("uri".to_string(), Variant(Box::new(uri.clone()) as Box<dyn RefArg>));
+                let signal = Message::new_signal(path.clone(), REQUEST_INTERFACE, "Response").unwrap().append2(code, results);
+
+                conn.send(call.method_return().append1(dbus::Path::new(path).unwrap())).unwrap();
+                if honours_token {
+                    conn.send(signal).unwrap();
+                } else {
+                    queue.lock().unwrap().push((Instant::now() + Duration::from_millis(300), signal));
+                }
+                true
+            }));
+
+            let stop = Arc::new(AtomicBool::new(false));
+            let stopped = stop.clone();
+            let server = std::thread::spawn(move || {
+                while !stopped.load(Ordering::SeqCst) {
+                    conn.process(Duration::from_millis(20)).unwrap();
+                    let mut delayed = delayed.lock().unwrap();
+                    while delayed.first().is_some_and(|(due, _)| *due <= Instant::now()) {
+                        conn.send(delayed.remove(0).1).unwrap();
+                    }
+                }
+            });
+            Self { stop, server, calls }
+        }
+
+        fn stop(self) -> usize {
+            self.stop.store(true, Ordering::SeqCst);
+            self.server.join().unwrap();
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[test]
+    fn session_bus_talks_to_the_screenshot_portal() {
+        let bus = PrivateBus::start();
+        let session = SessionBus::with_address(&bus.address);
+
+        // Nobody serves the portal yet: unreachable (fall back to X11), not a failed capture
+        assert!(session.screenshot().is_err());
+
+        let uri = "file:///tmp/framesense%20portal.png";
+        let portal = FakePortal::serve(&bus, vec![(0, true), (0, false), (1, true), (2, true)], uri);
+        let success = PortalResponse::Success { uri: uri.to_string() };
+        assert_eq!(session.screenshot(), Ok(success.clone()));
+        // Old portals ignore handle_token; the request path they return is watched instead
+        assert_eq!(session.screenshot(), Ok(success));
+        assert_eq!(session.screenshot(), Ok(PortalResponse::Cancelled));
+        assert!(matches!(session.screenshot(), Ok(PortalResponse::Failed(_))));
+        assert_eq!(portal.stop(), 4);
+    }
+}
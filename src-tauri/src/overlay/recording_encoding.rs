@@ -0,0 +1,170 @@
+// Output encoding for screen recordings. GIF and APNG are pure Rust and always
+// available; WebM (VP8 through libvpx) is only linked with `--features webm-recording`,
+// so default builds don't need the native encoder.
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingFormat {
+    #[default]
+    Gif,
+    Apng, // Full color, larger than GIF
+    Webm, // Needs the webm-recording feature
+}
+
+impl RecordingFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            RecordingFormat::Gif => "image/gif",
+            RecordingFormat::Apng => "image/apng",
+            RecordingFormat::Webm => "video/webm",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            RecordingFormat::Gif => "gif",
+            RecordingFormat::Apng => "png",
+            RecordingFormat::Webm => "webm",
+        }
+    }
+
+    /// Whether this build can write the format
+    pub fn is_available(self) -> bool {
+        self != RecordingFormat::Webm || cfg!(feature = "webm-recording")
+    }
+}
+
+/// One frame of a recording, shown for `duration_ms`
+#[derive(Clone, Debug)]
+pub struct RecordedFrame {
+    pub image: RgbaImage,
+    pub duration_ms: u32,
+}
+
+/// Encode `frames` (all the same size) as a looping animation or a video
+pub fn encode(frames: &[RecordedFrame], format: RecordingFormat) -> Result<Vec<u8>, String> {
+    let first = frames.first().ok_or("Nothing was recorded")?;
+    if frames.iter().any(|frame| frame.image.dimensions() != first.image.dimensions()) {
+        return Err("Recorded frames differ in size".to_string());
+    }
+    match format {
+        RecordingFormat::Gif => encode_gif(frames),
+        RecordingFormat::Apng => encode_apng(frames),
+        RecordingFormat::Webm => encode_webm(frames),
+    }
+}
+
+fn encode_gif(frames: &[RecordedFrame]) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    {
+        // Speed 20 of 30: palette quality barely differs on screen content, encode time does
+        let mut encoder = GifEncoder::new_with_speed(&mut bytes, 20);
+        encoder.set_repeat(Repeat::Infinite)
+            .map_err(|e| format!("GIF encoding failed: {}", e))?;
+        encoder.encode_frames(frames.iter().map(|frame| {
+            Frame::from_parts(frame.image.clone(), 0, 0, Delay::from_numer_denom_ms(frame.duration_ms, 1))
+        })).map_err(|e| format!("GIF encoding failed: {}", e))?;
+    }
+    Ok(bytes)
+}
+
+fn encode_apng(frames: &[RecordedFrame]) -> Result<Vec<u8>, String> {
+    let png_error = |e: png::EncodingError| format!("APNG encoding failed: {}", e);
+    let (width, height) = frames[0].image.dimensions();
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Fast);
+        encoder.set_animated(frames.len() as u32, 0).map_err(png_error)?; // 0 plays: loop forever
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        for frame in frames {
+            // Delays are a u16 fraction; long pauses on one frame fall back to centiseconds
+            let (numerator, denominator) = match u16::try_from(frame.duration_ms) {
+                Ok(ms) => (ms, 1000),
+                Err(_) => ((frame.duration_ms / 10).min(u16::MAX as u32) as u16, 100),
+            };
+            writer.set_frame_delay(numerator, denominator).map_err(png_error)?;
+            writer.write_image_data(frame.image.as_raw()).map_err(png_error)?;
+        }
+        writer.finish().map_err(png_error)?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(feature = "webm-recording")]
+fn encode_webm(frames: &[RecordedFrame]) -> Result<Vec<u8>, String> {
+    use webm::mux::{self, Track};
+
+    // 4:2:0 chroma needs even dimensions; drop the odd row/column
+    let (width, height) = frames[0].image.dimensions();
+    let (width, height) = (width & !1, height & !1);
+    if width == 0 || height == 0 {
+        return Err("Recording is too small for WebM".to_string());
+    }
+
+    // The muxer wants a seekable writer it keeps, so go through a temporary file
+    let path = std::env::temp_dir().join(format!("framesense-recording-{}-{}.webm", std::process::id(),
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0)));
+    let file = std::fs::File::create(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut segment = mux::Segment::new(mux::Writer::new(file)).ok_or("Failed to start the WebM muxer")?;
+    let mut track = segment.add_video_track(width, height, None, mux::VideoCodecId::VP8);
+
+    let mut vpx = vpx_encode::Encoder::new(vpx_encode::Config {
+        width,
+        height,
+        timebase: [1, 1000], // Milliseconds
+        bitrate: 4000,       // kbit/s; text stays sharp
+        codec: vpx_encode::VideoCodecId::VP8,
+    }).map_err(|e| format!("Failed to start the VP8 encoder: {:?}", e))?;
+
+    let mut pts_ms: i64 = 0;
+    for frame in frames {
+        let yuv = rgba_to_i420(&frame.image, width, height);
+        for packet in vpx.encode(pts_ms, &yuv).map_err(|e| format!("VP8 encoding failed: {:?}", e))? {
+            track.add_frame(packet.data, packet.pts as u64 * 1_000_000, packet.key);
+        }
+        pts_ms += frame.duration_ms as i64;
+    }
+    let mut remaining = vpx.finish().map_err(|e| format!("VP8 encoding failed: {:?}", e))?;
+    while let Some(packet) = remaining.next().map_err(|e| format!("VP8 encoding failed: {:?}", e))? {
+        track.add_frame(packet.data, packet.pts as u64 * 1_000_000, packet.key);
+    }
+    let _ = segment.finalize(Some(pts_ms as u64 * 1_000_000));
+
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    let _ = std::fs::remove_file(&path);
+    bytes
+}
+
+#[cfg(not(feature = "webm-recording"))]
+fn encode_webm(_frames: &[RecordedFrame]) -> Result<Vec<u8>, String> {
+    Err("WebM recording not available in this build (compile with --features webm-recording)".to_string())
+}
+
+/// BT.601 limited-range I420: a full Y plane, then U and V at half resolution
+#[cfg(feature = "webm-recording")]
+fn rgba_to_i420(image: &RgbaImage, width: u32, height: u32) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    let mut yuv = vec![0u8; w * h * 3 / 2];
+    let (y_plane, chroma) = yuv.split_at_mut(w * h);
+    let (u_plane, v_plane) = chroma.split_at_mut(w * h / 4);
+    for y in 0..h {
+        for x in 0..w {
+            let [r, g, b, _] = image.get_pixel(x as u32, y as u32).0;
+            let (r, g, b) = (r as i32, g as i32, b as i32);
+            y_plane[y * w + x] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+            if x % 2 == 0 && y % 2 == 0 {
+                let i = (y / 2) * (w / 2) + x / 2;
+                u_plane[i] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+                v_plane[i] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+            }
+        }
+    }
+    yuv
+}
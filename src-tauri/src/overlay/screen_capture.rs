@@ -1,10 +1,14 @@
-use screenshots::Screen;
-use image::{ImageFormat, RgbaImage, DynamicImage};
-use std::io::Cursor;
-use base64::{Engine as _, engine::general_purpose};
+use screenshots::DisplayInfo;
+use image::{imageops, RgbaImage};
 use serde::{Deserialize, Serialize};
+use super::capture_service::CaptureError;
+use super::capture_source::{default_source, CaptureSource};
+use super::coordinates::{self, ScreenLayout};
+use super::image_encoding::{self, EncodedCapture, OutputOptions};
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+/// A rectangle in global desktop coordinates (display units, origin at the
+/// primary screen's top-left; screens left of or above it have negative x/y)
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct CaptureBounds {
     pub x: i32,
     pub y: i32,
@@ -12,11 +16,45 @@ pub struct CaptureBounds {
     pub height: u32,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct CaptureResult {
-    pub image_data: String, // Base64 encoded image
-    pub bounds: CaptureBounds,
-    pub timestamp: u64,
+impl CaptureBounds {
+    fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+
+    /// Overlapping part of two rectangles, None when they don't overlap
+    pub fn intersection(&self, other: &CaptureBounds) -> Option<CaptureBounds> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x || bottom <= y {
+            return None;
+        }
+        Some(CaptureBounds { x, y, width: (right - x) as u32, height: (bottom - y) as u32 })
+    }
+
+    /// Smallest rectangle containing both
+    pub fn union(&self, other: &CaptureBounds) -> CaptureBounds {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        CaptureBounds {
+            x,
+            y,
+            width: (self.right().max(other.right()) - x) as u32,
+            height: (self.bottom().max(other.bottom()) - y) as u32,
+        }
+    }
+}
+
+/// One screen's share of a capture that may span several screens
+#[derive(Clone, Debug)]
+pub struct CapturePiece {
+    pub screen_index: usize,
+    pub area: CaptureBounds, // Global desktop coordinates
 }
 
 pub struct ScreenCapture;
@@ -26,147 +64,186 @@ impl ScreenCapture {
         Self
     }
 
-    /// Take a fullscreen screenshot
+    /// Take a screenshot of the whole desktop (all screens stitched together)
     pub async fn capture_fullscreen() -> Result<String, String> {
         println!("🖼️ Taking fullscreen screenshot...");
         
-        let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
-        
-        if screens.is_empty() {
-            return Err("No screens found".to_string());
-        }
+        let screens = Self::get_screen_info()?;
+        let desktop = Self::desktop_bounds(&screens).ok_or("No screens found")?;
+        println!("📸 Capturing {} screen(s): {}x{} at ({}, {})",
+                 screens.len(), desktop.width, desktop.height, desktop.x, desktop.y);
         
-        let screen = &screens[0];
-        println!("📸 Capturing screen: {}x{}", screen.display_info.width, screen.display_info.height);
-        
-        let screenshot = screen.capture().map_err(|e| format!("Failed to capture screen: {}", e))?;
-        
-        Self::encode_image_to_base64(screenshot)
+        let (_, image) = Self::capture_global(&desktop)?;
+        Self::encode_rgba_to_base64(image)
     }
 
-    /// Take a screenshot of a specific region
-    pub async fn capture_region(bounds: CaptureBounds) -> Result<CaptureResult, String> {
-        println!("🎯 Capturing region: {:?}", bounds);
-        
-        let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
-        
+    /// Capture `bounds` from whichever screens it covers. Returns the area actually
+    /// captured (clipped to the screens) and the image, stitched when it spans screens.
+    pub fn capture_global(bounds: &CaptureBounds) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        Self::capture_global_from(default_source().as_ref(), bounds)
+    }
+
+    /// `capture_global` against a specific capture backend
+    pub fn capture_global_from(source: &dyn CaptureSource, bounds: &CaptureBounds) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        let screens = source.screens()?;
         if screens.is_empty() {
-            return Err("No screens found".to_string());
+            return Err(CaptureError::NoScreens);
         }
-        
-        let screen = &screens[0];
-        let screenshot = screen.capture().map_err(|e| format!("Failed to capture screen: {}", e))?;
-        
-        // Convert to RgbaImage for cropping
-        let rgba_image = RgbaImage::from_raw(
-            screenshot.width(),
-            screenshot.height(),
-            screenshot.rgba().to_vec(),
-        ).ok_or("Failed to create RGBA image from screenshot")?;
-        
-        // Crop the image to the specified bounds
-        let cropped = Self::crop_image(rgba_image, &bounds)?;
-        
-        // Encode to base64
-        let image_data = Self::encode_rgba_to_base64(cropped)?;
-        
-        Ok(CaptureResult {
-            image_data,
-            bounds,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        })
-    }
-
-    /// Crop an RgbaImage to the specified bounds
-    fn crop_image(image: RgbaImage, bounds: &CaptureBounds) -> Result<RgbaImage, String> {
-        let (img_width, img_height) = image.dimensions();
-        
-        // Validate bounds
-        if bounds.x < 0 || bounds.y < 0 {
-            return Err("Negative coordinates not supported".to_string());
+
+        let pieces = Self::plan_capture(bounds, &screens);
+        if pieces.is_empty() {
+            return Err(CaptureError::OutsideScreens(bounds.clone()));
         }
-        
-        let x = bounds.x as u32;
-        let y = bounds.y as u32;
-        let width = bounds.width.min(img_width.saturating_sub(x));
-        let height = bounds.height.min(img_height.saturating_sub(y));
-        
-        if width == 0 || height == 0 {
-            return Err("Invalid crop dimensions".to_string());
+
+        let mut captured = Vec::with_capacity(pieces.len());
+        for piece in &pieces {
+            let image = source.capture_area(&screens[piece.screen_index], &piece.area)?;
+            captured.push((piece.area.clone(), image));
         }
-        
-        // Create new image with cropped dimensions
-        let mut cropped = RgbaImage::new(width, height);
-        
-        for crop_y in 0..height {
-            for crop_x in 0..width {
-                let src_x = x + crop_x;
-                let src_y = y + crop_y;
-                
-                if src_x < img_width && src_y < img_height {
-                    let pixel = image.get_pixel(src_x, src_y);
-                    cropped.put_pixel(crop_x, crop_y, *pixel);
-                }
+
+        if captured.len() > 1 {
+            println!("🧩 Stitching capture from {} screens", captured.len());
+        }
+        Ok(Self::stitch(captured))
+    }
+
+    /// Split `bounds` into per-screen areas. Mirrored screens (same position and
+    /// size as an earlier one) are skipped so they don't get captured twice.
+    pub fn plan_capture(bounds: &CaptureBounds, screens: &[ScreenInfo]) -> Vec<CapturePiece> {
+        let mut pieces: Vec<CapturePiece> = Vec::new();
+        for (index, screen) in screens.iter().enumerate() {
+            let mirrored = screens[..index].iter().any(|earlier| {
+                (earlier.x, earlier.y, earlier.width, earlier.height) == (screen.x, screen.y, screen.width, screen.height)
+            });
+            if mirrored {
+                continue;
+            }
+            if let Some(area) = bounds.intersection(&screen.bounds()) {
+                pieces.push(CapturePiece { screen_index: index, area });
             }
         }
-        
-        Ok(cropped)
+        pieces
     }
 
-    /// Convert screenshots::Image to base64
-    fn encode_image_to_base64(screenshot: screenshots::Image) -> Result<String, String> {
-        let rgba_image = RgbaImage::from_raw(
-            screenshot.width(),
-            screenshot.height(),
-            screenshot.rgba().to_vec(),
-        ).ok_or("Failed to create RGBA image from screenshot")?;
-        
-        Self::encode_rgba_to_base64(rgba_image)
+    /// Combine per-screen captures into one image covering all of them. Screens can
+    /// have different scale factors, so everything is drawn at the highest density.
+    pub fn stitch(mut pieces: Vec<(CaptureBounds, RgbaImage)>) -> (CaptureBounds, RgbaImage) {
+        if pieces.len() == 1 {
+            return pieces.remove(0);
+        }
+
+        let bounds = pieces.iter().skip(1)
+            .fold(pieces[0].0.clone(), |acc, (area, _)| acc.union(area));
+        let scale = pieces.iter()
+            .map(|(area, image)| image.width() as f32 / area.width.max(1) as f32)
+            .fold(1.0f32, f32::max);
+
+        let to_pixels = |value: i32| (value as f32 * scale).round() as i64;
+        let mut canvas = RgbaImage::new(to_pixels(bounds.width as i32) as u32, to_pixels(bounds.height as i32) as u32);
+        for (area, image) in pieces {
+            let width = to_pixels(area.width as i32) as u32;
+            let height = to_pixels(area.height as i32) as u32;
+            let image = if image.dimensions() == (width, height) {
+                image
+            } else {
+                imageops::resize(&image, width, height, imageops::FilterType::Triangle)
+            };
+            imageops::replace(&mut canvas, &image, to_pixels(area.x - bounds.x), to_pixels(area.y - bounds.y));
+        }
+
+        (bounds, canvas)
     }
 
     /// Convert RgbaImage to base64 PNG
-    fn encode_rgba_to_base64(rgba_image: RgbaImage) -> Result<String, String> {
-        let dynamic_image = DynamicImage::ImageRgba8(rgba_image);
-        
-        let mut png_buffer = Vec::new();
-        {
-            let mut cursor = Cursor::new(&mut png_buffer);
-            dynamic_image.write_to(&mut cursor, ImageFormat::Png)
-                .map_err(|e| format!("Failed to encode PNG: {}", e))?;
-        }
-        
-        let base64_data = general_purpose::STANDARD.encode(&png_buffer);
-        Ok(format!("data:image/png;base64,{}", base64_data))
+    pub fn encode_rgba_to_base64(rgba_image: RgbaImage) -> Result<String, String> {
+        Self::encode_rgba(&rgba_image, &OutputOptions::default()).map(|encoded| encoded.image_data)
+    }
+
+    /// Encode as a data URL in the requested format, downscaled to `max_edge` if set
+    pub fn encode_rgba(rgba_image: &RgbaImage, output: &OutputOptions) -> Result<EncodedCapture, String> {
+        image_encoding::encode(rgba_image, output)
+    }
+
+    /// Display scale factor of the screen showing the center of `bounds` (1.0 when unknown)
+    pub fn scale_factor_for(bounds: &CaptureBounds) -> f32 {
+        Self::scale_factor_from(default_source().as_ref(), bounds)
+    }
+
+    /// `scale_factor_for` against a specific capture backend
+    pub fn scale_factor_from(source: &dyn CaptureSource, bounds: &CaptureBounds) -> f32 {
+        ScreenLayout::from_source(source).ok()
+            .and_then(|layout| layout.screen_for(bounds).map(coordinates::screen_scale))
+            .unwrap_or(1.0)
     }
 
     /// Get display information for all screens
     pub fn get_screen_info() -> Result<Vec<ScreenInfo>, String> {
-        let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
-        
-        let screen_info: Vec<ScreenInfo> = screens
-            .iter()
-            .enumerate()
-            .map(|(index, screen)| ScreenInfo {
-                id: index as u32,
-                width: screen.display_info.width,
-                height: screen.display_info.height,
-                scale_factor: screen.display_info.scale_factor,
-                is_primary: index == 0, // First screen is typically primary
-            })
-            .collect();
-        
-        Ok(screen_info)
+        default_source().screens().map_err(String::from)
+    }
+
+    /// The screen the OS reports as primary (first screen if none is flagged)
+    pub fn find_primary(screens: &[ScreenInfo]) -> Option<&ScreenInfo> {
+        screens.iter().find(|screen| screen.is_primary).or(screens.first())
+    }
+
+    /// Area a selection overlay covers: the whole desktop, so a selection can
+    /// start on any monitor (falls back to 1920x1080 when screens are unknown)
+    pub fn overlay_bounds() -> CaptureBounds {
+        let fallback = CaptureBounds { x: 0, y: 0, width: 1920, height: 1080 };
+        match Self::get_screen_info() {
+            Ok(screens) => match Self::desktop_bounds(&screens) {
+                Some(desktop) => {
+                    println!("📺 Desktop spans {} screen(s): {}x{} at ({}, {})",
+                             screens.len(), desktop.width, desktop.height, desktop.x, desktop.y);
+                    desktop
+                },
+                None => {
+                    println!("⚠️ No screens found, using fallback 1920x1080");
+                    fallback
+                }
+            },
+            Err(e) => {
+                println!("❌ Failed to get screen info: {}, using fallback", e);
+                fallback
+            }
+        }
+    }
+
+    /// Bounding box of all screens (the virtual desktop)
+    pub fn desktop_bounds(screens: &[ScreenInfo]) -> Option<CaptureBounds> {
+        screens.iter()
+            .map(ScreenInfo::bounds)
+            .reduce(|acc, bounds| acc.union(&bounds))
     }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ScreenInfo {
     pub id: u32,
+    pub x: i32, // Position in global desktop coordinates
+    pub y: i32,
     pub width: u32,
     pub height: u32,
     pub scale_factor: f32,
     pub is_primary: bool,
+}
+
+impl ScreenInfo {
+    pub fn bounds(&self) -> CaptureBounds {
+        CaptureBounds { x: self.x, y: self.y, width: self.width, height: self.height }
+    }
+}
+
+impl From<&DisplayInfo> for ScreenInfo {
+    fn from(info: &DisplayInfo) -> Self {
+        Self {
+            id: info.id,
+            x: info.x,
+            y: info.y,
+            width: info.width,
+            height: info.height,
+            scale_factor: info.scale_factor,
+            is_primary: info.is_primary,
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,335 @@
+// Screen recording of one region: a worker thread captures frames at the requested
+// rate until stopped, then the frames are encoded in one go (APNG needs the frame
+// count up front). Unchanged frames only lengthen the previous one, so an idle
+// screen costs no memory.
+use image::{imageops, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use super::capture_registry::{CaptureHandle, CaptureRegistry};
+use super::capture_service::{CaptureError, CaptureService};
+use super::image_encoding::{self, EncodedBytes};
+use super::recording_encoding::{self, RecordedFrame, RecordingFormat};
+use super::screen_capture::CaptureBounds;
+
+pub const MAX_FPS: u32 = 30;
+pub const MAX_DURATION_SECS: u32 = 600;
+// Raw RGBA kept before encoding; recording stops itself beyond this
+pub const MAX_BUFFERED_BYTES: usize = 1024 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingOptions {
+    pub format: RecordingFormat,
+    pub fps: u32,
+    pub max_duration_secs: u32,
+    pub max_edge: Option<u32>, // Downscale frames so the longer side is at most this many pixels
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            format: RecordingFormat::Gif,
+            fps: 10,
+            max_duration_secs: 60,
+            max_edge: None,
+        }
+    }
+}
+
+impl RecordingOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.format.is_available() {
+            return Err(format!("{:?} recording is not available in this build", self.format));
+        }
+        if self.fps == 0 || self.fps > MAX_FPS {
+            return Err(format!("Frame rate must be 1-{} fps, got {}", MAX_FPS, self.fps));
+        }
+        if self.max_duration_secs == 0 || self.max_duration_secs > MAX_DURATION_SECS {
+            return Err(format!("Recording length must be 1-{} seconds, got {}", MAX_DURATION_SECS, self.max_duration_secs));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingState {
+    Recording,
+    Paused,
+    /// Stopped itself at the length or memory limit; waiting for `stop`
+    LimitReached,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordingStatus {
+    pub state: RecordingState,
+    pub bounds: CaptureBounds,
+    pub options: RecordingOptions,
+    pub frames: usize, // Distinct frames so far
+    pub duration_ms: u64, // Recorded time, pauses excluded
+    pub buffered_bytes: usize,
+    pub error: Option<String>,
+}
+
+/// An encoded recording
+#[derive(Clone, Debug)]
+pub struct Recording {
+    pub bytes: Vec<u8>,
+    pub format: RecordingFormat,
+    pub width: u32,
+    pub height: u32,
+    pub frames: usize,
+    pub duration_ms: u64,
+}
+
+/// What a finished recording reports to the webview
+#[derive(Clone, Serialize)]
+pub struct RecordingResult {
+    pub capture: Option<CaptureHandle>, // Served by handle only to a caller that releases it
+    pub path: Option<String>, // Where the clip was saved, if saving worked
+    pub format: RecordingFormat,
+    pub frames: usize,
+    pub duration_ms: u64,
+}
+
+enum Command {
+    Pause,
+    Resume,
+}
+
+/// Called once when the recording stops by itself
+pub type LimitCallback = Box<dyn FnOnce() + Send>;
+
+/// A running recording; dropping it discards what was recorded
+pub struct ScreenRecorder {
+    commands: Option<Sender<Command>>, // Dropping the sender stops the worker
+    status: Arc<Mutex<RecordingStatus>>,
+    worker: Option<JoinHandle<Vec<RecordedFrame>>>,
+}
+
+impl ScreenRecorder {
+    /// Start recording `bounds` through `service`; `on_limit` runs if the recording
+    /// hits its length or memory limit before anyone stops it
+    pub fn start(service: CaptureService, bounds: &CaptureBounds, mut options: RecordingOptions, on_limit: LimitCallback) -> Result<Self, CaptureError> {
+        options.validate().map_err(CaptureError::Backend)?;
+        // Backends without a video stream (the Wayland screenshot portal) record slower;
+        // the status reports the rate actually used
+        if let Some(max_fps) = service.source().max_frame_rate().filter(|max_fps| options.fps > *max_fps) {
+            println!("⚠️ {} captures at most {} fps, recording at that rate", service.source().name(), max_fps);
+            options.fps = max_fps.max(1);
+        }
+        // The first frame fixes the size, and shows the region can be captured at all
+        let (area, first) = service.capture_pixels(bounds)?;
+        let first = image_encoding::downscale(&first, options.max_edge).into_owned();
+
+        let status = Arc::new(Mutex::new(RecordingStatus {
+            state: RecordingState::Recording,
+            bounds: area.clone(),
+            options: options.clone(),
+            frames: 0,
+            duration_ms: 0,
+            buffered_bytes: 0,
+            error: None,
+        }));
+        let (commands, received) = mpsc::channel();
+        let worker_status = status.clone();
+        let worker = std::thread::spawn(move || {
+            let mut recorder = RecordingWorker { service, area, options, status: worker_status, frames: Vec::new() };
+            if recorder.run(first, &received) {
+                on_limit();
+            }
+            recorder.frames
+        });
+
+        println!("🔴 Recording {}x{} at ({}, {})", bounds.width, bounds.height, bounds.x, bounds.y);
+        Ok(Self { commands: Some(commands), status, worker: Some(worker) })
+    }
+
+    pub fn status(&self) -> RecordingStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn pause(&self) -> RecordingStatus {
+        self.send(Command::Pause, RecordingState::Paused)
+    }
+
+    pub fn resume(&self) -> RecordingStatus {
+        self.send(Command::Resume, RecordingState::Recording)
+    }
+
+    /// Stop and encode what was recorded
+    pub fn stop(mut self) -> Result<Recording, CaptureError> {
+        let frames = self.join();
+        let status = self.status();
+        let bytes = recording_encoding::encode(&frames, status.options.format).map_err(CaptureError::EncodeFailed)?;
+        let (width, height) = frames.first().map(|frame| frame.image.dimensions()).unwrap_or((0, 0));
+        println!("⏹️ Recording stopped: {} frames, {:.1}s, {}KB {:?}", frames.len(), status.duration_ms as f64 / 1000.0, bytes.len() / 1024, status.options.format);
+        Ok(Recording { bytes, format: status.options.format, width, height, frames: frames.len(), duration_ms: status.duration_ms })
+    }
+
+    /// Stop, hand the clip to `save` (which says where it went) and, when `serve`,
+    /// register it in `registry`. The handle is the caller's to release, so stops with
+    /// no one waiting on them (tray, length limit) leave the registry untouched.
+    pub fn finish(self, registry: &Mutex<CaptureRegistry>, serve: bool,
+                  save: impl FnOnce(&Recording) -> Option<String>) -> Result<RecordingResult, CaptureError> {
+        let recording = self.stop()?;
+        let path = save(&recording);
+        let capture = serve.then(|| registry.lock().unwrap().insert(EncodedBytes {
+            bytes: recording.bytes,
+            mime_type: recording.format.mime_type(),
+            width: recording.width,
+            height: recording.height,
+        }));
+        Ok(RecordingResult {
+            capture,
+            path,
+            format: recording.format,
+            frames: recording.frames,
+            duration_ms: recording.duration_ms,
+        })
+    }
+
+    // The state is updated right away so the caller sees it; the worker confirms it
+    fn send(&self, command: Command, state: RecordingState) -> RecordingStatus {
+        let mut status = self.status.lock().unwrap();
+        if matches!(status.state, RecordingState::Recording | RecordingState::Paused) {
+            if let Some(commands) = &self.commands {
+                let _ = commands.send(command);
+            }
+            status.state = state;
+        }
+        status.clone()
+    }
+
+    fn join(&mut self) -> Vec<RecordedFrame> {
+        self.commands = None;
+        self.worker.take().and_then(|worker| worker.join().ok()).unwrap_or_default()
+    }
+}
+
+impl Drop for ScreenRecorder {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+struct RecordingWorker {
+    service: CaptureService,
+    area: CaptureBounds,
+    options: RecordingOptions,
+    status: Arc<Mutex<RecordingStatus>>,
+    frames: Vec<RecordedFrame>,
+}
+
+impl RecordingWorker {
+    /// Capture until stopped; true if a limit stopped the recording
+    fn run(&mut self, first: RgbaImage, commands: &Receiver<Command>) -> bool {
+        let frame_interval = Duration::from_secs(1) / self.options.fps;
+        let max_duration_ms = self.options.max_duration_secs as u64 * 1000;
+        let mut recorded_ms: u64 = 0;
+        let mut image = first;
+        let mut frame_started = Instant::now();
+
+        loop {
+            self.push(image);
+
+            // Sleep until the next frame is due, handling commands meanwhile
+            let due = frame_started + frame_interval;
+            loop {
+                match commands.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        self.account(frame_started, &mut recorded_ms);
+                        return false;
+                    },
+                    Ok(Command::Resume) => {},
+                    Ok(Command::Pause) => {
+                        // The paused time is not part of the recording
+                        self.account(frame_started, &mut recorded_ms);
+                        self.set_state(RecordingState::Paused);
+                        if !wait_for_resume(commands) {
+                            return false;
+                        }
+                        self.set_state(RecordingState::Recording);
+                        frame_started = Instant::now();
+                        break;
+                    },
+                }
+            }
+            self.account(frame_started, &mut recorded_ms);
+            frame_started = Instant::now();
+
+            let buffered = self.status.lock().unwrap().buffered_bytes;
+            if recorded_ms >= max_duration_ms || buffered >= MAX_BUFFERED_BYTES {
+                println!("⏹️ Recording reached its limit ({:.1}s, {}MB)", recorded_ms as f64 / 1000.0, buffered / (1024 * 1024));
+                self.set_state(RecordingState::LimitReached);
+                return true;
+            }
+
+            image = match self.capture() {
+                Ok(image) => image,
+                Err(error) => {
+                    println!("❌ Recording failed: {}", error);
+                    let mut status = self.status.lock().unwrap();
+                    status.state = RecordingState::Failed;
+                    status.error = Some(error.to_string());
+                    return false;
+                },
+            };
+        }
+    }
+
+    /// The next frame, at the first frame's size even if the screens changed since
+    fn capture(&self) -> Result<RgbaImage, CaptureError> {
+        let (_, image) = self.service.capture_pixels(&self.area)?;
+        let image = image_encoding::downscale(&image, self.options.max_edge).into_owned();
+        match self.frames.first() {
+            Some(first) if first.image.dimensions() != image.dimensions() => {
+                let (width, height) = first.image.dimensions();
+                Ok(imageops::resize(&image, width, height, imageops::FilterType::Triangle))
+            },
+            _ => Ok(image),
+        }
+    }
+
+    // A frame identical to the previous one only extends how long that is shown
+    fn push(&mut self, image: RgbaImage) {
+        if self.frames.last().is_some_and(|last| last.image == image) {
+            return;
+        }
+        let bytes = image.as_raw().len();
+        self.frames.push(RecordedFrame { image, duration_ms: 0 });
+        let mut status = self.status.lock().unwrap();
+        status.frames = self.frames.len();
+        status.buffered_bytes += bytes;
+    }
+
+    // Time since `since` goes to the last frame and the recording's length
+    fn account(&mut self, since: Instant, recorded_ms: &mut u64) {
+        let elapsed_ms = since.elapsed().as_millis() as u64;
+        if let Some(last) = self.frames.last_mut() {
+            last.duration_ms = last.duration_ms.saturating_add(elapsed_ms as u32);
+        }
+        *recorded_ms += elapsed_ms;
+        self.status.lock().unwrap().duration_ms = *recorded_ms;
+    }
+
+    fn set_state(&self, state: RecordingState) {
+        self.status.lock().unwrap().state = state;
+    }
+}
+
+// Block while paused; false if the recording was stopped instead
+fn wait_for_resume(commands: &Receiver<Command>) -> bool {
+    loop {
+        match commands.recv() {
+            Ok(Command::Resume) => return true,
+            Ok(Command::Pause) => {},
+            Err(_) => return false,
+        }
+    }
+}
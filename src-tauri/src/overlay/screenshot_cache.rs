@@ -1,35 +1,94 @@
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use crate::CaptureBounds;
-use base64::Engine;
+use super::capture_service::{self, CaptureError, CaptureResult};
+use super::capture_source::{default_source, CaptureSource};
+use super::coordinates;
+use super::lru_cache::LruCache;
+use super::image_encoding::{self, EncodedCapture, OutputOptions};
+use super::screen_capture::{self, CaptureBounds, ScreenCapture};
+use image::{imageops, RgbaImage};
+use serde::Serialize;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct BoundsKey {
-    x: i32,
-    y: i32, 
+// Entries are keyed on the captured pixels, not the rectangle: the same content
+// selected twice (or from a slightly different position) is encoded once, and
+// changed content always misses. Each output format is cached separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ContentKey {
+    hash: u64,
     width: u32,
     height: u32,
 }
 
-impl From<CaptureBounds> for BoundsKey {
-    fn from(bounds: CaptureBounds) -> Self {
-        Self { x: bounds.x, y: bounds.y, width: bounds.width, height: bounds.height }
+impl ContentKey {
+    fn of(image: &RgbaImage) -> Self {
+        let mut hasher = DefaultHasher::new();
+        image.as_raw().hash(&mut hasher);
+        Self { hash: hasher.finish(), width: image.width(), height: image.height() }
     }
 }
 
+type CacheKey = (ContentKey, OutputOptions);
+
 #[derive(Debug, Clone)]
 struct CachedCapture {
-    data: String,          // Base64 PNG data
+    capture: EncodedCapture, // Data URL in the requested format
+    last_used: Instant,
+    raw_bytes: usize,        // RGBA pixels the entry stands for
+}
+
+impl CachedCapture {
+    /// Memory the entry actually holds, charged against the budget
+    fn stored_bytes(&self) -> usize {
+        self.capture.image_data.capacity() + std::mem::size_of::<Self>() + std::mem::size_of::<CacheKey>()
+    }
+}
+
+// A recent capture of whole screens; selections inside it are cropped instead of
+// recaptured. Frames only live for one selection gesture (overlay shown until it
+// closes): follow-up selections in it (pointer jitter, switching to window mode,
+// a second try) skip the capture, at the cost of not seeing what changes under the
+// overlay during the gesture (at most `frame_ttl`). Outside a gesture every capture
+// reads the screen, so content from an earlier gesture is never served.
+struct CachedFrame {
+    bounds: CaptureBounds, // Desktop units
+    image: RgbaImage,      // Physical pixels
     captured_at: Instant,
-    size_bytes: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub size_bytes: usize,    // Memory held by entries (data URL + bookkeeping)
+    pub budget_bytes: usize,
+    pub raw_bytes: usize,     // RGBA size of the cached captures
+    pub encoded_bytes: usize, // Encoded (PNG, WebP, ...) size of the cached captures
+    pub expired_entries: usize,
+    pub evictions: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub bytes_served: u64, // Encoded data returned from cache hits
+    pub frame_reuses: u64, // Selections cropped from a cached frame
+    pub frames: usize,
+    pub frame_bytes: usize,
 }
 
 pub struct ScreenshotCache {
-    cache: HashMap<BoundsKey, CachedCapture>,
+    source: Arc<dyn CaptureSource>,
+    cache: LruCache<CacheKey, CachedCapture>,
+    frames: Vec<CachedFrame>,
     screen_info: Option<ScreenInfo>,
-    png_buffer: Vec<u8>,  // Återanvänd buffer
-    max_cache_size: usize,
+    encode_buffer: Vec<u8>,  // Återanvänd buffer
     cache_ttl: Duration,
+    frame_ttl: Duration,
+    max_frames: usize,
+    selecting: bool, // Inside a selection gesture, see `begin_selection`
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    bytes_served: u64,
+    frame_reuses: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -42,187 +101,253 @@ struct ScreenInfo {
 
 impl ScreenshotCache {
     pub fn new() -> Self {
+        Self::with_source(default_source())
+    }
+
+    /// Cache on top of a specific capture backend (e.g. a FixtureSource in tests)
+    pub fn with_source(source: Arc<dyn CaptureSource>) -> Self {
         Self {
-            cache: HashMap::new(),
+            source,
+            cache: LruCache::new(50 * 1024 * 1024), // 50MB max cache
+            frames: Vec::new(),
             screen_info: None,
-            png_buffer: Vec::with_capacity(1024 * 1024), // 1MB initial buffer
-            max_cache_size: 50 * 1024 * 1024, // 50MB max cache
-            cache_ttl: Duration::from_secs(30), // 30s cache TTL
+            encode_buffer: Vec::with_capacity(1024 * 1024), // 1MB initial buffer
+            cache_ttl: Duration::from_secs(30), // Entries unused for 30s expire
+            frame_ttl: Duration::from_millis(1500), // Upper bound within one selection gesture
+            max_frames: 4,
+            selecting: false,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            bytes_served: 0,
+            frame_reuses: 0,
         }
     }
-    
-    pub fn capture_optimized(&mut self, bounds: CaptureBounds) -> Result<String, String> {
-        let bounds_key = BoundsKey::from(bounds.clone());
-        
-        // 1. Cache check
-        if let Some(cached) = self.cache.get(&bounds_key) {
-            if cached.captured_at.elapsed() < self.cache_ttl {
-                println!("💰 Screenshot cache hit: {}x{}", bounds.width, bounds.height);
-                return Ok(cached.data.clone());
-            } else {
-                println!("⏰ Screenshot cache expired");
-                self.cache.remove(&bounds_key);
-            }
+
+    /// Capture backend this cache reads from
+    pub fn source(&self) -> Arc<dyn CaptureSource> {
+        self.source.clone()
+    }
+
+    /// The selection overlay opened: captures until `end_selection` may share frames
+    pub fn begin_selection(&mut self) {
+        self.selecting = true;
+        self.frames.clear();
+    }
+
+    /// The overlay closed: drop its frames so the next capture reads the screen again
+    pub fn end_selection(&mut self) {
+        self.selecting = false;
+        self.frames.clear();
+    }
+
+    /// Capture as a PNG data URL
+    pub fn capture_optimized(&mut self, bounds: &CaptureBounds) -> Result<String, CaptureError> {
+        self.capture_encoded(bounds, &OutputOptions::default()).map(|capture| capture.image_data)
+    }
+
+    /// Cached counterpart of `CaptureService::capture`
+    pub fn capture(&mut self, bounds: &CaptureBounds, output: &OutputOptions) -> Result<CaptureResult, CaptureError> {
+        let (area, image) = self.capture_pixels(bounds)?;
+        let encoded = self.encode_cached(&image, output)?;
+        Ok(CaptureResult::new(area, encoded))
+    }
+
+    /// Capture encoded as `output` asks (format, quality, maximum edge length)
+    pub fn capture_encoded(&mut self, bounds: &CaptureBounds, output: &OutputOptions) -> Result<EncodedCapture, CaptureError> {
+        // 1-2. Screen info and pixels
+        let (_, image) = self.capture_pixels(bounds)?;
+        self.encode_cached(&image, output)
+    }
+
+    fn encode_cached(&mut self, image: &RgbaImage, output: &OutputOptions) -> Result<EncodedCapture, CaptureError> {
+        // 3. Content cache check
+        let cache_key = (ContentKey::of(image), output.clone());
+        if let Some(cached) = self.cache.get_mut(&cache_key) {
+            cached.last_used = Instant::now();
+            self.hits += 1;
+            self.bytes_served += cached.capture.image_data.len() as u64;
+            println!("💰 Screenshot cache hit: {}x{}", image.width(), image.height());
+            return Ok(cached.capture.clone());
         }
-        
-        // 2. Screen info cache
-        if self.screen_info.is_none() || 
+        self.misses += 1;
+
+        // 4. Encode straight into the reusable buffer and cache
+        let capture = image_encoding::encode_with_buffer(image, output, &mut self.encode_buffer)
+            .map_err(CaptureError::EncodeFailed)?;
+        println!("📸 Optimized capture ({}): {} {}x{}, {}KB", self.source.name(),
+                 capture.mime_type, capture.width, capture.height, capture.byte_size / 1024);
+        self.add_to_cache(cache_key, CachedCapture {
+            capture: capture.clone(),
+            last_used: Instant::now(),
+            raw_bytes: image.as_raw().len(),
+        });
+
+        Ok(capture)
+    }
+
+    /// Pixels of `bounds`, clipped to the screens, cropped from a recent frame when one covers them
+    pub fn capture_pixels(&mut self, bounds: &CaptureBounds) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        let area = bounds.clone();
+
+        // 1. Screen info cache
+        if self.screen_info.is_none() ||
            self.screen_info.as_ref().unwrap().cached_at.elapsed() > Duration::from_secs(60) {
             self.screen_info = Some(self.get_screen_info()?);
             println!("📺 Refreshed screen info cache");
         }
-        
-        // 3. Optimerad capture
-        let image_data = self.capture_with_reused_buffer(bounds.clone())?;
-        
-        // 4. Cache management
-        self.add_to_cache(bounds_key, image_data.clone());
-        
-        Ok(image_data)
-    }
-    
-    fn capture_with_reused_buffer(&mut self, bounds: CaptureBounds) -> Result<String, String> {
-        // Använd screenshots library men med optimerad encoding
-        match screenshots::Screen::all() {
-            Ok(screens) => {
-                if let Some(screen) = screens.first() {
-                    let screen_width = screen.display_info.width;
-                    let screen_height = screen.display_info.height;
-                    
-                    // Validate and clamp coordinates to screen bounds
-                    let safe_x = bounds.x.max(0).min((screen_width as i32) - (bounds.width as i32));
-                    let safe_y = bounds.y.max(0).min((screen_height as i32) - (bounds.height as i32));
-                    let safe_width = bounds.width.min((screen_width as u32) - (safe_x as u32));
-                    let safe_height = bounds.height.min((screen_height as u32) - (safe_y as u32));
-                    
-                    // Ensure minimum size
-                    if safe_width < 10 || safe_height < 10 {
-                        return Err(format!("Capture area too small after adjustment: {}x{}", safe_width, safe_height));
-                    }
-                    
-                    match screen.capture_area(safe_x, safe_y, safe_width, safe_height) {
-                        Ok(image) => {
-                            // PNG encoding (screenshots library handles the buffer internally)
-                            match image.to_png(None) {
-                                Ok(png_data) => {
-                                    // Store in our reusable buffer for potential future optimizations
-                                    self.png_buffer.clear();
-                                    self.png_buffer.extend_from_slice(&png_data);
-                                    
-                                    let base64_data = base64::engine::general_purpose::STANDARD.encode(&png_data);
-                                    let full_data = format!("data:image/png;base64,{}", base64_data);
-                                    
-                                    println!("📸 Optimized capture: {}KB", png_data.len() / 1024);
-                                    Ok(full_data)
-                                },
-                                Err(e) => Err(format!("PNG encoding failed: {}", e))
-                            }
-                        },
-                        Err(e) => Err(format!("Screen capture failed: {}", e))
-                    }
-                } else {
-                    Err("No screens available".to_string())
-                }
+
+        // 2. Pixels: cropped from a frame of this selection gesture covering the area, else captured
+        self.frames.retain(|frame| frame.captured_at.elapsed() < self.frame_ttl);
+        let (area, image) = match self.frames.iter().find_map(|frame| Self::crop_contained(frame, &area)) {
+            Some(image) => {
+                self.frame_reuses += 1;
+                println!("✂️ Cropped {}x{} from cached frame", area.width, area.height);
+                (area, image)
             },
-            Err(e) => Err(format!("Failed to access screens: {}", e))
-        }
+            None => self.capture_frame(&area)?,
+        };
+
+        capture_service::check_size(&area)?;
+        Ok((area, image))
     }
-    
-    fn add_to_cache(&mut self, key: BoundsKey, data: String) {
-        let size = data.len();
-        
-        // Cache size management
-        if self.get_total_cache_size() + size > self.max_cache_size {
-            self.evict_oldest_entries(size);
+
+    /// Capture the whole screens `area` touches, keep that as a frame and crop `area` from it
+    fn capture_frame(&mut self, area: &CaptureBounds) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        // Bounds are global desktop coordinates - may be on any screen or span several
+        let screens = self.source.screens()?;
+        let covering = screens.iter()
+            .map(|screen| screen.bounds())
+            .filter(|screen| screen.intersection(area).is_some())
+            .reduce(|acc, screen| acc.union(&screen))
+            .ok_or_else(|| CaptureError::OutsideScreens(area.clone()))?;
+
+        let (frame_bounds, frame_image) = ScreenCapture::capture_global_from(self.source.as_ref(), &covering)?;
+        let frame = CachedFrame { bounds: frame_bounds, image: frame_image, captured_at: Instant::now() };
+
+        let clipped = area.intersection(&frame.bounds)
+            .ok_or_else(|| CaptureError::OutsideScreens(area.clone()))?;
+        let image = Self::crop_contained(&frame, &clipped)
+            .ok_or_else(|| CaptureError::Backend(format!("Capture area {:?} is outside the captured frame", clipped)))?;
+
+        if self.selecting {
+            if self.frames.len() >= self.max_frames {
+                self.frames.remove(0);
+            }
+            self.frames.push(frame);
         }
-        
-        self.cache.insert(key, CachedCapture {
-            data,
-            captured_at: Instant::now(),
-            size_bytes: size,
-        });
-        
-        println!("💾 Added to screenshot cache. Total entries: {}", self.cache.len());
+        Ok((clipped, image))
     }
-    
-    fn get_total_cache_size(&self) -> usize {
-        self.cache.values().map(|cached| cached.size_bytes).sum()
-    }
-    
-    fn evict_oldest_entries(&mut self, needed_space: usize) {
-        let mut entries: Vec<_> = self.cache.iter().collect();
-        entries.sort_by_key(|(_, cached)| cached.captured_at);
-        
-        let mut freed_space = 0;
-        let mut keys_to_remove = Vec::new();
-        
-        for (key, cached) in entries {
-            keys_to_remove.push(key.clone());
-            freed_space += cached.size_bytes;
-            
-            if freed_space >= needed_space {
-                break;
-            }
+
+    /// Physical pixels of `area`, when the frame covers all of it
+    fn crop_contained(frame: &CachedFrame, area: &CaptureBounds) -> Option<RgbaImage> {
+        if area.intersection(&frame.bounds).as_ref() != Some(area) {
+            return None;
         }
-        
-        for key in keys_to_remove {
-            self.cache.remove(&key);
+
+        let scale = frame.image.width() as f32 / frame.bounds.width.max(1) as f32;
+        let frame_screen = screen_capture::ScreenInfo {
+            id: 0,
+            x: frame.bounds.x,
+            y: frame.bounds.y,
+            width: frame.bounds.width,
+            height: frame.bounds.height,
+            scale_factor: scale,
+            is_primary: false,
+        };
+        let region = coordinates::logical_to_physical(area, &frame_screen, scale, frame.image.dimensions());
+        if region.width == 0 || region.height == 0 {
+            return None;
         }
-        
-        println!("🗑️ Evicted {} old cache entries, freed {}KB", 
-                 self.cache.len(), freed_space / 1024);
-    }
-    
-    fn get_screen_info(&self) -> Result<ScreenInfo, String> {
-        match screenshots::Screen::all() {
-            Ok(screens) => {
-                if let Some(screen) = screens.first() {
-                    Ok(ScreenInfo {
-                        width: screen.display_info.width,
-                        height: screen.display_info.height,
-                        scale_factor: screen.display_info.scale_factor as f64,
-                        cached_at: Instant::now(),
-                    })
-                } else {
-                    Err("No screens available".to_string())
-                }
-            },
-            Err(e) => Err(format!("Failed to get screen info: {}", e))
+        Some(imageops::crop_imm(&frame.image, region.x, region.y, region.width, region.height).to_image())
+    }
+
+    fn add_to_cache(&mut self, key: CacheKey, capture: CachedCapture) {
+        let size = capture.stored_bytes();
+        let evicted = self.cache.insert(key, capture, size);
+        if !evicted.is_empty() {
+            let freed_space: usize = evicted.iter().map(|(_, cached)| cached.stored_bytes()).sum();
+            self.evictions += evicted.len() as u64;
+            println!("🗑️ Evicted {} least recently used cache entries, freed {}KB",
+                     evicted.len(), freed_space / 1024);
         }
+
+        println!("💾 Added to screenshot cache. Total entries: {}", self.cache.len());
+    }
+
+    /// Memory budget for cached captures; entries over it are evicted least recently used first
+    pub fn set_budget(&mut self, budget_bytes: usize) {
+        let evicted = self.cache.set_budget(budget_bytes);
+        self.evictions += evicted.len() as u64;
+        println!("📏 Screenshot cache budget set to {}MB ({} entries evicted)",
+                 budget_bytes / (1024 * 1024), evicted.len());
+    }
+
+    fn get_screen_info(&self) -> Result<ScreenInfo, CaptureError> {
+        let screens = self.source.screens()?;
+        let desktop = ScreenCapture::desktop_bounds(&screens).ok_or(CaptureError::NoScreens)?;
+        let scale_factor = screens.iter().map(|screen| screen.scale_factor).fold(1.0f32, f32::max);
+
+        Ok(ScreenInfo {
+            width: desktop.width,
+            height: desktop.height,
+            scale_factor: scale_factor as f64,
+            cached_at: Instant::now(),
+        })
     }
-    
+
     pub fn clear_cache(&mut self) {
         self.cache.clear();
+        self.frames.clear();
         println!("🗑️ Screenshot cache cleared");
     }
-    
-    pub fn get_cache_stats(&self) -> (usize, usize, usize) {
-        let total_entries = self.cache.len();
-        let total_size = self.get_total_cache_size();
-        let expired_entries = self.cache.values()
-            .filter(|cached| cached.captured_at.elapsed() > self.cache_ttl)
-            .count();
-        (total_entries, total_size, expired_entries)
+
+    pub fn get_cache_stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.cache.len(),
+            size_bytes: self.cache.bytes(),
+            budget_bytes: self.cache.budget(),
+            raw_bytes: self.cache.iter().map(|(_, cached)| cached.raw_bytes).sum(),
+            encoded_bytes: self.cache.iter().map(|(_, cached)| cached.capture.byte_size).sum(),
+            expired_entries: self.cache.iter()
+                .filter(|(_, cached)| cached.last_used.elapsed() > self.cache_ttl)
+                .count(),
+            evictions: self.evictions,
+            hits: self.hits,
+            misses: self.misses,
+            bytes_served: self.bytes_served,
+            frame_reuses: self.frame_reuses,
+            frames: self.frames.len(),
+            frame_bytes: self.frames.iter().map(|frame| frame.image.as_raw().len()).sum(),
+        }
     }
-    
+
     pub fn cleanup_expired(&mut self) {
+        if self.cache.is_empty() && self.frames.is_empty() {
+            return;
+        }
+
         let now = Instant::now();
-        let before_count = self.cache.len();
-        
-        self.cache.retain(|_key, cached| now.duration_since(cached.captured_at) < self.cache_ttl);
-        
-        let after_count = self.cache.len();
-        let removed = before_count - after_count;
-        
+        let cache_ttl = self.cache_ttl;
+
+        let removed = self.cache.retain(|_key, cached| now.duration_since(cached.last_used) < cache_ttl);
+        self.frames.retain(|frame| now.duration_since(frame.captured_at) < self.frame_ttl);
+
         if removed > 0 {
             println!("🧹 Cleaned up {} expired screenshot cache entries", removed);
         }
     }
-    
+
+    /// How long a captured frame serves follow-up selections (zero disables reuse)
+    pub fn set_frame_ttl(&mut self, frame_ttl: Duration) {
+        self.frame_ttl = frame_ttl;
+        self.frames.retain(|frame| frame.captured_at.elapsed() < frame_ttl);
+    }
+
     pub fn resize_buffer(&mut self, new_capacity: usize) {
-        self.png_buffer.clear();
-        self.png_buffer.reserve(new_capacity);
-        println!("📏 Resized PNG buffer to {}MB", new_capacity / (1024 * 1024));
+        self.encode_buffer.clear();
+        self.encode_buffer.reserve(new_capacity);
+        println!("📏 Resized encode buffer to {}MB", new_capacity / (1024 * 1024));
     }
 }
 
@@ -230,4 +355,4 @@ impl Default for ScreenshotCache {
     fn default() -> Self {
         Self::new()
     }
-} 
\ No newline at end of file
+}
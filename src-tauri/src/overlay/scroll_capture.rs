@@ -0,0 +1,323 @@
+// Scrolling capture: the same region is captured over and over while the user
+// scrolls, and consecutive frames are stitched where their rows overlap.
+// ScrollStitcher is the pure algorithm (frames in, one tall image out); ScrollCapture
+// runs it against live captures on a worker thread.
+use image::RgbaImage;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use super::capture_service::{CaptureError, CaptureService};
+use super::screen_capture::CaptureBounds;
+
+/// How often the region is captured while scrolling
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(150);
+/// Stitched images stop growing here (about 240MB of RGBA at 2000px wide)
+pub const MAX_STITCHED_HEIGHT: u32 = 30_000;
+// An overlap must contain this many non-blank rows to be trusted...
+const MIN_MATCH_ROWS: usize = 8;
+// ...and this share of them must match exactly
+const MIN_MATCH_RATIO: f32 = 0.9;
+
+/// What pushing one frame did
+#[derive(Clone, Debug, PartialEq)]
+pub enum StitchStep {
+    /// First frame of the sequence
+    Started,
+    /// Same content as the previous frame (not scrolled)
+    Unchanged,
+    /// Scrolled down by `rows`; that many new rows were added
+    Appended { rows: u32 },
+    /// No overlap with the previous frame (scrolled too far at once, or upwards);
+    /// the frame was dropped and the next one is matched against the old frame
+    NoOverlap,
+    /// MAX_STITCHED_HEIGHT reached; further frames are ignored
+    Full,
+}
+
+// A row's content hash and whether it has any detail (blank rows match anything)
+#[derive(Clone, Copy, PartialEq)]
+struct RowSignature {
+    hash: u64,
+    informative: bool,
+}
+
+fn row_signatures(frame: &RgbaImage) -> Vec<RowSignature> {
+    let stride = frame.width() as usize * 4;
+    frame.as_raw().chunks_exact(stride.max(4))
+        .map(|row| {
+            let mut hasher = DefaultHasher::new();
+            row.hash(&mut hasher);
+            RowSignature {
+                hash: hasher.finish(),
+                informative: row.chunks_exact(4).any(|pixel| pixel != &row[..4]),
+            }
+        })
+        .collect()
+}
+
+/// Stitches a sequence of equally sized frames of one region into a tall image.
+/// Rows that stay put while the rest scrolls (sticky headers, toolbars, footers) are
+/// kept once: the header from the first frame and the footer from the last.
+#[derive(Default)]
+pub struct ScrollStitcher {
+    width: u32,
+    height: u32,
+    // Header and scrolled content so far, RGBA rows
+    content: Vec<u8>,
+    // Static rows at the top and bottom, fixed at the first scroll
+    margins: Option<(u32, u32)>,
+    last: Option<(RgbaImage, Vec<RowSignature>)>,
+    frames: usize,
+    skipped: usize,
+    full: bool,
+}
+
+impl ScrollStitcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, frame: RgbaImage) -> Result<StitchStep, String> {
+        let signatures = row_signatures(&frame);
+        let Some((previous, previous_signatures)) = &self.last else {
+            self.width = frame.width();
+            self.height = frame.height();
+            self.content = frame.as_raw().clone();
+            self.frames = 1;
+            self.last = Some((frame, signatures));
+            return Ok(StitchStep::Started);
+        };
+        if frame.dimensions() != previous.dimensions() {
+            return Err(format!("Frame is {}x{}, the scrolling region is {}x{}",
+                               frame.width(), frame.height(), previous.width(), previous.height()));
+        }
+        if self.full {
+            return Ok(StitchStep::Full);
+        }
+        if signatures == *previous_signatures {
+            self.frames += 1;
+            self.last = Some((frame, signatures));
+            return Ok(StitchStep::Unchanged);
+        }
+
+        let (top, bottom) = self.margins.unwrap_or_else(|| static_margins(previous_signatures, &signatures));
+        let Some(offset) = find_scroll_offset(previous_signatures, &signatures, top, bottom) else {
+            // Not scrolled if at most a caret blinked or a row repainted
+            if overlap_ratio(previous_signatures, &signatures, 0).is_some_and(|ratio| ratio >= MIN_MATCH_RATIO) {
+                self.frames += 1;
+                self.last = Some((frame, signatures));
+                return Ok(StitchStep::Unchanged);
+            }
+            self.skipped += 1;
+            return Ok(StitchStep::NoOverlap);
+        };
+
+        if self.margins.is_none() {
+            // The first frame's footer is not part of the content
+            self.margins = Some((top, bottom));
+            self.content.truncate((self.height - bottom) as usize * self.row_bytes());
+        }
+        let available = MAX_STITCHED_HEIGHT.saturating_sub(self.stitched_height());
+        let rows = offset.min(available);
+        let end = self.height - bottom;
+        let row_bytes = self.row_bytes();
+        self.content.extend_from_slice(&frame.as_raw()[(end - offset) as usize * row_bytes..(end - offset + rows) as usize * row_bytes]);
+
+        self.frames += 1;
+        self.last = Some((frame, signatures));
+        if rows < offset || available == rows {
+            self.full = true;
+            return Ok(StitchStep::Full);
+        }
+        Ok(StitchStep::Appended { rows })
+    }
+
+    fn row_bytes(&self) -> usize {
+        self.width as usize * 4
+    }
+
+    fn footer_rows(&self) -> u32 {
+        self.margins.map_or(0, |(_, bottom)| bottom)
+    }
+
+    /// Height of the image `finish` returns
+    pub fn stitched_height(&self) -> u32 {
+        (self.content.len() / self.row_bytes().max(1)) as u32 + self.footer_rows()
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Frames dropped for not overlapping the previous one
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// The stitched image: header, all scrolled content, then the latest footer
+    pub fn finish(&self) -> Option<RgbaImage> {
+        let (last, _) = self.last.as_ref()?;
+        let mut pixels = self.content.clone();
+        let footer = self.footer_rows();
+        if footer > 0 {
+            pixels.extend_from_slice(&last.as_raw()[(self.height - footer) as usize * self.row_bytes()..]);
+        }
+        RgbaImage::from_raw(self.width, self.stitched_height(), pixels)
+    }
+}
+
+/// Rows equal at the same position at the top and bottom of both frames
+fn static_margins(previous: &[RowSignature], next: &[RowSignature]) -> (u32, u32) {
+    let top = previous.iter().zip(next).take_while(|(a, b)| a == b).count();
+    let bottom = previous.iter().rev().zip(next.iter().rev()).take_while(|(a, b)| a == b).count();
+    // Identical frames are handled before this; keep the margins from overlapping
+    let bottom = bottom.min(previous.len().saturating_sub(top));
+    (top as u32, bottom as u32)
+}
+
+/// How many rows the content between the margins moved up from `previous` to `next`.
+/// The candidate whose overlapping rows match best wins, the smaller offset on ties.
+fn find_scroll_offset(previous: &[RowSignature], next: &[RowSignature], top: u32, bottom: u32) -> Option<u32> {
+    let start = top as usize;
+    let end = previous.len().saturating_sub(bottom as usize);
+    if end <= start {
+        return None;
+    }
+    let previous = &previous[start..end];
+    let next = &next[start..end];
+
+    let mut best: Option<(u32, f32)> = None;
+    for offset in 1..previous.len() {
+        let Some(ratio) = overlap_ratio(previous, next, offset) else { continue };
+        if ratio >= MIN_MATCH_RATIO && best.is_none_or(|(_, best_ratio)| ratio > best_ratio) {
+            best = Some((offset as u32, ratio));
+        }
+    }
+    best.map(|(offset, _)| offset)
+}
+
+/// Share of rows that match when `next` is `previous` moved up by `offset` rows;
+/// None when the overlap has too few non-blank rows to judge
+fn overlap_ratio(previous: &[RowSignature], next: &[RowSignature], offset: usize) -> Option<f32> {
+    let (mut compared, mut matched) = (0usize, 0usize);
+    for (old, new) in previous.iter().skip(offset).zip(next) {
+        if !old.informative && !new.informative {
+            continue;
+        }
+        compared += 1;
+        if old.hash == new.hash {
+            matched += 1;
+        }
+    }
+    (compared >= MIN_MATCH_ROWS).then(|| matched as f32 / compared as f32)
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScrollStatus {
+    pub running: bool,
+    pub frames: usize,
+    pub skipped: usize,
+    pub width: u32,
+    pub height: u32,
+    pub last_error: Option<String>,
+}
+
+/// A scrolling capture in progress: captures `bounds` every FRAME_INTERVAL (slower
+/// where the backend caps its frame rate) on a worker thread until finished
+pub struct ScrollCapture {
+    bounds: CaptureBounds,
+    service: Arc<CaptureService>,
+    stitcher: Arc<Mutex<ScrollStitcher>>,
+    stop: Arc<AtomicBool>,
+    last_error: Arc<Mutex<Option<CaptureError>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ScrollCapture {
+    /// Take the first frame (so a bad region fails right away) and start the worker
+    pub fn start(service: CaptureService, bounds: &CaptureBounds) -> Result<Self, CaptureError> {
+        let (bounds, first) = service.capture_pixels(bounds)?;
+        let mut stitcher = ScrollStitcher::new();
+        stitcher.push(first).map_err(CaptureError::Backend)?;
+
+        let mut capture = Self {
+            bounds,
+            service: Arc::new(service),
+            stitcher: Arc::new(Mutex::new(stitcher)),
+            stop: Arc::new(AtomicBool::new(false)),
+            last_error: Arc::new(Mutex::new(None)),
+            worker: None,
+        };
+
+        let (service, stitcher, stop, last_error) = (capture.service.clone(), capture.stitcher.clone(), capture.stop.clone(), capture.last_error.clone());
+        let bounds = capture.bounds.clone();
+        let interval = service.frame_interval(FRAME_INTERVAL);
+        capture.worker = Some(std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                std::thread::sleep(interval);
+                match Self::capture_frame(&service, &stitcher, &bounds) {
+                    Ok(StitchStep::Full) => break,
+                    Ok(_) => {},
+                    Err(e) => *last_error.lock().unwrap() = Some(e),
+                }
+            }
+        }));
+        println!("📜 Scrolling capture started: {}x{} at ({}, {})",
+                 capture.bounds.width, capture.bounds.height, capture.bounds.x, capture.bounds.y);
+        Ok(capture)
+    }
+
+    fn capture_frame(service: &CaptureService, stitcher: &Mutex<ScrollStitcher>, bounds: &CaptureBounds) -> Result<StitchStep, CaptureError> {
+        let (_, frame) = service.capture_pixels(bounds)?;
+        stitcher.lock().unwrap().push(frame).map_err(CaptureError::Backend)
+    }
+
+    pub fn bounds(&self) -> &CaptureBounds {
+        &self.bounds
+    }
+
+    pub fn status(&self) -> ScrollStatus {
+        let stitcher = self.stitcher.lock().unwrap();
+        ScrollStatus {
+            running: self.worker.as_ref().is_some_and(|worker| !worker.is_finished()),
+            frames: stitcher.frames(),
+            skipped: stitcher.skipped(),
+            width: self.bounds.width,
+            height: stitcher.stitched_height(),
+            last_error: self.last_error.lock().unwrap().as_ref().map(|e| e.to_string()),
+        }
+    }
+
+    fn stop_worker(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+
+    /// Stop capturing and return the stitched image (with one last frame, so
+    /// scrolling just before finishing is not lost)
+    pub fn finish(mut self) -> Result<(CaptureBounds, RgbaImage), CaptureError> {
+        self.stop_worker();
+        if let Err(e) = Self::capture_frame(&self.service, &self.stitcher, &self.bounds) {
+            println!("⚠️ Final scrolling frame failed: {}", e);
+        }
+
+        let stitcher = self.stitcher.lock().unwrap();
+        let image = stitcher.finish()
+            .ok_or_else(|| CaptureError::Backend("Scrolling capture has no frames".to_string()))?;
+        println!("📜 Scrolling capture finished: {} frames ({} skipped) stitched to {}x{}",
+                 stitcher.frames(), stitcher.skipped(), image.width(), image.height());
+        Ok((self.bounds.clone(), image))
+    }
+}
+
+impl Drop for ScrollCapture {
+    fn drop(&mut self) {
+        self.stop_worker();
+    }
+}
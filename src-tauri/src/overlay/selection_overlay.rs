@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
-use super::screen_capture::{CaptureBounds, ScreenCapture, ScreenInfo};
+use super::capture_service::CaptureService;
+use super::image_encoding::OutputOptions;
+use super::screen_capture::{CaptureBounds, ScreenInfo};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct SelectionResult {
@@ -68,7 +70,7 @@ impl SelectionOverlay {
         println!("📐 Simulated selection bounds: {:?}", bounds);
         
         // Capture the selected region
-        match ScreenCapture::capture_region(bounds.clone()).await {
+        match CaptureService::new().capture(&bounds, &OutputOptions::default()) {
             Ok(capture_result) => Ok(SelectionResult {
                 bounds: capture_result.bounds,
                 image_data: capture_result.image_data,
@@ -149,7 +151,7 @@ impl SelectionOverlay {
             }
             
             // Capture the selected region
-            match ScreenCapture::capture_region(bounds.clone()).await {
+            match CaptureService::new().capture(&bounds, &OutputOptions::default()) {
                 Ok(capture_result) => Ok(Some(SelectionResult {
                     bounds: capture_result.bounds,
                     image_data: capture_result.image_data,
@@ -0,0 +1,34 @@
+// Top-level application windows for the window-picking capture mode
+// Backends list windows front to back (topmost first) in desktop units; the overlay
+// highlights the one under the cursor and a click captures exactly that window.
+use serde::{Deserialize, Serialize};
+use super::screen_capture::CaptureBounds;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct WindowInfo {
+    pub id: u64,          // Native handle (X11 window id)
+    pub title: String,
+    pub app_name: String, // Application class, e.g. "firefox"
+    pub bounds: CaptureBounds,
+}
+
+impl WindowInfo {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.bounds.x && y >= self.bounds.y
+            && x < self.bounds.x + self.bounds.width as i32
+            && y < self.bounds.y + self.bounds.height as i32
+    }
+}
+
+/// Topmost window under a point in desktop units (`windows` ordered front to back)
+pub fn window_at(windows: &[WindowInfo], x: i32, y: i32) -> Option<&WindowInfo> {
+    windows.iter().find(|window| window.contains(x, y))
+}
+
+/// Windows as the overlay draws them: same order, bounds in overlay viewport pixels
+#[derive(Clone, Serialize, Debug)]
+pub struct OverlayWindow {
+    #[serde(flatten)]
+    pub window: WindowInfo,
+    pub overlay_bounds: CaptureBounds,
+}
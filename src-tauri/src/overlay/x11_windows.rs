@@ -0,0 +1,194 @@
+// Top-level windows under X11, read from the window manager's EWMH hints
+// _NET_CLIENT_LIST_STACKING lists managed windows bottom to top. When a compositing
+// manager runs, every frame is redirected off-screen (Composite extension), so a
+// window's own pixmap still has its pixels where other windows cover it.
+use image::RgbaImage;
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::composite::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt as _, ImageFormat, ImageOrder, MapState, Window};
+use x11rb::rust_connection::RustConnection;
+use super::capture_service::CaptureError;
+use super::screen_capture::CaptureBounds;
+use super::window_capture::WindowInfo;
+
+// _NET_WM_DESKTOP of windows shown on every desktop
+const ALL_DESKTOPS: u32 = 0xFFFF_FFFF;
+
+struct Atoms {
+    client_list_stacking: Atom,
+    current_desktop: Atom,
+    wm_desktop: Atom,
+    wm_name: Atom,
+    wm_pid: Atom,
+    wm_state: Atom,
+    wm_state_hidden: Atom,
+    wm_window_type: Atom,
+    // Panels and the desktop background are not windows anyone wants to capture
+    skipped_types: [Atom; 2],
+    utf8_string: Atom,
+}
+
+impl Atoms {
+    fn new(conn: &RustConnection) -> Result<Self, CaptureError> {
+        Ok(Self {
+            client_list_stacking: intern(conn, "_NET_CLIENT_LIST_STACKING")?,
+            current_desktop: intern(conn, "_NET_CURRENT_DESKTOP")?,
+            wm_desktop: intern(conn, "_NET_WM_DESKTOP")?,
+            wm_name: intern(conn, "_NET_WM_NAME")?,
+            wm_pid: intern(conn, "_NET_WM_PID")?,
+            wm_state: intern(conn, "_NET_WM_STATE")?,
+            wm_state_hidden: intern(conn, "_NET_WM_STATE_HIDDEN")?,
+            wm_window_type: intern(conn, "_NET_WM_WINDOW_TYPE")?,
+            skipped_types: [intern(conn, "_NET_WM_WINDOW_TYPE_DOCK")?, intern(conn, "_NET_WM_WINDOW_TYPE_DESKTOP")?],
+            utf8_string: intern(conn, "UTF8_STRING")?,
+        })
+    }
+}
+
+fn x11_error(error: impl std::fmt::Display) -> CaptureError {
+    CaptureError::Backend(format!("X11: {}", error))
+}
+
+fn intern(conn: &RustConnection, name: &str) -> Result<Atom, CaptureError> {
+    Ok(conn.intern_atom(false, name.as_bytes()).map_err(x11_error)?.reply().map_err(x11_error)?.atom)
+}
+
+fn property32(conn: &RustConnection, window: Window, property: Atom, kind: impl Into<Atom>) -> Result<Vec<u32>, CaptureError> {
+    let reply = conn.get_property(false, window, property, kind, 0, u32::MAX / 4)
+        .map_err(x11_error)?.reply().map_err(x11_error)?;
+    Ok(reply.value32().map(|values| values.collect()).unwrap_or_default())
+}
+
+fn property_bytes(conn: &RustConnection, window: Window, property: impl Into<Atom>, kind: impl Into<Atom>) -> Result<Vec<u8>, CaptureError> {
+    let reply = conn.get_property(false, window, property, kind, 0, u32::MAX / 4)
+        .map_err(x11_error)?.reply().map_err(x11_error)?;
+    Ok(reply.value)
+}
+
+/// Managed windows on the current desktop, topmost first. X11 positions are root
+/// pixels; `scale` (Xft.dpi) turns them into desktop units.
+pub fn windows(scale: f32) -> Result<Vec<WindowInfo>, CaptureError> {
+    let (conn, screen_num) = x11rb::connect(None).map_err(x11_error)?;
+    let root = conn.setup().roots[screen_num].root;
+    let atoms = Atoms::new(&conn)?;
+
+    let stacking = property32(&conn, root, atoms.client_list_stacking, AtomEnum::WINDOW)?;
+    if stacking.is_empty() {
+        return Err(CaptureError::Backend("The window manager does not publish _NET_CLIENT_LIST_STACKING (EWMH)".to_string()));
+    }
+    let current_desktop = property32(&conn, root, atoms.current_desktop, AtomEnum::CARDINAL)?.first().copied();
+
+    let mut windows = Vec::new();
+    for &window in stacking.iter().rev() {
+        // Windows close between listing and querying; skip those
+        if let Ok(Some(info)) = describe(&conn, root, &atoms, window, current_desktop, scale) {
+            windows.push(info);
+        }
+    }
+    Ok(windows)
+}
+
+fn describe(conn: &RustConnection, root: Window, atoms: &Atoms, window: Window, current_desktop: Option<u32>, scale: f32) -> Result<Option<WindowInfo>, CaptureError> {
+    let attributes = conn.get_window_attributes(window).map_err(x11_error)?.reply().map_err(x11_error)?;
+    if attributes.map_state != MapState::VIEWABLE
+        || property32(conn, window, atoms.wm_state, AtomEnum::ATOM)?.contains(&atoms.wm_state_hidden)
+        || property32(conn, window, atoms.wm_window_type, AtomEnum::ATOM)?.iter().any(|kind| atoms.skipped_types.contains(kind))
+        // Our own overlay sits on top while picking
+        || property32(conn, window, atoms.wm_pid, AtomEnum::CARDINAL)?.first() == Some(&std::process::id())
+    {
+        return Ok(None);
+    }
+    let desktop = property32(conn, window, atoms.wm_desktop, AtomEnum::CARDINAL)?.first().copied();
+    if let (Some(desktop), Some(current)) = (desktop, current_desktop) {
+        if desktop != ALL_DESKTOPS && desktop != current {
+            return Ok(None);
+        }
+    }
+
+    let geometry = conn.get_geometry(window).map_err(x11_error)?.reply().map_err(x11_error)?;
+    let origin = conn.translate_coordinates(window, root, 0, 0).map_err(x11_error)?.reply().map_err(x11_error)?;
+
+    let mut title = property_bytes(conn, window, atoms.wm_name, atoms.utf8_string)?;
+    if title.is_empty() {
+        title = property_bytes(conn, window, AtomEnum::WM_NAME, AtomEnum::STRING)?;
+    }
+    // WM_CLASS is "instance\0class\0"; the class names the application
+    let class = property_bytes(conn, window, AtomEnum::WM_CLASS, AtomEnum::STRING)?;
+    let mut class_parts = class.split(|byte| *byte == 0).filter(|part| !part.is_empty());
+    let instance = class_parts.next().unwrap_or_default();
+    let app_name = class_parts.next().unwrap_or(instance);
+
+    Ok(Some(WindowInfo {
+        id: window as u64,
+        title: String::from_utf8_lossy(&title).into_owned(),
+        app_name: String::from_utf8_lossy(app_name).into_owned(),
+        bounds: root_to_desktop(origin.dst_x as i32, origin.dst_y as i32, geometry.width as u32, geometry.height as u32, scale),
+    }))
+}
+
+/// Root-window pixels to desktop units (rounded outwards)
+fn root_to_desktop(x: i32, y: i32, width: u32, height: u32, scale: f32) -> CaptureBounds {
+    let scale = if scale.is_finite() && scale > 0.0 { scale as f64 } else { 1.0 };
+    let x1 = (x as f64 / scale).floor();
+    let y1 = (y as f64 / scale).floor();
+    let x2 = ((x as f64 + width as f64) / scale).ceil();
+    let y2 = ((y as f64 + height as f64) / scale).ceil();
+    CaptureBounds { x: x1 as i32, y: y1 as i32, width: (x2 - x1) as u32, height: (y2 - y1) as u32 }
+}
+
+/// The window's client area from its off-screen pixmap. None without a compositing
+/// manager (nothing is redirected, so covered parts have no pixels to read).
+pub fn capture_window(window: &WindowInfo) -> Result<Option<RgbaImage>, CaptureError> {
+    let (conn, screen_num) = x11rb::connect(None).map_err(x11_error)?;
+    let root = conn.setup().roots[screen_num].root;
+
+    let manager = intern(&conn, &format!("_NET_WM_CM_S{}", screen_num))?;
+    let owner = conn.get_selection_owner(manager).map_err(x11_error)?.reply().map_err(x11_error)?.owner;
+    if owner == x11rb::NONE || conn.extension_information(composite::X11_EXTENSION_NAME).map_err(x11_error)?.is_none() {
+        return Ok(None);
+    }
+    conn.composite_query_version(0, 2).map_err(x11_error)?.reply().map_err(x11_error)?;
+
+    // The compositor redirects the window manager's frame, not the client inside it
+    let client = window.id as Window;
+    let mut frame = client;
+    loop {
+        let parent = conn.query_tree(frame).map_err(x11_error)?.reply().map_err(x11_error)?.parent;
+        if parent == root || parent == x11rb::NONE {
+            break;
+        }
+        frame = parent;
+    }
+    let offset = conn.translate_coordinates(client, frame, 0, 0).map_err(x11_error)?.reply().map_err(x11_error)?;
+    let geometry = conn.get_geometry(client).map_err(x11_error)?.reply().map_err(x11_error)?;
+
+    let pixmap = conn.generate_id().map_err(x11_error)?;
+    if let Err(e) = conn.composite_name_window_pixmap(frame, pixmap).map_err(x11_error)?.check() {
+        println!("⚠️ Window {} is not redirected ({}), capturing it from the screen", client, e);
+        return Ok(None);
+    }
+    let image = conn.get_image(ImageFormat::Z_PIXMAP, pixmap, offset.dst_x, offset.dst_y, geometry.width, geometry.height, !0)
+        .map_err(x11_error)?.reply();
+    let _ = conn.free_pixmap(pixmap);
+    let image = image.map_err(x11_error)?;
+
+    let setup = conn.setup();
+    let bits_per_pixel = setup.pixmap_formats.iter()
+        .find(|format| format.depth == image.depth)
+        .map(|format| format.bits_per_pixel);
+    if bits_per_pixel != Some(32) {
+        println!("⚠️ Window {} has an unsupported pixel format (depth {}), capturing it from the screen", client, image.depth);
+        return Ok(None);
+    }
+
+    // 32-bit ZPixmap pixels are BGRX (ARGB for 32-bit visuals) in the server's byte order
+    let lsb_first = setup.image_byte_order == ImageOrder::LSB_FIRST;
+    let mut rgba = Vec::with_capacity(image.data.len());
+    for pixel in image.data.chunks_exact(4) {
+        let (r, g, b) = if lsb_first { (pixel[2], pixel[1], pixel[0]) } else { (pixel[1], pixel[2], pixel[3]) };
+        rgba.extend_from_slice(&[r, g, b, 255]);
+    }
+    RgbaImage::from_raw(geometry.width as u32, geometry.height as u32, rgba)
+        .map(Some)
+        .ok_or_else(|| CaptureError::Backend(format!("Window {} returned a short image", client)))
+}
@@ -0,0 +1,189 @@
+// Pattern detectors for data that must not leave the machine. Patterns are
+// deliberately loose (OCR inserts spaces and confuses characters); checksums
+// (Luhn for cards, mod-97 for IBANs) keep the false positives down.
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectorKind {
+    Email,
+    CreditCard,
+    Iban,
+    ApiKey,
+    Jwt,
+    Password, // The value after "password:", not the label
+    Phone,
+}
+
+impl DetectorKind {
+    pub const ALL: [DetectorKind; 7] = [
+        DetectorKind::Email,
+        DetectorKind::CreditCard,
+        DetectorKind::Iban,
+        DetectorKind::ApiKey,
+        DetectorKind::Jwt,
+        DetectorKind::Password,
+        DetectorKind::Phone,
+    ];
+
+    /// Placeholder the text is replaced with
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            DetectorKind::Email => "[REDACTED EMAIL]",
+            DetectorKind::CreditCard => "[REDACTED CARD]",
+            DetectorKind::Iban => "[REDACTED IBAN]",
+            DetectorKind::ApiKey => "[REDACTED KEY]",
+            DetectorKind::Jwt => "[REDACTED TOKEN]",
+            DetectorKind::Password => "[REDACTED PASSWORD]",
+            DetectorKind::Phone => "[REDACTED PHONE]",
+        }
+    }
+}
+
+/// Something sensitive in a text, as a byte range
+#[derive(Clone, Debug, PartialEq)]
+pub struct Detection {
+    pub kind: DetectorKind,
+    pub range: Range<usize>,
+}
+
+struct Detector {
+    kind: DetectorKind,
+    pattern: Regex,
+    // Capture group holding the secret (0 is the whole match)
+    group: usize,
+    is_valid: fn(&str) -> bool,
+}
+
+// In precedence order: where matches overlap the earlier detector wins, so a card
+// number is not also reported as a phone number
+fn detectors() -> &'static [Detector] {
+    static DETECTORS: OnceLock<Vec<Detector>> = OnceLock::new();
+    DETECTORS.get_or_init(|| {
+        let detector = |kind, pattern: &str, group, is_valid| Detector {
+            kind,
+            pattern: Regex::new(pattern).expect("detector pattern"),
+            group,
+            is_valid,
+        };
+        vec![
+            detector(DetectorKind::Jwt, r"\beyJ[A-Za-z0-9_-]{5,}\.eyJ[A-Za-z0-9_-]{5,}\.[A-Za-z0-9_-]{10,}", 0, |_| true),
+            detector(DetectorKind::ApiKey,
+                     r"\b(?:sk-(?:proj-)?[A-Za-z0-9_-]{20,}|[sr]k_(?:live|test)_[A-Za-z0-9]{16,}|gh[pousr]_[A-Za-z0-9]{30,}|github_pat_[A-Za-z0-9_]{30,}|AKIA[0-9A-Z]{16}|xox[abprs]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})",
+                     0, |_| true),
+            // Anything assigned to a key-like name, e.g. `API_KEY=...` or `"client_secret": "..."`
+            detector(DetectorKind::ApiKey,
+                     r#"(?i)\b(?:api[_-]?key|secret(?:[_-]?key)?|access[_-]?token|auth[_-]?token|client[_-]?secret)["']?\s*[:=]\s*["']?([A-Za-z0-9_\-./+=]{12,})"#,
+                     1, |_| true),
+            detector(DetectorKind::Password,
+                     r#"(?i)\b(?:password|passwd|pwd|pass|passwort|lösenord|mot de passe)["']?\s*[:=]\s*["']?([^\s"']+)"#,
+                     1, |_| true),
+            detector(DetectorKind::Email, r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b", 0, |_| true),
+            detector(DetectorKind::Iban, r"(?i)\b[a-z]{2}\d{2}(?: ?[a-z0-9]){11,30}\b", 0, is_iban),
+            detector(DetectorKind::CreditCard, r"\b\d(?:[ -]?\d){12,18}\b", 0, is_card_number),
+            detector(DetectorKind::Phone, r"(?:\+|\b)\d[\d ().-]{6,18}\d\b", 0, is_phone_number),
+        ]
+    })
+}
+
+/// Every enabled detector's findings in `text`, in text order and never overlapping
+pub fn detect(text: &str, enabled: &[DetectorKind]) -> Vec<Detection> {
+    let mut detections: Vec<Detection> = Vec::new();
+    for detector in detectors().iter().filter(|detector| enabled.contains(&detector.kind)) {
+        for captures in detector.pattern.captures_iter(text) {
+            let Some(found) = captures.get(detector.group) else { continue };
+            let range = found.range();
+            if range.is_empty() || !(detector.is_valid)(found.as_str()) {
+                continue;
+            }
+            if detections.iter().any(|existing| existing.range.start < range.end && range.start < existing.range.end) {
+                continue;
+            }
+            detections.push(Detection { kind: detector.kind, range });
+        }
+    }
+    detections.sort_by_key(|detection| detection.range.start);
+    detections
+}
+
+/// `text` with every detection replaced by its placeholder
+pub fn redact(text: &str, detections: &[Detection]) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut position = 0;
+    for detection in detections {
+        redacted.push_str(&text[position..detection.range.start]);
+        redacted.push_str(detection.kind.placeholder());
+        position = detection.range.end;
+    }
+    redacted.push_str(&text[position..]);
+    redacted
+}
+
+/// Enough of a finding to recognise it in a report without repeating it
+pub fn mask(kind: DetectorKind, value: &str) -> String {
+    let chars: Vec<char> = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let tail = |n: usize| chars[chars.len().saturating_sub(n)..].iter().collect::<String>();
+    match kind {
+        DetectorKind::CreditCard | DetectorKind::Iban => format!("••••{}", tail(4)),
+        DetectorKind::Phone => format!("••••{}", tail(2)),
+        DetectorKind::Email => match value.split_once('@') {
+            Some((user, domain)) => format!("{}•••@{}", user.chars().next().unwrap_or('•'), domain),
+            None => "•••".to_string(),
+        },
+        // Key prefixes (sk-, ghp_, AKIA) say which service it belongs to
+        DetectorKind::ApiKey | DetectorKind::Jwt => format!("{}•••", chars.iter().take(4).collect::<String>()),
+        DetectorKind::Password => "••••••".to_string(),
+    }
+}
+
+fn digits(text: &str) -> Vec<u32> {
+    text.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// 13-19 digits passing the Luhn check
+pub fn is_card_number(text: &str) -> bool {
+    let digits = digits(text);
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits.iter().rev().enumerate().map(|(i, &digit)| {
+        if i % 2 == 1 {
+            let doubled = digit * 2;
+            if doubled > 9 { doubled - 9 } else { doubled }
+        } else {
+            digit
+        }
+    }).sum();
+    sum.is_multiple_of(10)
+}
+
+/// Country code, check digits and account, passing the ISO 13616 mod-97 check
+pub fn is_iban(text: &str) -> bool {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+    if !(15..=34).contains(&compact.len()) || !compact.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    // Move the first four characters to the end, letters become 10..35
+    let rearranged = compact[4..].chars().chain(compact[..4].chars());
+    let mut remainder: u32 = 0;
+    for c in rearranged {
+        let value = c.to_digit(36).unwrap_or(0);
+        remainder = if value >= 10 { (remainder * 100 + value) % 97 } else { (remainder * 10 + value) % 97 };
+    }
+    remainder == 1
+}
+
+// International numbers (+46 70 ...) with 7-15 digits, local ones with 9-15;
+// shorter runs are too easily order numbers or dates
+fn is_phone_number(text: &str) -> bool {
+    let count = digits(text).len();
+    let looks_like_date = text.len() == 10 && text.chars().filter(|c| *c == '-').count() == 2;
+    if text.starts_with('+') {
+        (7..=15).contains(&count)
+    } else {
+        (9..=15).contains(&count) && !looks_like_date
+    }
+}
@@ -0,0 +1,175 @@
+// The filter between a capture and the analysis backend: the image's words are
+// read with OCR, run through the detectors line by line, and the boxes of any
+// sensitive words are blurred or blacked out with the annotation renderer. Text
+// sent along (OCR context, prompts) gets placeholders instead. Recognition is
+// passed in, so this can be tested without Tesseract.
+use crate::annotate::{self, Annotation, AnnotationDocument, Color, Rect, RedactStyle};
+use crate::ocr::OCRLayout;
+use crate::overlay::CaptureBounds;
+use image::RgbaImage;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use super::detectors::{self, DetectorKind};
+use super::settings::{PrivacyPolicy, RedactionStyle};
+
+// OCR boxes hug the glyphs; a margin covers anti-aliasing and descenders
+const BOX_PADDING: u32 = 3;
+
+/// Word boxes of an image, in its own pixels (the OCR service in the app)
+pub type LayoutReader<'a> = &'a dyn Fn(&RgbaImage) -> Result<OCRLayout, String>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionSource {
+    Image,
+    Text,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Redaction {
+    pub kind: DetectorKind,
+    pub source: RedactionSource,
+    pub preview: String, // Masked, e.g. "••••1111"
+    pub bounds: Option<CaptureBounds>, // Image pixels, for image redactions
+}
+
+/// What the filter did, for the UI and the logs
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RedactionReport {
+    pub redactions: Vec<Redaction>,
+    pub image_scanned: bool,
+    // The image could not be scanned and the policy doesn't allow sending it unscanned
+    pub image_withheld: bool,
+    pub scan_error: Option<String>,
+}
+
+impl RedactionReport {
+    pub fn counts(&self) -> BTreeMap<DetectorKind, usize> {
+        let mut counts = BTreeMap::new();
+        for redaction in &self.redactions {
+            *counts.entry(redaction.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// What may be sent
+pub struct FilteredContent {
+    pub image: Option<RgbaImage>,
+    pub text: Option<String>,
+    pub report: RedactionReport,
+}
+
+/// Redact `image` and `text` according to `policy`
+pub fn filter(policy: &PrivacyPolicy, image: Option<RgbaImage>, text: Option<&str>, read_layout: LayoutReader) -> FilteredContent {
+    let mut report = RedactionReport::default();
+    if !policy.enabled || policy.detectors.is_empty() {
+        return FilteredContent { image, text: text.map(str::to_string), report };
+    }
+
+    let text = text.map(|text| {
+        if !policy.redact_text {
+            return text.to_string();
+        }
+        let (redacted, redactions) = redact_text(text, &policy.detectors);
+        report.redactions.extend(redactions);
+        redacted
+    });
+
+    let image = image.and_then(|image| {
+        let redacted = read_layout(&image).and_then(|layout| redact_image(&image, &layout, policy));
+        match redacted {
+            Ok((redacted, redactions)) => {
+                report.image_scanned = true;
+                report.redactions.extend(redactions);
+                Some(redacted)
+            },
+            Err(error) => {
+                println!("⚠️ Could not scan capture for sensitive data: {}", error);
+                report.scan_error = Some(error);
+                report.image_withheld = !policy.allow_unscanned_images;
+                policy.allow_unscanned_images.then_some(image)
+            },
+        }
+    });
+
+    FilteredContent { image, text, report }
+}
+
+/// `text` with placeholders for everything the detectors found
+pub fn redact_text(text: &str, enabled: &[DetectorKind]) -> (String, Vec<Redaction>) {
+    let detections = detectors::detect(text, enabled);
+    let redactions = detections.iter().map(|detection| Redaction {
+        kind: detection.kind,
+        source: RedactionSource::Text,
+        preview: detectors::mask(detection.kind, &text[detection.range.clone()]),
+        bounds: None,
+    }).collect();
+    (detectors::redact(text, &detections), redactions)
+}
+
+/// `image` with the words of every detection hidden. Detection runs per OCR line,
+/// so values OCR split into several words ("4111 1111 ...") are still found.
+pub fn redact_image(image: &RgbaImage, layout: &OCRLayout, policy: &PrivacyPolicy) -> Result<(RgbaImage, Vec<Redaction>), String> {
+    let mut redactions = Vec::new();
+    for line in layout.blocks.iter().flat_map(|block| &block.lines) {
+        // The line's text rebuilt from its words, remembering where each word is
+        let mut text = String::new();
+        let mut word_ranges = Vec::with_capacity(line.words.len());
+        for word in &line.words {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            word_ranges.push(text.len()..text.len() + word.text.len());
+            text.push_str(&word.text);
+        }
+
+        for detection in detectors::detect(&text, &policy.detectors) {
+            let covered = line.words.iter().zip(&word_ranges)
+                .filter(|(_, range)| range.start < detection.range.end && detection.range.start < range.end)
+                .map(|(word, _)| &word.bounds);
+            let Some(bounds) = union(covered) else { continue };
+            redactions.push(Redaction {
+                kind: detection.kind,
+                source: RedactionSource::Image,
+                preview: detectors::mask(detection.kind, &text[detection.range.clone()]),
+                bounds: Some(pad(&bounds, image.width(), image.height())),
+            });
+        }
+    }
+
+    let document = AnnotationDocument {
+        annotations: redactions.iter().filter_map(|redaction| redaction.bounds.as_ref()).map(|bounds| Annotation::Redact {
+            rect: Rect { x: bounds.x as f32, y: bounds.y as f32, width: bounds.width as f32, height: bounds.height as f32 },
+            style: match policy.style {
+                // Strong enough that the blurred text can't be guessed from its shape
+                RedactionStyle::Blur => RedactStyle::Blur { radius: (bounds.height / 2).max(8) },
+                RedactionStyle::BlackOut => RedactStyle::Fill { color: Color::BLACK },
+            },
+        }).collect(),
+        ..AnnotationDocument::new(image.width(), image.height())
+    };
+    Ok((annotate::render(image, &document)?, redactions))
+}
+
+fn union<'a>(mut bounds: impl Iterator<Item = &'a CaptureBounds>) -> Option<CaptureBounds> {
+    let first = bounds.next()?;
+    let (mut x1, mut y1) = (first.x, first.y);
+    let (mut x2, mut y2) = (first.x + first.width as i32, first.y + first.height as i32);
+    for b in bounds {
+        x1 = x1.min(b.x);
+        y1 = y1.min(b.y);
+        x2 = x2.max(b.x + b.width as i32);
+        y2 = y2.max(b.y + b.height as i32);
+    }
+    Some(CaptureBounds { x: x1, y: y1, width: (x2 - x1) as u32, height: (y2 - y1) as u32 })
+}
+
+fn pad(bounds: &CaptureBounds, width: u32, height: u32) -> CaptureBounds {
+    let padding = BOX_PADDING as i32;
+    let x1 = (bounds.x - padding).max(0);
+    let y1 = (bounds.y - padding).max(0);
+    let x2 = (bounds.x + bounds.width as i32 + padding).min(width as i32);
+    let y2 = (bounds.y + bounds.height as i32 + padding).min(height as i32);
+    CaptureBounds { x: x1, y: y1, width: (x2 - x1).max(0) as u32, height: (y2 - y1).max(0) as u32 }
+}
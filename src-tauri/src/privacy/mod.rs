@@ -0,0 +1,7 @@
+pub mod detectors;
+pub mod filter;
+pub mod settings;
+
+pub use detectors::DetectorKind;
+pub use filter::RedactionReport;
+pub use settings::{PrivacyPolicy, PrivacySettings, PrivacyStore, RedactionStyle};
@@ -0,0 +1,150 @@
+// Privacy policies on disk
+// <dir>/privacy.json holds the default policy and any per-team overrides; a team
+// without its own policy gets the default.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use super::detectors::DetectorKind;
+
+const SETTINGS_FILE: &str = "privacy.json";
+const SETTINGS_VERSION: u32 = 1;
+
+/// How detected regions are hidden in the image
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionStyle {
+    #[default]
+    Blur,
+    BlackOut,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyPolicy {
+    pub enabled: bool,
+    pub detectors: Vec<DetectorKind>,
+    pub style: RedactionStyle,
+    pub redact_text: bool, // OCR text and other text sent along with the image
+    // When the image can't be scanned, send it anyway instead of withholding it.
+    // On by default only in builds without an OCR engine, where no image could ever
+    // be scanned; the UI says when an image went out unscanned.
+    pub allow_unscanned_images: bool,
+}
+
+impl Default for PrivacyPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            detectors: DetectorKind::ALL.to_vec(),
+            style: RedactionStyle::Blur,
+            redact_text: true,
+            allow_unscanned_images: !crate::ocr::engine_available(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PrivacySettings {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub default: PrivacyPolicy,
+    #[serde(default)]
+    pub teams: BTreeMap<String, PrivacyPolicy>,
+}
+
+pub struct PrivacyStore {
+    dir: PathBuf,
+    settings: PrivacySettings,
+    // Why changes are refused, when privacy.json exists but could not be read
+    read_only: Option<String>,
+}
+
+impl PrivacyStore {
+    /// Open the policies kept in `dir`. A corrupt file is set aside as
+    /// privacy.json.bak and the defaults (everything on) apply. A file that can't be
+    /// read (permissions, I/O errors) also gets the defaults but is never replaced.
+    pub fn open(dir: PathBuf) -> Self {
+        let path = dir.join(SETTINGS_FILE);
+        let mut read_only = None;
+        let settings = match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str::<PrivacySettings>(&json) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    println!("⚠️ Privacy settings are corrupt ({}), using the defaults", e);
+                    let _ = fs::rename(&path, dir.join(format!("{}.bak", SETTINGS_FILE)));
+                    PrivacySettings::default()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => PrivacySettings::default(),
+            Err(e) => {
+                println!("⚠️ Cannot read {} ({}), using the default privacy policy read-only", path.display(), e);
+                read_only = Some(format!("{} could not be read: {}", path.display(), e));
+                PrivacySettings::default()
+            },
+        };
+        println!("🛡️ Privacy filter: {} team policies in {}", settings.teams.len(), dir.display());
+        Self { dir, settings, read_only }
+    }
+
+    /// Whether changes can be saved (false when privacy.json could not be read)
+    pub fn is_writable(&self) -> bool {
+        self.read_only.is_none()
+    }
+
+    pub fn settings(&self) -> &PrivacySettings {
+        &self.settings
+    }
+
+    /// The team's policy, or the default one
+    pub fn policy(&self, team: Option<&str>) -> &PrivacyPolicy {
+        team.and_then(|team| self.settings.teams.get(team)).unwrap_or(&self.settings.default)
+    }
+
+    /// Replace the default policy (`team` None) or one team's
+    pub fn set_policy(&mut self, team: Option<&str>, policy: PrivacyPolicy) -> Result<(), String> {
+        let previous = self.settings.clone();
+        match team.map(str::trim) {
+            Some("") => return Err("Team name is empty".to_string()),
+            Some(team) => { self.settings.teams.insert(team.to_string(), policy); },
+            None => self.settings.default = policy,
+        }
+        if let Err(e) = self.save() {
+            self.settings = previous;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Drop a team's override so it gets the default again; false if it had none
+    pub fn remove_team_policy(&mut self, team: &str) -> Result<bool, String> {
+        let Some(removed) = self.settings.teams.remove(team) else {
+            return Ok(false);
+        };
+        if let Err(e) = self.save() {
+            self.settings.teams.insert(team.to_string(), removed);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    // Write to a temporary file and rename, so a crash never leaves half a file
+    fn save(&self) -> Result<(), String> {
+        if let Some(reason) = &self.read_only {
+            return Err(format!("Privacy settings are read-only: {}", reason));
+        }
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+        let settings = PrivacySettings { version: SETTINGS_VERSION, ..self.settings.clone() };
+        let json = serde_json::to_string_pretty(&settings)
+            .map_err(|e| format!("Failed to serialize privacy settings: {}", e))?;
+
+        let path = self.dir.join(SETTINGS_FILE);
+        let temp_path = self.dir.join(format!("{}.tmp", SETTINGS_FILE));
+        fs::write(&temp_path, json)
+            .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+        fs::rename(&temp_path, &path)
+            .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,155 @@
+// Clipboard: copying captures, OCR text and answers out, and reading whatever the
+// user copied elsewhere as the input of an analysis. The system clipboard is
+// opened on first use, so a session without one (headless, no X server) only
+// fails the clipboard commands instead of startup.
+use crate::overlay::capture_service;
+use crate::overlay::CaptureBounds;
+use image::RgbaImage;
+use std::borrow::Cow;
+
+pub trait ClipboardBackend: Send {
+    fn set_text(&mut self, text: &str) -> Result<(), String>;
+    fn set_image(&mut self, image: &RgbaImage) -> Result<(), String>;
+    /// None when the clipboard holds no image
+    fn image(&mut self) -> Result<Option<RgbaImage>, String>;
+    /// None when the clipboard holds no text
+    fn text(&mut self) -> Result<Option<String>, String>;
+}
+
+/// The desktop clipboard. On X11/Wayland the copied data is served by a thread
+/// that lives as long as this does, so it is kept for the app's lifetime.
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+impl SystemClipboard {
+    pub fn open() -> Result<Self, String> {
+        arboard::Clipboard::new()
+            .map(|clipboard| Self { clipboard })
+            .map_err(|e| format!("Clipboard not available: {}", e))
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), String> {
+        self.clipboard.set_text(text).map_err(|e| format!("Failed to copy text: {}", e))
+    }
+
+    // Offered as image/png (and the platform's native bitmap formats)
+    fn set_image(&mut self, image: &RgbaImage) -> Result<(), String> {
+        self.clipboard.set_image(arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: Cow::Borrowed(image.as_raw()),
+        }).map_err(|e| format!("Failed to copy image: {}", e))
+    }
+
+    fn image(&mut self) -> Result<Option<RgbaImage>, String> {
+        match self.clipboard.get_image() {
+            Ok(data) => RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+                .map(Some)
+                .ok_or_else(|| format!("Clipboard image is not {}x{} RGBA", data.width, data.height)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(format!("Failed to read clipboard image: {}", e)),
+        }
+    }
+
+    fn text(&mut self) -> Result<Option<String>, String> {
+        match self.clipboard.get_text() {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(format!("Failed to read clipboard text: {}", e)),
+        }
+    }
+}
+
+/// In-memory clipboard for tests; like a real one it holds one thing at a time
+#[derive(Default)]
+pub struct MemoryClipboard {
+    pub image: Option<RgbaImage>,
+    pub text: Option<String>,
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), String> {
+        self.image = None;
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+
+    fn set_image(&mut self, image: &RgbaImage) -> Result<(), String> {
+        self.text = None;
+        self.image = Some(image.clone());
+        Ok(())
+    }
+
+    fn image(&mut self) -> Result<Option<RgbaImage>, String> {
+        Ok(self.image.clone())
+    }
+
+    fn text(&mut self) -> Result<Option<String>, String> {
+        Ok(self.text.clone())
+    }
+}
+
+/// What the clipboard held
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClipboardContent {
+    Image(RgbaImage),
+    Text(String),
+}
+
+pub struct ClipboardService {
+    backend: Option<Box<dyn ClipboardBackend>>, // None until the system clipboard is first used
+}
+
+impl ClipboardService {
+    pub fn system() -> Self {
+        Self { backend: None }
+    }
+
+    pub fn with_backend(backend: Box<dyn ClipboardBackend>) -> Self {
+        Self { backend: Some(backend) }
+    }
+
+    pub fn copy_text(&mut self, text: &str) -> Result<(), String> {
+        if text.trim().is_empty() {
+            return Err("Nothing to copy".to_string());
+        }
+        self.backend()?.set_text(text)?;
+        println!("📋 Copied {} characters to the clipboard", text.chars().count());
+        Ok(())
+    }
+
+    pub fn copy_image(&mut self, image: &RgbaImage) -> Result<(), String> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err("Nothing to copy".to_string());
+        }
+        self.backend()?.set_image(image)?;
+        println!("📋 Copied {}x{} image to the clipboard", image.width(), image.height());
+        Ok(())
+    }
+
+    /// The clipboard's image, else its text; None when it holds neither (or only
+    /// whitespace). Images too small to be a deliberate capture are refused like
+    /// screen selections are.
+    pub fn read(&mut self) -> Result<Option<ClipboardContent>, String> {
+        let backend = self.backend()?;
+        if let Some(image) = backend.image()? {
+            capture_service::check_size(&CaptureBounds { x: 0, y: 0, width: image.width(), height: image.height() })
+                .map_err(|e| e.to_string())?;
+            return Ok(Some(ClipboardContent::Image(image)));
+        }
+        Ok(backend.text()?
+            .filter(|text| !text.trim().is_empty())
+            .map(ClipboardContent::Text))
+    }
+
+    fn backend(&mut self) -> Result<&mut dyn ClipboardBackend, String> {
+        let backend = match self.backend.take() {
+            Some(backend) => backend,
+            None => Box::new(SystemClipboard::open()?),
+        };
+        Ok(self.backend.insert(backend).as_mut())
+    }
+}
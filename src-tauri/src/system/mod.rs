@@ -1,3 +1,5 @@
+pub mod clipboard;
 pub mod permission_cache;
 
-pub use permission_cache::{Permission, PermissionCache}; 
\ No newline at end of file
+pub use clipboard::{ClipboardContent, ClipboardService};
+pub use permission_cache::{Permission, PermissionCache};
@@ -58,12 +58,7 @@ impl PermissionCache {
     
     fn check_permission_native_sync(&self, perm: Permission) -> Result<bool, String> {
         match perm {
-            Permission::ScreenRecording => {
-                // For macOS screen recording, we rely on system prompts
-                // In a real implementation, you'd use macOS APIs to check this
-                // For now, return true since macOS will prompt if needed
-                Ok(true)
-            },
+            Permission::ScreenRecording => Ok(screen_recording_granted()),
             Permission::Accessibility => {
                 // For macOS accessibility, we rely on system prompts
                 // In a real implementation, you'd use macOS APIs to check this
@@ -107,6 +102,27 @@ impl PermissionCache {
     }
 }
 
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+}
+
+/// Whether the app may read other apps' pixels. macOS answers captures without
+/// Screen Recording access with the wallpaper only, so check before capturing;
+/// the other platforms have no such switch.
+pub fn screen_recording_granted() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        // Only reads the TCC state, never prompts
+        unsafe { CGPreflightScreenCaptureAccess() }
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        true
+    }
+}
+
 impl Default for PermissionCache {
     fn default() -> Self {
         Self::new()
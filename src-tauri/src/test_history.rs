@@ -0,0 +1,344 @@
+// Capture history verification, run against a scratch directory
+use crate::annotate::{Annotation, AnnotationDocument, Color, Point, Rect, RedactStyle};
+use crate::history::{HistoryStore, HistoryUpdate, NewHistoryEntry, RetentionPolicy};
+use crate::overlay::CaptureBounds;
+use base64::Engine;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PLAIN_TEXT_PNG: &[u8] = include_bytes!("../fixtures/content/plain_text.png");
+const CODE_PNG: &[u8] = include_bytes!("../fixtures/content/code.png");
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+// Each test gets its own empty history directory
+type HistoryTest = fn(&Path) -> Result<String, String>;
+
+pub fn run_all_tests() -> Result<(), String> {
+    println!("\n🚀 STARTING HISTORY VERIFICATION TESTS");
+    println!("=====================================");
+
+    let dir = std::env::temp_dir().join(format!("framesense-history-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let tests: [(&str, HistoryTest); 5] = [
+        ("Store and Reopen", run_persistence_test),
+        ("Full-Text Search", run_search_test),
+        ("Retention Limits", run_retention_test),
+        ("Delete and Recovery", run_delete_test),
+        ("Annotations Sidecar", run_annotations_test),
+    ];
+    let mut result = Ok(());
+    for (number, (name, test)) in tests.iter().enumerate() {
+        println!("\n📋 Test {}: {}", number + 1, name);
+        let test_dir = dir.join(format!("test-{}", number + 1));
+        match test(&test_dir) {
+            Ok(msg) => println!("✅ {}", msg),
+            Err(err) => {
+                println!("❌ {}", err);
+                result = Err(format!("{}: {}", name, err));
+                break;
+            }
+        }
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+    if result.is_ok() {
+        println!("\n🎉 All history tests passed!");
+    }
+    println!("=====================================");
+    result
+}
+
+fn run_persistence_test(dir: &Path) -> Result<String, String> {
+    let base = 1_700_000_000_000;
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    store.set_retention(unlimited())?;
+
+    let first = store.add_at(capture(PLAIN_TEXT_PNG, "Quarterly invoice total", &["Work"]), base)?;
+    let second = store.add_at(NewHistoryEntry {
+        bounds: Some(CaptureBounds { x: -1920, y: 40, width: 480, height: 220 }),
+        ai_answer: Some("The loop never terminates".to_string()),
+        ..capture(CODE_PNG, "fn main() { loop {} }", &["#code", "work", "Code"])
+    }, base + HOUR_MS)?;
+    // Same millisecond still gets its own entry
+    let third = store.add_at(capture(PLAIN_TEXT_PNG, "", &[]), base + HOUR_MS)?;
+
+    if first.id == second.id || second.id == third.id {
+        return Err(format!("Duplicate ids {} / {} / {}", first.id, second.id, third.id));
+    }
+    if second.tags != vec!["code", "work"] || third.ocr_text.is_some() {
+        return Err(format!("Tags {:?} / OCR text {:?} not normalized", second.tags, third.ocr_text));
+    }
+    let (width, height) = image::load_from_memory(CODE_PNG).map_err(|e| e.to_string())?.to_rgba8().dimensions();
+    if (second.width, second.height) != (width, height) || second.image_bytes != CODE_PNG.len() as u64 {
+        return Err(format!("Entry size {}x{} ({} bytes) does not match the capture", second.width, second.height, second.image_bytes));
+    }
+
+    // A delivered selection is stored as encoded, its OCR text and answer follow later
+    let delivered = store.add_png(PLAIN_TEXT_PNG, 480, 200, Some(CaptureBounds { x: 10, y: 20, width: 480, height: 200 }))?;
+    store.update(&delivered.id, HistoryUpdate {
+        ocr_text: Some("Delivered selection".to_string()),
+        ai_answer: Some("It says hello".to_string()),
+        tags: None,
+    })?;
+    if store.add_png(b"not a png", 1, 1, None).is_ok() || store.len() != 4 {
+        return Err("A non-PNG capture was stored".to_string());
+    }
+
+    // Everything comes back from disk, newest first
+    let reopened = HistoryStore::open(dir.to_path_buf());
+    let ids: Vec<String> = reopened.list(0, 10).into_iter().map(|entry| entry.id).collect();
+    if reopened.len() != 4 || ids[0] != delivered.id || ids[3] != first.id {
+        return Err(format!("Reopened history lists {:?}", ids));
+    }
+    if reopened.list(1, 1).len() != 1 || !reopened.list(4, 10).is_empty() {
+        return Err("Paging returned the wrong number of entries".to_string());
+    }
+
+    let opened = reopened.open_entry(&second.id)?;
+    let png = base64::engine::general_purpose::STANDARD
+        .decode(opened.image_data.trim_start_matches("data:image/png;base64,"))
+        .map_err(|e| e.to_string())?;
+    if png != CODE_PNG || opened.entry.bounds != second.bounds {
+        return Err("Opened entry does not match what was saved".to_string());
+    }
+    if reopened.search("terminates", 10).first().map(|hit| &hit.entry.id) != Some(&second.id)
+        || reopened.search("says hello", 10).first().map(|hit| &hit.entry.id) != Some(&delivered.id) {
+        return Err("Search index was not rebuilt on open".to_string());
+    }
+
+    Ok(format!("4 entries saved and reopened from {}", reopened.dir().display()))
+}
+
+fn run_search_test(dir: &Path) -> Result<String, String> {
+    let base = 1_700_000_000_000;
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    store.set_retention(unlimited())?;
+
+    let invoice = store.add_at(capture(PLAIN_TEXT_PNG, "Invoice 4471 from ACME, total due: 1,250 EUR", &["finance"]), base)?;
+    let error = store.add_at(capture(CODE_PNG, "error[E0382]: borrow of moved value: `config`\nerror: aborting", &["code"]), base + HOUR_MS)?;
+    let older_error = store.add_at(capture(CODE_PNG, "warning: unused variable, then an error", &[]), base - HOUR_MS)?;
+    let newer_error = store.add_at(capture(CODE_PNG, "warning: unused variable, then an error", &[]), base + 2 * HOUR_MS)?;
+
+    // Case-insensitive, every word must match
+    let ids = |query: &str| hit_ids(&store, query);
+    if ids("ACME invoice") != vec![invoice.id.clone()] || !ids("acme borrow").is_empty() {
+        return Err(format!("AND search returned {:?} / {:?}", ids("ACME invoice"), ids("acme borrow")));
+    }
+    // The last word matches as a prefix while typing
+    if ids("borrow conf") != vec![error.id.clone()] || ids("invo") != vec![invoice.id.clone()] {
+        return Err(format!("Prefix search returned {:?} / {:?}", ids("borrow conf"), ids("invo")));
+    }
+    // Entries mentioning a word more often rank first, then newer ones
+    let ranked = ids("error");
+    if ranked != vec![error.id.clone(), newer_error.id.clone(), older_error.id.clone()] {
+        return Err(format!("Ranking for 'error' was {:?}", ranked));
+    }
+    if ids("error tag:code") != vec![error.id.clone()] || ids("tag:finance") != vec![invoice.id.clone()] {
+        return Err(format!("Tag filters returned {:?} / {:?}", ids("error tag:code"), ids("tag:finance")));
+    }
+    if !ids("").is_empty() || !ids("   ").is_empty() || store.search("error", 1).len() != 1 {
+        return Err("Empty query or limit not respected".to_string());
+    }
+
+    let snippet = &store.search("due", 1)[0].snippet;
+    if !snippet.contains("total due") {
+        return Err(format!("Snippet '{}' misses the match", snippet));
+    }
+
+    // Answers and tags added later are searchable, replaced text no longer is
+    store.update(&invoice.id, HistoryUpdate {
+        ocr_text: Some("Receipt for lunch".to_string()),
+        ai_answer: Some("This receipt totals 18 euros".to_string()),
+        tags: Some(vec!["Personal".to_string()]),
+    })?;
+    let ids = |query: &str| hit_ids(&store, query);
+    if ids("18 euros") != vec![invoice.id.clone()] || !ids("acme").is_empty() || ids("tag:personal") != vec![invoice.id.clone()] {
+        return Err("Updated entry was not re-indexed".to_string());
+    }
+
+    Ok("AND, prefix, ranking, tag filters and re-indexing work".to_string())
+}
+
+fn run_retention_test(dir: &Path) -> Result<String, String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_millis() as u64;
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    store.set_retention(unlimited())?;
+
+    // One capture a day, the oldest five and a half days ago
+    let mut ids = Vec::new();
+    for day in 0..6u64 {
+        let created_at = now - (5 - day) * DAY_MS - DAY_MS / 2;
+        ids.push(store.add_at(capture(PLAIN_TEXT_PNG, &format!("note from day {}", day), &[]), created_at)?.id);
+    }
+
+    // Count and size limits drop the oldest entries with their images
+    let removed = store.set_retention(RetentionPolicy { max_entries: Some(5), ..unlimited() })?;
+    if removed != 1 || image_path(dir, &ids[0]).exists() || !image_path(dir, &ids[5]).exists() {
+        return Err(format!("Count limit removed {} entries", removed));
+    }
+    let max_bytes = 4 * PLAIN_TEXT_PNG.len() as u64;
+    let removed = store.set_retention(RetentionPolicy { max_total_bytes: Some(max_bytes), ..unlimited() })?;
+    if removed != 1 || store.stats().total_bytes != max_bytes {
+        return Err(format!("Size limit removed {} entries, {} bytes left", removed, store.stats().total_bytes));
+    }
+
+    // Age limit now, and again a day later
+    let removed = store.set_retention(RetentionPolicy { max_age_days: Some(2), ..unlimited() })?;
+    let left: Vec<String> = store.list(0, 10).into_iter().map(|entry| entry.id).collect();
+    if removed != 2 || left != vec![ids[5].clone(), ids[4].clone()] {
+        return Err(format!("Age limit removed {} entries, left {:?}", removed, left));
+    }
+    if store.apply_retention_at(now + DAY_MS)? != 1 || store.len() != 1 {
+        return Err(format!("{} entries left a day later", store.len()));
+    }
+
+    // Limits persist and apply to new captures too
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    if store.retention().max_age_days != Some(2) || store.len() != 1 {
+        return Err(format!("Retention {:?} was not persisted", store.retention()));
+    }
+    store.add_at(capture(PLAIN_TEXT_PNG, "stale", &[]), now - 3 * DAY_MS)?;
+    if store.len() != 1 || !store.search("stale", 10).is_empty() {
+        return Err(format!("{} entries after adding an expired capture", store.len()));
+    }
+
+    Ok("Age, count and size limits drop the oldest entries and their images".to_string())
+}
+
+fn run_delete_test(dir: &Path) -> Result<String, String> {
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    let kept = store.add(capture(PLAIN_TEXT_PNG, "keep me", &[]))?;
+    let doomed = store.add(capture(CODE_PNG, "delete me", &[]))?;
+
+    store.delete(&doomed.id)?;
+    if image_path(dir, &doomed.id).exists() || store.open_entry(&doomed.id).is_ok() || !store.search("delete", 10).is_empty() {
+        return Err("Deleted entry is still reachable".to_string());
+    }
+    if store.delete(&doomed.id).is_ok() {
+        return Err("Deleting a missing entry succeeded".to_string());
+    }
+    if store.add(capture(b"not an image", "broken", &[])).is_ok() {
+        return Err("Invalid image data was accepted".to_string());
+    }
+
+    // A corrupt index is set aside and the entries are rebuilt from the images
+    let annotated = store.add(capture(CODE_PNG, "annotated", &[]))?;
+    store.save_annotations(&annotated.id, &AnnotationDocument {
+        annotations: vec![Annotation::Step { center: Point { x: 20.0, y: 20.0 }, number: 1, color: Color::RED, radius: 10.0 }],
+        ..AnnotationDocument::new(annotated.width, annotated.height)
+    })?;
+    fs::write(dir.join("index.json"), "{ not json").map_err(|e| e.to_string())?;
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    if !dir.join("index.json.bak").exists() || !image_path(dir, &kept.id).exists() || !image_path(dir, &annotated.id).exists() {
+        return Err("Recovering from a corrupt index lost images".to_string());
+    }
+    let recovered = store.get(&kept.id).cloned().ok_or("Entry was not rebuilt from its image")?;
+    if store.len() != 2 || recovered.created_at != kept.created_at || (recovered.width, recovered.height) != (kept.width, kept.height)
+        || store.annotations(&annotated.id)?.is_none() {
+        return Err(format!("Rebuilt {} entries, {:?}", store.len(), recovered));
+    }
+    // The rebuilt index is saved
+    if HistoryStore::open(dir.to_path_buf()).len() != 2 {
+        return Err("The rebuilt index was not saved".to_string());
+    }
+    let after = store.add(capture(PLAIN_TEXT_PNG, "after recovery", &[]))?;
+    if store.clear()? != 3 || store.len() != 0 || image_path(dir, &after.id).exists() {
+        return Err("Clearing the history left entries".to_string());
+    }
+
+    // An index that can't be read is never replaced: the history opens read-only
+    let entry = store.add(capture(PLAIN_TEXT_PNG, "still here", &[]))?;
+    let index = fs::read(dir.join("index.json")).map_err(|e| e.to_string())?;
+    fs::remove_file(dir.join("index.json")).map_err(|e| e.to_string())?;
+    fs::create_dir(dir.join("index.json")).map_err(|e| e.to_string())?; // Reading a directory fails
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    let refused = store.add(capture(CODE_PNG, "new", &[])).is_err() && store.delete(&entry.id).is_err() && store.clear().is_err();
+    if store.is_writable() || !refused || store.len() != 1 || !image_path(dir, &entry.id).exists() {
+        return Err("A history with an unreadable index was changed".to_string());
+    }
+    fs::remove_dir(dir.join("index.json")).map_err(|e| e.to_string())?;
+    fs::write(dir.join("index.json"), index).map_err(|e| e.to_string())?;
+
+    Ok("Deletes remove images and index entries; lost indexes are rebuilt, unreadable ones left alone".to_string())
+}
+
+fn run_annotations_test(dir: &Path) -> Result<String, String> {
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    let entry = store.add(capture(CODE_PNG, "api_key = secret", &[]))?;
+    let original = fs::read(image_path(dir, &entry.id)).map_err(|e| e.to_string())?;
+
+    let document = AnnotationDocument {
+        annotations: vec![
+            Annotation::Redact { rect: Rect { x: 10.0, y: 10.0, width: 100.0, height: 20.0 }, style: RedactStyle::Fill { color: Color::BLACK } },
+            Annotation::Step { center: Point { x: 200.0, y: 100.0 }, number: 1, color: Color::RED, radius: 14.0 },
+        ],
+        ..AnnotationDocument::new(entry.width, entry.height)
+    };
+    let saved = store.save_annotations(&entry.id, &document)?;
+    if saved.annotations_file.as_deref() != Some(format!("images/{}.json", entry.id).as_str()) {
+        return Err(format!("Annotations saved as {:?}", saved.annotations_file));
+    }
+
+    // Kept next to the untouched image and found again after reopening
+    let mut store = HistoryStore::open(dir.to_path_buf());
+    if fs::read(image_path(dir, &entry.id)).map_err(|e| e.to_string())? != original {
+        return Err("Saving annotations changed the stored image".to_string());
+    }
+    if store.open_entry(&entry.id)?.annotations.as_ref() != Some(&document) {
+        return Err("Annotations were not reopened".to_string());
+    }
+    let exported = store.render_annotated(&entry.id)?;
+    if exported.get_pixel(50, 20).0 != [0, 0, 0, 255] {
+        return Err("Exported image is missing the redaction".to_string());
+    }
+    if store.save_annotations("capture-0", &document).is_ok() {
+        return Err("Annotations were saved for a missing entry".to_string());
+    }
+
+    // Clearing the annotations or deleting the entry removes the file
+    let annotations_path = dir.join("images").join(format!("{}.json", entry.id));
+    store.save_annotations(&entry.id, &AnnotationDocument::new(entry.width, entry.height))?;
+    if annotations_path.exists() || store.annotations(&entry.id)?.is_some() {
+        return Err("Empty annotations left a file behind".to_string());
+    }
+    store.save_annotations(&entry.id, &document)?;
+    store.delete(&entry.id)?;
+    if annotations_path.exists() {
+        return Err("Deleting the entry left its annotations".to_string());
+    }
+
+    Ok("Annotations are stored beside the image, reopened, exported and cleaned up".to_string())
+}
+
+fn capture(png: &[u8], ocr_text: &str, tags: &[&str]) -> NewHistoryEntry {
+    NewHistoryEntry {
+        image_data: format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png)),
+        bounds: None,
+        ocr_text: Some(ocr_text.to_string()),
+        ai_answer: None,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    }
+}
+
+fn hit_ids(store: &HistoryStore, query: &str) -> Vec<String> {
+    store.search(query, 10).into_iter().map(|hit| hit.entry.id).collect()
+}
+
+fn unlimited() -> RetentionPolicy {
+    RetentionPolicy { max_entries: None, max_age_days: None, max_total_bytes: None }
+}
+
+fn image_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join("images").join(format!("{}.png", id))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn history_store() {
+        super::run_all_tests().unwrap();
+    }
+}
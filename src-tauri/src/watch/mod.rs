@@ -0,0 +1,5 @@
+pub mod store;
+pub mod watcher;
+
+pub use store::{NewRegionWatch, RegionWatch, WatchStore};
+pub use watcher::{RegionWatches, TextReader, WatchEvent, WatchSink, WatchStatus};
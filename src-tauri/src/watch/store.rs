@@ -0,0 +1,200 @@
+// Pinned regions on disk
+// <dir>/watches.json holds every watch so they come back after a restart; what a
+// watch last read is only kept in memory.
+use crate::overlay::capture_service;
+use crate::overlay::CaptureBounds;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WATCHES_FILE: &str = "watches.json";
+const WATCHES_VERSION: u32 = 1;
+/// Recognition takes a while, so polling faster than this only queues up work
+pub const MIN_INTERVAL_SECS: u32 = 2;
+pub const MAX_INTERVAL_SECS: u32 = 24 * 60 * 60;
+pub const DEFAULT_INTERVAL_SECS: u32 = 10;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RegionWatch {
+    pub id: String,
+    pub name: String,
+    pub bounds: CaptureBounds, // Desktop coordinates
+    pub interval_secs: u32,
+    // Regex; None alerts on any text change, Some only when the text starts matching
+    pub pattern: Option<String>,
+    pub notify: bool, // Desktop notification as well as the region-watch event
+    pub created_at: u64, // Unix milliseconds
+}
+
+/// What a new watch is made of; unset fields get defaults
+#[derive(Clone, Deserialize, Debug)]
+pub struct NewRegionWatch {
+    pub name: Option<String>,
+    pub bounds: CaptureBounds,
+    pub interval_secs: Option<u32>,
+    pub pattern: Option<String>,
+    pub notify: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct WatchesFile {
+    version: u32,
+    watches: Vec<RegionWatch>,
+}
+
+pub struct WatchStore {
+    dir: PathBuf,
+    watches: Vec<RegionWatch>, // Oldest first
+    // Why changes are refused, when watches.json exists but could not be read
+    read_only: Option<String>,
+}
+
+impl WatchStore {
+    /// Open (or start) the watches kept in `dir`. A corrupt file is set aside as
+    /// watches.json.bak rather than overwritten. A file that can't be read
+    /// (permissions, I/O errors) leaves the watches read-only, so it is never replaced.
+    pub fn open(dir: PathBuf) -> Self {
+        let path = dir.join(WATCHES_FILE);
+        let mut read_only = None;
+        let file = match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str::<WatchesFile>(&json) {
+                Ok(file) => file,
+                Err(e) => {
+                    println!("⚠️ Region watch file is corrupt ({}), starting a new one", e);
+                    let _ = fs::rename(&path, dir.join(format!("{}.bak", WATCHES_FILE)));
+                    WatchesFile::default()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => WatchesFile::default(),
+            Err(e) => {
+                println!("⚠️ Cannot read {} ({}), region watches are read-only", path.display(), e);
+                read_only = Some(format!("{} could not be read: {}", path.display(), e));
+                WatchesFile::default()
+            },
+        };
+
+        // Hand-edited watches that don't validate are dropped rather than polled
+        let watches: Vec<RegionWatch> = file.watches.into_iter()
+            .filter(|watch| match validate(watch) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("⚠️ Skipping region watch {}: {}", watch.id, e);
+                    false
+                }
+            })
+            .collect();
+        println!("📌 Region watches: {} in {}", watches.len(), dir.display());
+        Self { dir, watches, read_only }
+    }
+
+    /// Whether changes can be saved (false when watches.json could not be read)
+    pub fn is_writable(&self) -> bool {
+        self.read_only.is_none()
+    }
+
+    pub fn list(&self) -> &[RegionWatch] {
+        &self.watches
+    }
+
+    pub fn get(&self, id: &str) -> Option<&RegionWatch> {
+        self.watches.iter().find(|watch| watch.id == id)
+    }
+
+    /// Validate and store a new watch
+    pub fn add(&mut self, new_watch: NewRegionWatch) -> Result<RegionWatch, String> {
+        let created_at = now_millis();
+        let id = self.unique_id(created_at);
+        let name = new_watch.name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("Region {}x{} at ({}, {})", new_watch.bounds.width, new_watch.bounds.height, new_watch.bounds.x, new_watch.bounds.y));
+        let watch = RegionWatch {
+            id,
+            name,
+            bounds: new_watch.bounds,
+            interval_secs: new_watch.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS),
+            pattern: new_watch.pattern.filter(|pattern| !pattern.trim().is_empty()),
+            notify: new_watch.notify.unwrap_or(true),
+            created_at,
+        };
+        validate(&watch)?;
+
+        self.watches.push(watch.clone());
+        if let Err(e) = self.save() {
+            self.watches.pop();
+            return Err(e);
+        }
+        Ok(watch)
+    }
+
+    /// Forget a watch; false if there was none with that id
+    pub fn remove(&mut self, id: &str) -> Result<bool, String> {
+        let Some(position) = self.watches.iter().position(|watch| watch.id == id) else {
+            return Ok(false);
+        };
+        let removed = self.watches.remove(position);
+        if let Err(e) = self.save() {
+            self.watches.insert(position, removed);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    fn unique_id(&self, created_at: u64) -> String {
+        let base = format!("watch-{}", created_at);
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while self.get(&id).is_some() {
+            id = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        id
+    }
+
+    // Write to a temporary file and rename, so a crash never leaves half a file
+    fn save(&self) -> Result<(), String> {
+        if let Some(reason) = &self.read_only {
+            return Err(format!("Region watches are read-only: {}", reason));
+        }
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create watch directory: {}", e))?;
+        let file = WatchesFile {
+            version: WATCHES_VERSION,
+            watches: self.watches.clone(),
+        };
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Failed to serialize region watches: {}", e))?;
+
+        let path = self.dir.join(WATCHES_FILE);
+        let temp_path = self.dir.join(format!("{}.tmp", WATCHES_FILE));
+        fs::write(&temp_path, json)
+            .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+        fs::rename(&temp_path, &path)
+            .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+    }
+}
+
+pub fn validate(watch: &RegionWatch) -> Result<(), String> {
+    capture_service::check_size(&watch.bounds)?;
+    if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&watch.interval_secs) {
+        return Err(format!("Watch interval must be {}-{} seconds, got {}", MIN_INTERVAL_SECS, MAX_INTERVAL_SECS, watch.interval_secs));
+    }
+    if let Some(pattern) = &watch.pattern {
+        compile_pattern(pattern)?;
+    }
+    Ok(())
+}
+
+/// Patterns match case-insensitively, since OCR capitalization is unreliable
+pub fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    regex::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid watch pattern '{}': {}", pattern, e))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
@@ -0,0 +1,241 @@
+// Polling pinned regions: each watch captures its region on its own thread, reads
+// the text when the pixels changed, and reports changes and pattern matches through
+// a sink. Text recognition is passed in, so this knows nothing about Tesseract or
+// Tauri; main.rs wires in the OCR service and turns events into notifications.
+use crate::overlay::capture_scheduler::changed_percent;
+use crate::overlay::{CaptureSource, CaptureService};
+use image::RgbaImage;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::store::{self, NewRegionWatch, RegionWatch, WatchStore};
+
+/// Text in a captured region (the OCR service in the app)
+pub type TextReader = Arc<dyn Fn(&RgbaImage) -> Result<String, String> + Send + Sync>;
+/// Receives every event with the watch it came from
+pub type WatchSink = Arc<dyn Fn(&RegionWatch, WatchEvent) + Send + Sync>;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchEvent {
+    /// First reading after the watch started; a baseline, never an alert
+    Started { id: String, name: String, text: String },
+    Changed {
+        id: String,
+        name: String,
+        previous: String,
+        text: String,
+        matched: Option<String>, // What the pattern matched in the new text
+        alert: bool,             // Worth a notification: any change, or the pattern started matching
+    },
+    /// Reported once per distinct error, not on every poll
+    Failed { id: String, name: String, error: String },
+}
+
+/// What a running watch last saw
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct WatchReading {
+    pub text: Option<String>,
+    pub checked_at: Option<u64>, // Unix milliseconds
+    pub changed_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchStatus {
+    #[serde(flatten)]
+    pub watch: RegionWatch,
+    pub running: bool,
+    pub reading: WatchReading,
+}
+
+struct Worker {
+    // Dropping the sender stops the watch
+    _stop: Sender<()>,
+    reading: Arc<Mutex<WatchReading>>,
+    worker: JoinHandle<()>,
+}
+
+/// The saved watches and the threads polling them
+pub struct RegionWatches {
+    store: WatchStore,
+    source: Arc<dyn CaptureSource>,
+    reader: TextReader,
+    sink: WatchSink,
+    workers: HashMap<String, Worker>,
+}
+
+impl RegionWatches {
+    /// Nothing is polled until `start_all` or `add`
+    pub fn new(store: WatchStore, source: Arc<dyn CaptureSource>, reader: TextReader, sink: WatchSink) -> Self {
+        Self { store, source, reader, sink, workers: HashMap::new() }
+    }
+
+    /// Start every saved watch that is not running yet, returning how many started
+    pub fn start_all(&mut self) -> usize {
+        let watches: Vec<RegionWatch> = self.store.list().iter()
+            .filter(|watch| !self.workers.contains_key(&watch.id))
+            .cloned()
+            .collect();
+        let count = watches.len();
+        for watch in watches {
+            self.start(watch);
+        }
+        count
+    }
+
+    /// Save a new watch and start polling it
+    pub fn add(&mut self, new_watch: NewRegionWatch) -> Result<RegionWatch, String> {
+        let watch = self.store.add(new_watch)?;
+        self.start(watch.clone());
+        Ok(watch)
+    }
+
+    /// Stop and forget a watch; false if there was none with that id
+    pub fn remove(&mut self, id: &str) -> Result<bool, String> {
+        let removed = self.store.remove(id)?;
+        if self.workers.remove(id).is_some() {
+            println!("📌 Region watch {} stopped", id);
+        }
+        Ok(removed)
+    }
+
+    /// Every saved watch with what it last read, oldest first
+    pub fn list(&self) -> Vec<WatchStatus> {
+        self.store.list().iter().map(|watch| {
+            let worker = self.workers.get(&watch.id);
+            WatchStatus {
+                watch: watch.clone(),
+                running: worker.is_some_and(|worker| !worker.worker.is_finished()),
+                reading: worker.map(|worker| worker.reading.lock().unwrap().clone()).unwrap_or_default(),
+            }
+        }).collect()
+    }
+
+    /// Stop polling without forgetting anything, returning how many were running
+    pub fn stop_all(&mut self) -> usize {
+        let count = self.workers.len();
+        self.workers.clear();
+        count
+    }
+
+    fn start(&mut self, watch: RegionWatch) {
+        let (stop, stopped) = mpsc::channel();
+        let reading = Arc::new(Mutex::new(WatchReading::default()));
+        let runner = WatchRunner {
+            pattern: watch.pattern.as_deref().and_then(|pattern| store::compile_pattern(pattern).ok()),
+            service: CaptureService::with_source(self.source.clone()),
+            reader: self.reader.clone(),
+            sink: self.sink.clone(),
+            reading: reading.clone(),
+            stopped,
+            watch: watch.clone(),
+        };
+        let worker = std::thread::spawn(move || runner.run());
+
+        println!("📌 Region watch {} ('{}') polling every {}s", watch.id, watch.name, watch.interval_secs);
+        self.workers.insert(watch.id, Worker { _stop: stop, reading, worker });
+    }
+}
+
+struct WatchRunner {
+    watch: RegionWatch,
+    pattern: Option<regex::Regex>,
+    service: CaptureService,
+    reader: TextReader,
+    sink: WatchSink,
+    reading: Arc<Mutex<WatchReading>>,
+    stopped: Receiver<()>,
+}
+
+impl WatchRunner {
+    fn run(&self) {
+        let interval = Duration::from_secs(self.watch.interval_secs as u64);
+        let mut last_image: Option<RgbaImage> = None;
+        let mut last_text: Option<String> = None;
+        let mut last_error: Option<String> = None;
+        loop {
+            match self.poll(last_image.as_ref()) {
+                // Same pixels as last time: same text, no need to read it again
+                Ok(None) => {},
+                Ok(Some((image, text))) => {
+                    last_image = Some(image);
+                    last_error = None;
+                    self.report_text(last_text.as_deref(), &text);
+                    last_text = Some(text);
+                },
+                Err(error) => {
+                    if last_error.as_ref() != Some(&error) {
+                        println!("❌ Region watch {} failed: {}", self.watch.id, error);
+                        (self.sink)(&self.watch, WatchEvent::Failed { id: self.watch.id.clone(), name: self.watch.name.clone(), error: error.clone() });
+                    }
+                    last_error = Some(error);
+                },
+            }
+            {
+                let mut reading = self.reading.lock().unwrap();
+                reading.checked_at = Some(now_millis());
+                reading.error = last_error.clone();
+            }
+
+            if !matches!(self.stopped.recv_timeout(interval), Err(RecvTimeoutError::Timeout)) {
+                return;
+            }
+        }
+    }
+
+    /// The region's pixels and text, or None when the pixels are unchanged
+    fn poll(&self, last_image: Option<&RgbaImage>) -> Result<Option<(RgbaImage, String)>, String> {
+        let (_, image) = self.service.capture_pixels(&self.watch.bounds)?;
+        if last_image.is_some_and(|last| changed_percent(last, &image) == 0.0) {
+            return Ok(None);
+        }
+        let text = normalize_text(&(self.reader)(&image)?);
+        Ok(Some((image, text)))
+    }
+
+    fn report_text(&self, previous: Option<&str>, text: &str) {
+        let id = self.watch.id.clone();
+        let name = self.watch.name.clone();
+        let event = match previous {
+            None => WatchEvent::Started { id, name, text: text.to_string() },
+            Some(previous) if previous == text => return,
+            Some(previous) => {
+                let matched = self.pattern.as_ref()
+                    .and_then(|pattern| pattern.find(text))
+                    .map(|found| found.as_str().to_string());
+                // With a pattern, only the change into a matching state is an alert
+                let alert = match &self.pattern {
+                    None => true,
+                    Some(pattern) => matched.is_some() && !pattern.is_match(previous),
+                };
+                WatchEvent::Changed { id, name, previous: previous.to_string(), text: text.to_string(), matched, alert }
+            },
+        };
+
+        let mut reading = self.reading.lock().unwrap();
+        if reading.text.is_some() {
+            reading.changed_at = Some(now_millis());
+        }
+        reading.text = Some(text.to_string());
+        drop(reading);
+        (self.sink)(&self.watch, event);
+    }
+}
+
+/// OCR output with the layout noise removed: lines trimmed, runs of spaces
+/// collapsed and blank lines dropped, so only real text changes count
+pub fn normalize_text(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
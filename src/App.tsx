@@ -1,4 +1,4 @@
-import React, { useEffect, useState } from 'react';
+import React, { useEffect, useRef, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { open } from '@tauri-apps/plugin-shell';
@@ -38,6 +38,18 @@ interface OCRResult {
 	has_text: boolean;
 }
 
+// Privacy filter output (matches Rust FilteredUpload)
+interface FilteredUpload {
+	image_data: string | null; // null when the image could not be scanned and was withheld
+	text: string | null;
+	report: {
+		redactions: { kind: string; source: 'image' | 'text'; preview: string }[];
+		image_scanned: boolean;
+		image_withheld: boolean;
+		scan_error: string | null;
+	};
+}
+
 
 
 function App() {
@@ -50,6 +62,11 @@ function App() {
 	
 	// 🖼️ STEG 2: Separate state for AI image context (independent from badge)
 	const [selectedImageForAI, setSelectedImageForAI] = useState<string | null>(null);
+	const captureIdRef = useRef<string | null>(null); // Backend capture the badge shows
+	// History entry of that capture, gets its OCR text and answer. The backend saves it in
+	// the background and reports the id with 'history-saved', so this resolves later.
+	const historyIdRef = useRef<Promise<string | null>>(Promise.resolve(null));
+	const pendingHistoryRef = useRef(new Map<string, (historyId: string | null) => void>());
 	
 	// 🔍 OCR Context state for automatic text extraction
 	const [ocrContext, setOcrContext] = useState<OCRResult | null>(null);
@@ -294,17 +311,30 @@ function App() {
 			console.log('🎯 Received selection result from Rust:', event.payload);
 			const result = event.payload;
 			
-			if (result.success && result.type === 'image' && result.imageData) {
+			if (result.success && result.type === 'image' && (result.imageUrl || result.imageData)) {
+				// Captures arrive as a handle; the badge loads the image straight from the backend
+				const imageUrl: string = result.imageUrl ?? result.imageData;
+				if (captureIdRef.current) {
+					invoke('release_capture', { id: captureIdRef.current }).catch(() => {});
+				}
+				captureIdRef.current = result.capture?.id ?? null;
+				const captureId: string | null = captureIdRef.current;
+				historyIdRef.current = result.savingToHistory && captureId
+					? new Promise(resolve => pendingHistoryRef.current.set(captureId, resolve))
+					: Promise.resolve(null);
+				
 				// STEG 1: Behåll screenshot for badge (oförändrad)
-				setScreenshotResult(result.imageData);
+				setScreenshotResult(imageUrl);
 				console.log('✅ Screen selection image loaded for badge!');
 				
-				// STEG 1: Save screenshot for AI context  
-				setSelectedImageForAI(result.imageData);
+				// STEG 1: Save screenshot for AI context (the AI service uploads data URLs)
+				loadImageAsDataUrl(imageUrl)
+					.then(setSelectedImageForAI)
+					.catch((error) => console.log('⚠️ Could not load capture for AI:', error));
 				console.log('✅ Screenshot saved for AI analysis!');
 				
 				// 🔍 NEW: Run automatic OCR in background (SILENT)
-				runAutomaticOCR(result.imageData);
+				runAutomaticOCR(result.capture ? { captureId: result.capture.id } : { imageData: result.imageData }, historyIdRef.current);
 				
 				// STEG 1: Auto-activate ChatBox after screenshot
 				console.log('🔄 Auto-activating ChatBox with image context...');
@@ -315,15 +345,38 @@ function App() {
 				// Show brief success message (remove later)
 				const bounds = result.bounds;
 				console.log(`📸 Selection: ${bounds.width}x${bounds.height} at (${bounds.x}, ${bounds.y}) - ChatBox activated!`);
+			} else if (result.success && result.type === 'text' && result.text) {
+				// Text from the clipboard or a PDF page: no image, the text itself is the context
+				if (captureIdRef.current) {
+					invoke('release_capture', { id: captureIdRef.current }).catch(() => {});
+				}
+				captureIdRef.current = null;
+				historyIdRef.current = Promise.resolve(null);
+				setScreenshotResult(null);
+				setSelectedImageForAI(null);
+				setOcrContext({ text: result.text, confidence: 1, has_text: true });
+				console.log(`📋 Text from ${result.source} loaded (${result.text.length} characters)`);
+
+				if (!chatBoxOpen) {
+					handleAskAI();
+				}
 			} else if (result.type === 'error') {
 				console.error('❌ Selection failed:', result.message);
 				alert(`Selection failed: ${result.message}`);
 			}
 		});
+
+		// Sent after the selection-result of the same capture, once its history entry is written
+		const unlistenHistory = listen('history-saved', (event: any) => {
+			const { captureId, historyId } = event.payload;
+			pendingHistoryRef.current.get(captureId)?.(historyId ?? null);
+			pendingHistoryRef.current.delete(captureId);
+		});
 		
 		return () => {
 			unlistenSave.then(fn => fn());
 			unlistenResult.then(fn => fn());
+			unlistenHistory.then(fn => fn());
 		};
 	}, []);
 
@@ -506,17 +559,44 @@ function App() {
 		// Restore CSS background but keep window size for AI response
 		console.log('✅ ChatBox hidden, keeping window expanded for AI response');
 		
+		// 🛡️ Redact sensitive data in the image and OCR text before anything is uploaded
+		setAiProcessingStage('Checking for sensitive data...');
+		let uploadImage: string | undefined = selectedImageForAI || undefined;
+		let ocrText: string | undefined = ocrContext?.has_text ? ocrContext.text : undefined;
+		let privacyNotice: string | null = null; // Shown above the answer when the image was not scanned
+		if (uploadImage || ocrText) {
+			try {
+				const filtered = await invoke('filter_for_upload', { imageData: uploadImage, text: ocrText }) as FilteredUpload;
+				uploadImage = filtered.image_data ?? undefined;
+				ocrText = filtered.text ?? undefined;
+				if (filtered.report.redactions.length > 0) {
+					console.log('🛡️ Redacted before upload:', filtered.report.redactions.map(r => `${r.kind} ${r.preview}`));
+				}
+				if (filtered.report.image_withheld) {
+					console.log('🛡️ Image withheld, it could not be scanned:', filtered.report.scan_error);
+					privacyNotice = `🛡️ The screenshot was not sent: it could not be scanned for sensitive data (${filtered.report.scan_error}).`;
+				} else if (uploadImage && filtered.report.scan_error) {
+					privacyNotice = `🛡️ The screenshot was sent without a sensitive-data scan (${filtered.report.scan_error}).`;
+				}
+			} catch (error) {
+				// Fail closed: without the filter only the typed message is sent
+				console.error('❌ Privacy filter failed, sending the message only:', error);
+				uploadImage = undefined;
+				ocrText = undefined;
+			}
+		}
+		
 		// 🔍 Enhanced message with OCR context if available
 		setAiProcessingStage('Processing OCR context...');
 		
-		const enhancedMessage = ocrContext?.has_text 
-			? `${message}\n\n[OCR Context - Text found in image: "${ocrContext.text}" (Confidence: ${Math.round(ocrContext.confidence * 100)}%)]`
+		const enhancedMessage = ocrText
+			? `${message}\n\n[OCR Context - Text found in image: "${ocrText}" (Confidence: ${Math.round((ocrContext?.confidence ?? 0) * 100)}%)]`
 			: message;
 		
 		// STEG 4: Create comprehensive AI message with text, image, and OCR data
 		const aiMessage: AIMessage = {
 			text: enhancedMessage,
-			imageData: selectedImageForAI || undefined,
+			imageData: uploadImage,
 			timestamp: Date.now(),
 			bounds: undefined // Future: Add capture bounds if needed
 		};
@@ -538,6 +618,8 @@ function App() {
 		try {
 			const aiResponse = await sendToAI(aiMessage);
 			setAiProcessingStage('Generating response...');
+			historyIdRef.current.then(historyId => historyId && invoke('update_history_entry', { id: historyId, aiAnswer: aiResponse }))
+				.catch((error) => console.log('⚠️ Could not save answer to history:', error));
 			
 			// Calculate window height based on response length
 			const getWindowHeight = (textLength: number) => {
@@ -556,7 +638,8 @@ function App() {
 				return Math.min(baseHeight + contentHeight, maxContentHeight);
 			};
 			
-			const windowHeight = getWindowHeight(aiResponse.length);
+			const content = privacyNotice ? `${privacyNotice}\n\n${aiResponse}` : aiResponse;
+			const windowHeight = getWindowHeight(content.length);
 			console.log(`📏 AI response length: ${aiResponse.length} chars → window height: ${windowHeight}px`);
 			
 			// Short delay to show final stage then resize and show response
@@ -572,7 +655,7 @@ function App() {
 				console.log('🔴 RED CIRCLE: About to create AIResult with aiResponse:', aiResponse.substring(0, 50) + '...');
 				const result: AIResult = {
 					id: `result_${Date.now()}`,
-					content: aiResponse,
+					content,
 					type: selectedImageForAI ? 'hybrid' : 'text',
 					confidence: 0.9,
 					timestamp: new Date(),
@@ -627,15 +710,29 @@ function App() {
 		console.log('✅ AI response generated with context:', contextTypes);
 	};
 
+	// Capture URLs (framesense://capture/<id>) read into a data URL; data URLs pass through
+	const loadImageAsDataUrl = async (imageUrl: string): Promise<string> => {
+		if (imageUrl.startsWith('data:')) return imageUrl;
+		const blob = await (await fetch(imageUrl)).blob();
+		return new Promise((resolve, reject) => {
+			const reader = new FileReader();
+			reader.onload = () => resolve(reader.result as string);
+			reader.onerror = () => reject(reader.error);
+			reader.readAsDataURL(blob);
+		});
+	};
+
 	// 🔍 Automatic OCR function - runs silently after screenshot
-	const runAutomaticOCR = async (imageData: string) => {
+	const runAutomaticOCR = async (image: { imageData?: string; captureId?: string }, historyId: Promise<string | null> = Promise.resolve(null)) => {
 		console.log('🔍 Running automatic OCR in background...');
 		
 		try {
-			const ocrResult = await invoke('extract_text_ocr', { imageData }) as OCRResult;
+			const ocrResult = await invoke('extract_text_ocr', image) as OCRResult;
 			setOcrContext(ocrResult);
 			
 			if (ocrResult.has_text) {
+				historyId.then(id => id && invoke('update_history_entry', { id, ocrText: ocrResult.text }))
+					.catch((error) => console.log('⚠️ Could not save OCR text to history:', error));
 				console.log(`✅ OCR completed silently - Found text: "${ocrResult.text.substring(0, 50)}..." (${Math.round(ocrResult.confidence * 100)}% confidence)`);
 			} else {
 				console.log('🔍 OCR completed silently - No text detected');
@@ -1,5 +1,6 @@
 import React, { useState, useRef, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 interface DragOverlayProps {
 	onSelectionComplete: (result: any) => void;
@@ -13,14 +14,99 @@ interface SelectionBox {
 	endY: number;
 }
 
+interface Rect {
+	x: number;
+	y: number;
+	width: number;
+	height: number;
+}
+
+// A window the backend listed for picking (topmost first)
+interface PickableWindow {
+	id: number;
+	title: string;
+	app_name: string;
+	bounds: Rect;
+	overlay_bounds: Rect;
+}
+
+// Progress of a scrolling capture (get_scroll_capture_status)
+interface ScrollStatus {
+	running: boolean;
+	frames: number;
+	skipped: number;
+	width: number;
+	height: number;
+	last_error: string | null;
+}
+
+// Capture timing picked with T (ScheduleMode on the Rust side); null captures right away
+type ScheduleMode =
+	| { mode: 'delayed'; delay_secs: number }
+	| { mode: 'interval'; interval_secs: number; count: number | null }
+	| { mode: 'on_change'; threshold_percent: number };
+
+const SCHEDULE_OPTIONS: { label: string; schedule: ScheduleMode | null }[] = [
+	{ label: 'now', schedule: null },
+	{ label: 'in 3s', schedule: { mode: 'delayed', delay_secs: 3 } },
+	{ label: 'in 5s', schedule: { mode: 'delayed', delay_secs: 5 } },
+	{ label: 'in 10s', schedule: { mode: 'delayed', delay_secs: 10 } },
+	{ label: 'every 5s', schedule: { mode: 'interval', interval_secs: 5, count: null } },
+	{ label: 'on 5% change', schedule: { mode: 'on_change', threshold_percent: 5 } },
+];
+
 const DragOverlay: React.FC<DragOverlayProps> = ({ onSelectionComplete, onCancel }) => {
 	const [isDragging, setIsDragging] = useState(false);
 	const [selectionBox, setSelectionBox] = useState<SelectionBox | null>(null);
+	const [windowMode, setWindowMode] = useState(false);
+	const [windows, setWindows] = useState<PickableWindow[]>([]);
+	const [hoveredWindow, setHoveredWindow] = useState<PickableWindow | null>(null);
+	const [scrollMode, setScrollMode] = useState(false);
+	const [scrollRegion, setScrollRegion] = useState<Rect | null>(null);
+	const [scrollStatus, setScrollStatus] = useState<ScrollStatus | null>(null);
+	const [scheduleIndex, setScheduleIndex] = useState(0);
+	const [pinMode, setPinMode] = useState(false);
+	const [recordMode, setRecordMode] = useState(false);
+	const [notice, setNotice] = useState<string | null>(null);
 	const overlayRef = useRef<HTMLDivElement>(null);
 
+	const toggleWindowMode = useCallback(async () => {
+		if (windowMode) {
+			setWindowMode(false);
+			setHoveredWindow(null);
+			return;
+		}
+		try {
+			const listed = await invoke<PickableWindow[]>('list_capture_windows');
+			console.log(`🪟 ${listed.length} window(s) available for picking`);
+			if (listed.length === 0) return;
+			setWindows(listed);
+			setSelectionBox(null);
+			setScrollMode(false);
+			setPinMode(false);
+			setRecordMode(false);
+			setWindowMode(true);
+		} catch (error) {
+			console.error('❌ Window picking unavailable:', error);
+			// CaptureError arrives as { kind, message }
+			const { kind, message } = error as { kind?: string; message?: string };
+			if (kind === 'unsupported' || kind === 'permission_denied') {
+				setNotice(message ?? null);
+				setTimeout(() => setNotice(null), 4000);
+			}
+		}
+	}, [windowMode]);
+
+	// Topmost window under a point in overlay coordinates
+	const windowAt = useCallback((x: number, y: number) => {
+		return windows.find(({ overlay_bounds: b }) =>
+			x >= b.x && y >= b.y && x < b.x + b.width && y < b.y + b.height) ?? null;
+	}, [windows]);
+
 	const handleMouseDown = useCallback((e: React.MouseEvent) => {
 		e.preventDefault();
 		e.stopPropagation();
+		if (windowMode) return;
 		
 		console.log('🖱️ Mouse down - starting drag');
 		const rect = overlayRef.current?.getBoundingClientRect();
@@ -36,11 +122,17 @@ const DragOverlay: React.FC<DragOverlayProps> = ({ onSelectionComplete, onCancel
 			endX: startX,
 			endY: startY,
 		});
-	}, []);
+	}, [windowMode]);
 
 	const handleMouseMove = useCallback((e: React.MouseEvent) => {
 		e.preventDefault();
 		e.stopPropagation();
+
+		if (windowMode) {
+			const rect = overlayRef.current?.getBoundingClientRect();
+			if (rect) setHoveredWindow(windowAt(e.clientX - rect.left, e.clientY - rect.top));
+			return;
+		}
 		
 		if (!isDragging || !selectionBox) return;
 
@@ -55,7 +147,7 @@ const DragOverlay: React.FC<DragOverlayProps> = ({ onSelectionComplete, onCancel
 			endX,
 			endY,
 		} : null);
-	}, [isDragging, selectionBox]);
+	}, [isDragging, selectionBox, windowMode, windowAt]);
 
 	const handleMouseUp = useCallback(async (e: React.MouseEvent) => {
 		e.preventDefault();
@@ -92,6 +184,50 @@ const DragOverlay: React.FC<DragOverlayProps> = ({ onSelectionComplete, onCancel
 
 			console.log(`🎯 Screen coordinates: ${screenWidth}x${screenHeight} at (${screenX}, ${screenY})`);
 
+			if (scrollMode) {
+				// Keep capturing this region while the user scrolls under the overlay
+				await invoke('start_scroll_capture', {
+					bounds: { x: screenX, y: screenY, width: screenWidth, height: screenHeight }
+				});
+				setSelectionBox(null);
+				setScrollRegion({ x: screenX, y: screenY, width: screenWidth, height: screenHeight });
+				console.log('📜 Scrolling capture started - scroll, then press Enter');
+				return;
+			}
+
+			if (recordMode) {
+				// Stopped from the tray or the main window; the clip arrives as 'recording-finished'
+				await invoke('record_screen_selection', {
+					bounds: { x: screenX, y: screenY, width: screenWidth, height: screenHeight },
+					options: { format: 'gif', fps: 10 },
+				});
+				console.log('🔴 Recording started - stop it from the tray');
+				onSelectionComplete({ success: true });
+				return;
+			}
+
+			if (pinMode) {
+				// Watched in the background; changes arrive as 'region-watch' events
+				const watch = await invoke<{ id: string; name: string }>('watch_screen_selection', {
+					bounds: { x: screenX, y: screenY, width: screenWidth, height: screenHeight },
+				});
+				console.log(`📌 Pinned "${watch.name}" (${watch.id})`);
+				onSelectionComplete({ success: true });
+				return;
+			}
+
+			const schedule = SCHEDULE_OPTIONS[scheduleIndex].schedule;
+			if (schedule) {
+				// Captured later (or repeatedly); results arrive as 'selection-result' events too
+				await invoke('schedule_screen_selection', {
+					bounds: { x: screenX, y: screenY, width: screenWidth, height: screenHeight },
+					mode: schedule,
+				});
+				console.log(`⏱️ Capture scheduled ${SCHEDULE_OPTIONS[scheduleIndex].label}`);
+				onSelectionComplete({ success: true });
+				return;
+			}
+
 			// Call our optimized process_screen_selection command with corrected screen coordinates
 			await invoke('process_screen_selection_optimized', { 
 				bounds: {
@@ -109,33 +245,110 @@ const DragOverlay: React.FC<DragOverlayProps> = ({ onSelectionComplete, onCancel
 			console.error('❌ Drag selection failed:', error);
 			onCancel();
 		}
-	}, [isDragging, selectionBox, onSelectionComplete, onCancel]);
+	}, [isDragging, selectionBox, scrollMode, pinMode, recordMode, scheduleIndex, onSelectionComplete, onCancel]);
 
 	// Handle clicks that aren't part of a drag (to prevent accidental cancellation)
-	const handleClick = useCallback((e: React.MouseEvent) => {
+	const handleClick = useCallback(async (e: React.MouseEvent) => {
 		e.preventDefault();
 		e.stopPropagation();
+
+		if (windowMode) {
+			if (!hoveredWindow) return;
+			console.log(`🪟 Picked window "${hoveredWindow.title}" (${hoveredWindow.app_name})`);
+			try {
+				await invoke('process_window_selection_optimized', { windowId: hoveredWindow.id });
+				onSelectionComplete({ success: true });
+			} catch (error) {
+				console.error('❌ Window capture failed:', error);
+				onCancel();
+			}
+			return;
+		}
 		
 		// Only cancel if this was a simple click (not a drag)
 		if (!isDragging && !selectionBox) {
 			console.log('🖱️ Click without drag - cancelling');
 			onCancel();
 		}
-	}, [isDragging, selectionBox, onCancel]);
+	}, [isDragging, selectionBox, windowMode, hoveredWindow, onSelectionComplete, onCancel]);
+
+	const handleKeyDown = useCallback(async (e: KeyboardEvent) => {
+		// The overlay only has focus until the mouse goes through it; the tray can
+		// finish or cancel too, and either way scroll-capture-finished closes the overlay
+		if (scrollRegion) {
+			if (e.key === 'Enter') {
+				e.preventDefault();
+				await invoke('finish_scroll_capture').catch((error) => {
+					console.error('❌ Scrolling capture failed:', error);
+					onCancel();
+				});
+			} else if (e.key === 'Escape') {
+				e.preventDefault();
+				await invoke('cancel_scroll_capture').catch(() => {});
+			}
+			return;
+		}
 
-	const handleKeyDown = useCallback((e: KeyboardEvent) => {
 		if (e.key === 'Escape') {
 			console.log('⏹️ Escape pressed - cancelling selection');
 			e.preventDefault();
 			onCancel();
+		} else if ((e.key === 'w' || e.key === 'W') && !isDragging) {
+			e.preventDefault();
+			toggleWindowMode();
+		} else if ((e.key === 's' || e.key === 'S') && !isDragging) {
+			e.preventDefault();
+			setWindowMode(false);
+			setHoveredWindow(null);
+			setPinMode(false);
+			setRecordMode(false);
+			setScrollMode(mode => !mode);
+		} else if ((e.key === 'p' || e.key === 'P') && !isDragging) {
+			e.preventDefault();
+			setWindowMode(false);
+			setHoveredWindow(null);
+			setScrollMode(false);
+			setRecordMode(false);
+			setPinMode(mode => !mode);
+		} else if ((e.key === 'r' || e.key === 'R') && !isDragging) {
+			e.preventDefault();
+			setWindowMode(false);
+			setHoveredWindow(null);
+			setScrollMode(false);
+			setPinMode(false);
+			setRecordMode(mode => !mode);
+		} else if ((e.key === 't' || e.key === 'T') && !isDragging) {
+			e.preventDefault();
+			setScheduleIndex(index => (index + 1) % SCHEDULE_OPTIONS.length);
 		}
-	}, [onCancel]);
+	}, [onCancel, onSelectionComplete, isDragging, toggleWindowMode, scrollRegion]);
 
 	React.useEffect(() => {
 		document.addEventListener('keydown', handleKeyDown);
 		return () => document.removeEventListener('keydown', handleKeyDown);
 	}, [handleKeyDown]);
 
+	React.useEffect(() => {
+		if (!scrollRegion) return;
+		const unlisten = listen<{ delivered: boolean }>('scroll-capture-finished', (event) => {
+			if (event.payload.delivered) {
+				onSelectionComplete({ success: true });
+			} else {
+				onCancel();
+			}
+		});
+		return () => { unlisten.then(fn => fn()); };
+	}, [scrollRegion, onSelectionComplete, onCancel]);
+
+	// Show how far the scrolling capture has got
+	React.useEffect(() => {
+		if (!scrollRegion) return;
+		const timer = setInterval(async () => {
+			setScrollStatus(await invoke<ScrollStatus | null>('get_scroll_capture_status'));
+		}, 500);
+		return () => clearInterval(timer);
+	}, [scrollRegion]);
+
 	const getSelectionStyle = () => {
 		if (!selectionBox) return {};
 
@@ -167,22 +380,71 @@ const DragOverlay: React.FC<DragOverlayProps> = ({ onSelectionComplete, onCancel
 			onClick={handleClick}
 		>
 			{/* Instructions */}
-			<div className="absolute top-4 left-1/2 transform -translate-x-1/2 bg-black bg-opacity-80 text-white px-4 py-2 rounded-lg text-sm pointer-events-none">
-				🖱️ Drag to select area • ⏹️ ESC to cancel
-			</div>
+			{!scrollRegion && (
+				<div className="absolute top-4 left-1/2 transform -translate-x-1/2 bg-black bg-opacity-80 text-white px-4 py-2 rounded-lg text-sm pointer-events-none">
+					{notice
+						? `⚠️ ${notice}`
+						: windowMode
+							? '🪟 Click a window to capture it • W for area • ⏹️ ESC to cancel'
+							: scrollMode
+								? '📜 Drag over the content to scroll through • S for area • ⏹️ ESC to cancel'
+								: pinMode
+									? '📌 Drag over a region to watch its text • P for area • ⏹️ ESC to cancel'
+									: recordMode
+										? '🔴 Drag over the region to record • R for area • ⏹️ ESC to cancel'
+										: `🖱️ Drag to select area (capture ${SCHEDULE_OPTIONS[scheduleIndex].label}, T to change) • W for window • S for scrolling • P to pin • R to record • ⏹️ ESC to cancel`}
+				</div>
+			)}
+
+			{/* Region being captured while scrolling; drawn outside it so it isn't captured */}
+			{scrollRegion && (
+				<div
+					className="absolute border-2 border-dashed border-blue-500 pointer-events-none"
+					style={{
+						left: scrollRegion.x - 3,
+						top: scrollRegion.y - 3,
+						width: scrollRegion.width + 6,
+						height: scrollRegion.height + 6,
+					}}
+				>
+					<div className="absolute -bottom-9 left-0 whitespace-nowrap bg-black bg-opacity-80 text-white px-2 py-1 rounded text-xs">
+						📜 Scroll the content • Enter or tray menu to finish • ESC or tray menu to cancel
+						{scrollStatus && ` • ${scrollStatus.frames} frames, ${scrollStatus.height}px`}
+					</div>
+				</div>
+			)}
 
 			{/* Close button */}
-			<button
-				onClick={(e) => {
-					e.preventDefault();
-					e.stopPropagation();
-					onCancel();
-				}}
-				className="absolute top-4 right-4 bg-red-500 hover:bg-red-600 text-white w-8 h-8 rounded-full flex items-center justify-center font-bold text-lg transition-colors"
-				style={{ pointerEvents: 'auto' }}
-			>
-				×
-			</button>
+			{!scrollRegion && (
+				<button
+					onClick={(e) => {
+						e.preventDefault();
+						e.stopPropagation();
+						onCancel();
+					}}
+					className="absolute top-4 right-4 bg-red-500 hover:bg-red-600 text-white w-8 h-8 rounded-full flex items-center justify-center font-bold text-lg transition-colors"
+					style={{ pointerEvents: 'auto' }}
+				>
+					×
+				</button>
+			)}
+
+			{/* Window under the cursor */}
+			{windowMode && hoveredWindow && (
+				<div
+					className="absolute border-2 border-blue-500 bg-blue-200 bg-opacity-20 pointer-events-none"
+					style={{
+						left: hoveredWindow.overlay_bounds.x,
+						top: hoveredWindow.overlay_bounds.y,
+						width: hoveredWindow.overlay_bounds.width,
+						height: hoveredWindow.overlay_bounds.height,
+					}}
+				>
+					<div className="absolute top-1 left-1 max-w-full truncate bg-black bg-opacity-80 text-white px-2 py-1 rounded text-xs">
+						{hoveredWindow.title || hoveredWindow.app_name} • {hoveredWindow.bounds.width} × {hoveredWindow.bounds.height}
+					</div>
+				</div>
+			)}
 
 			{/* Selection box */}
 			{selectionBox && (
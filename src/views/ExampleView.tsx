@@ -36,9 +36,13 @@ export default function ExampleView() {
 			await fs.writeTextFile('example_file.txt', 'oh this is from TAURI! COOLIO.\n', { baseDir: fs.BaseDirectory.Download });
 			// show in file explorer: https://github.com/tauri-apps/tauri/issues/4062
 			await shell.open(downloads!);
-			await invoke('process_file', { filepath: filePath }).then(msg => {
-				notify('Message from Rust', msg as string);
-				notifications.show({ title: 'Message from Rust', message: msg as string });
+			// only images and PDFs can be analyzed, so Rust explains why a text file is refused
+			await invoke<{ name: string, content: { ai_analysis: string | null } }>('process_file', { filepath: filePath }).then(result => {
+				const msg = `${result.name}: ${result.content.ai_analysis ?? 'processed'}`;
+				notify('Message from Rust', msg);
+				notifications.show({ title: 'Message from Rust', message: msg });
+			}).catch(error => {
+				notifications.show({ title: 'Message from Rust', message: String(error), color: 'red' });
 			});
 		}
 	}